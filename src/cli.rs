@@ -0,0 +1,107 @@
+use crate::domain::{DEFAULT_WORKSPACE, SortMode, validate_workspace_name};
+use crate::shell::{DEFAULT_FUNCTION_NAME, Shell, validate_function_name};
+use crate::tui::MAX_NUM_SESSIONS;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+/// atls is a minimalistic file explorer for the command line
+#[derive(Parser, Debug)]
+#[command(about, version, args_conflicts_with_subcommands = true)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<AtlsCommand>,
+    /// Paths to start in; each directory opens a session, files are opened
+    /// in a session at their parent directory with them highlighted
+    #[arg(value_name = "PATH", num_args = 0..=MAX_NUM_SESSIONS)]
+    pub paths: Vec<PathBuf>,
+    /// File to highlight on startup
+    #[arg(long = "select", value_name = "FILE")]
+    pub select: Option<PathBuf>,
+    /// Show hidden files
+    #[arg(long = "hidden", short = 'a')]
+    pub show_hidden: bool,
+    /// How to sort entries
+    #[arg(long = "sort", short = 's', value_name = "MODE")]
+    pub sort_mode: Option<SortMode>,
+    /// Disallow operations that modify the file system
+    #[arg(long = "read-only")]
+    pub read_only: bool,
+    /// Path to the config file to use instead of the default one
+    #[arg(long = "config", short = 'c', value_name = "PATH")]
+    pub config: Option<PathBuf>,
+    /// Restore the session state saved when atls last exited
+    #[arg(long = "restore", short = 'r', conflicts_with_all = ["paths", "select"])]
+    pub restore: bool,
+    /// Workspace to save session state to on exit (and restore it from)
+    #[arg(
+        long = "workspace",
+        short = 'w',
+        value_name = "NAME",
+        default_value = DEFAULT_WORKSPACE,
+        value_parser = validate_workspace_name,
+    )]
+    pub workspace: String,
+    /// Save marked paths along with the rest of the session state
+    #[arg(long = "save-marked")]
+    pub save_marked: bool,
+    /// Write the directory atls was in when it exited to this file
+    #[arg(long = "cwd-file", value_name = "PATH")]
+    pub cwd_file: Option<PathBuf>,
+    /// Print the directory atls was in when it exited to stdout
+    #[arg(long = "print-cwd")]
+    pub print_cwd: bool,
+    /// Run as a file chooser; on <enter>, write marked files (or the file under cursor) to this
+    /// file ("-" for stdout) and exit
+    #[arg(
+        long = "choose-files",
+        value_name = "PATH",
+        conflicts_with = "choose_dir"
+    )]
+    pub choose_files: Option<PathBuf>,
+    /// Run as a directory chooser; on <enter>, write marked directories (or the directory under
+    /// cursor, or the current one) to this file ("-" for stdout) and exit
+    #[arg(long = "choose-dir", value_name = "PATH")]
+    pub choose_dir: Option<PathBuf>,
+    /// Separate chosen paths with NUL instead of newline characters
+    #[arg(long = "print0", short = '0')]
+    pub print0: bool,
+    /// Leave the mouse to the terminal, eg. for selecting text, instead of handling clicks
+    #[arg(long = "no-mouse")]
+    pub no_mouse: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AtlsCommand {
+    /// Print a shell function that changes the shell's working directory to wherever atls exited in
+    ///
+    /// Add `eval "$(atls init bash)"` to ~/.bashrc, `eval "$(atls init zsh)"` to ~/.zshrc, or
+    /// `atls init fish | source` to ~/.config/fish/config.fish, and use `atlscd` instead of `atls`.
+    Init {
+        /// Shell to print the function for
+        #[arg(value_name = "SHELL")]
+        shell: Shell,
+        /// Name of the function
+        #[arg(
+            long = "cmd",
+            value_name = "NAME",
+            default_value = DEFAULT_FUNCTION_NAME,
+            value_parser = validate_function_name,
+        )]
+        function_name: String,
+    },
+    /// Work with the config file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Check that the config file is valid, reporting any errors in it
+    Check {
+        /// Config file to check instead of the default one
+        #[arg(value_name = "PATH")]
+        path: Option<PathBuf>,
+    },
+}
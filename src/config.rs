@@ -0,0 +1,335 @@
+use crate::dirs::get_config_dir;
+use crate::domain::{CustomCommand, Opener, SortMode};
+use crate::tui::{DEFAULT_THEME, Keymap, KeysConfig, PaneLayout, Theme, ThemeSpec};
+use anyhow::Context;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub show_hidden: bool,
+    pub sort: SortMode,
+    pub read_only: bool,
+    pub openers: Vec<Opener>,
+    pub commands: Vec<CustomCommand>,
+    pub preview: bool,
+    pub layout: PaneLayout,
+    pub theme: Option<String>,
+    pub themes: BTreeMap<String, ThemeSpec>,
+    pub colors: ThemeSpec,
+    pub ls_colors: bool,
+    // whether sizes of directories are computed on entering directories, rather than on demand
+    pub auto_dir_sizes: bool,
+    // leaves the mouse to the terminal, eg. for selecting text
+    pub disable_mouse: bool,
+    pub keys: KeysConfig,
+}
+
+impl Config {
+    /// Builds the keymap from the default bindings, the ones overridden in the config, and the
+    /// ones for custom commands.
+    pub fn keymap(&self) -> anyhow::Result<Keymap> {
+        Keymap::new(&self.keys, &self.commands).map_err(anyhow::Error::msg)
+    }
+
+    /// Resolves the selected theme, with colours overridden in the config applied on top.
+    pub fn theme(&self) -> anyhow::Result<Theme> {
+        let name = self.theme.as_deref().unwrap_or(DEFAULT_THEME);
+
+        Theme::resolve(name, &self.themes, &self.colors).map_err(anyhow::Error::msg)
+    }
+}
+
+/// Loads the config file at `path` if provided, or the one at the default location if it exists.
+pub fn load_config(path: Option<&Path>) -> anyhow::Result<Config> {
+    let path = match path {
+        Some(p) => p.to_path_buf(),
+        None => {
+            let default_path = get_default_config_path()?;
+            if !default_path.exists() {
+                debug!("no config file found at {:?}", &default_path);
+                return Ok(Config::default());
+            }
+
+            default_path
+        }
+    };
+
+    debug!("loading config from {:?}", &path);
+    let contents = std::fs::read_to_string(&path)
+        .with_context(|| format!("couldn't read config file {}", path.to_string_lossy()))?;
+
+    parse_config(&contents)
+        .with_context(|| format!("config file {} is invalid", path.to_string_lossy()))
+}
+
+pub fn get_default_config_path() -> anyhow::Result<PathBuf> {
+    let config_dir = get_config_dir().context("couldn't determine config directory")?;
+
+    Ok(config_dir.join(CONFIG_FILE_NAME))
+}
+
+fn parse_config(contents: &str) -> anyhow::Result<Config> {
+    let config: Config = toml::from_str(contents)?;
+    // conflicting key bindings and unknown themes can only be detected once these are resolved
+    config.keymap()?;
+    config.theme()?;
+
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tui::{Action, Lookup, Pane};
+    use insta::assert_snapshot;
+    use ratatui::style::Color;
+
+    #[test]
+    fn parsing_a_valid_config_works() {
+        // GIVEN
+        let contents = r#"
+show_hidden = true
+sort = "modified"
+read_only = true
+"#;
+
+        // WHEN
+        let config = parse_config(contents).expect("config should've been parsed");
+
+        // THEN
+        assert!(config.show_hidden);
+        assert_eq!(config.sort, SortMode::Modified);
+        assert!(config.read_only);
+    }
+
+    #[test]
+    fn parsing_a_config_with_openers_works() {
+        // GIVEN
+        let contents = r#"
+[[openers]]
+command = "zathura"
+globs = ["*.pdf"]
+blocking = false
+
+[[openers]]
+name = "image viewer"
+command = "imv"
+mime = ["image/*"]
+"#;
+
+        // WHEN
+        let config = parse_config(contents).expect("config should've been parsed");
+
+        // THEN
+        let openers = config
+            .openers
+            .iter()
+            .map(|o| (o.name.as_str(), o.command.as_str(), o.blocking))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            openers,
+            vec![("zathura", "zathura", false), ("image viewer", "imv", true)]
+        );
+    }
+
+    #[test]
+    fn parsing_a_config_with_custom_commands_works() {
+        // GIVEN
+        let contents = r#"
+[[commands]]
+name = "format"
+key = "F"
+command = "prettier --write %m"
+confirm = true
+
+[[commands]]
+name = "thumbnails"
+key = "T"
+command = "./scripts/thumbnails.sh %f"
+capture_output = false
+refresh = false
+"#;
+
+        // WHEN
+        let config = parse_config(contents).expect("config should've been parsed");
+
+        // THEN
+        let commands = config
+            .commands
+            .iter()
+            .map(|c| (c.key.as_str(), c.confirm, c.capture_output, c.refresh))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            commands,
+            vec![("F", true, true, true), ("T", false, false, false)]
+        );
+    }
+
+    #[test]
+    fn parsing_a_config_with_an_invalid_command_key_fails() {
+        // GIVEN
+        let contents = r#"
+[[commands]]
+name = "format"
+key = "<ctrl-nope>"
+command = "prettier --write %m"
+"#;
+
+        // WHEN
+        let result = parse_config(contents);
+
+        // THEN
+        let error = result
+            .expect_err("config should've been rejected")
+            .to_string();
+        assert_snapshot!(error, @r#"invalid key "<ctrl-nope>" for command "format" in the explorer pane: unknown key "<ctrl-nope>""#);
+    }
+
+    #[test]
+    fn parsing_a_config_with_remapped_keys_works() {
+        // GIVEN
+        let contents = r#"
+[keys.explorer]
+select_first = ["gg"]
+mark = ["<space>", "m"]
+go_to_session_1 = []
+
+[[commands]]
+name = "delete"
+key = "dd"
+command = "rm %f"
+"#;
+
+        // WHEN
+        let config = parse_config(contents).expect("config should've been parsed");
+
+        // THEN
+        let keymap = config.keymap().expect("keymap should've been built");
+        let lookup = |keys| keymap.lookup_str(Pane::Explorer, keys);
+        assert_eq!(lookup("gg"), Lookup::Matched(Action::SelectFirst));
+        assert_eq!(lookup("m"), Lookup::Matched(Action::Mark));
+        assert_eq!(lookup("<space>"), Lookup::Matched(Action::Mark));
+        assert_eq!(lookup("dd"), Lookup::Matched(Action::RunCustomCommand(0)));
+        // the default keys for remapped actions are freed up
+        assert_eq!(lookup("g"), Lookup::Pending);
+        assert_eq!(lookup("1"), Lookup::Unmatched);
+    }
+
+    #[test]
+    fn parsing_a_config_with_layout_and_colors_works() {
+        // GIVEN
+        let contents = r##"
+preview = true
+layout = "vertical"
+disable_mouse = true
+
+[colors]
+directory = "#ff8700"
+file = "bright-white"
+"##;
+
+        // WHEN
+        let config = parse_config(contents).expect("config should've been parsed");
+
+        // THEN
+        let theme = config.theme().expect("theme should've been resolved");
+        assert!(config.preview);
+        assert!(config.disable_mouse);
+        assert_eq!(config.layout, PaneLayout::Vertical);
+        assert_eq!(theme.directory, Color::Rgb(0xff, 0x87, 0x00));
+        assert_eq!(theme.file, Color::White);
+        assert_eq!(theme.symlink, Theme::default().symlink);
+    }
+
+    #[test]
+    fn parsing_a_config_with_a_user_theme_works() {
+        // GIVEN
+        let contents = r##"
+theme = "solarized"
+
+[themes.solarized]
+base = "light"
+directory = "#268bd2"
+
+[colors]
+file = "black"
+"##;
+
+        // WHEN
+        let config = parse_config(contents).expect("config should've been parsed");
+
+        // THEN
+        let theme = config.theme().expect("theme should've been resolved");
+        let light = Theme::resolve("light", &BTreeMap::new(), &ThemeSpec::default())
+            .expect("built-in theme should've been resolved");
+        assert_eq!(theme.directory, Color::Rgb(0x26, 0x8b, 0xd2));
+        assert_eq!(theme.file, Color::Black);
+        assert_eq!(theme.primary, light.primary);
+    }
+
+    #[test]
+    fn parsing_a_config_with_an_unknown_theme_fails() {
+        // GIVEN
+        let contents = r#"
+theme = "solarised"
+
+[themes.solarized]
+base = "light"
+"#;
+
+        // WHEN
+        let result = parse_config(contents);
+
+        // THEN
+        let error = result
+            .expect_err("config should've been rejected")
+            .to_string();
+        assert_snapshot!(error, @r#"unknown theme "solarised"; available themes: dark, light, high-contrast, solarized"#);
+    }
+
+    #[test]
+    fn parsing_a_config_with_conflicting_command_keys_fails() {
+        // GIVEN
+        let contents = r#"
+[[commands]]
+name = "format"
+key = "F"
+command = "prettier --write %m"
+
+[[commands]]
+name = "find"
+key = "F"
+command = "fd %d"
+"#;
+
+        // WHEN
+        let result = parse_config(contents);
+
+        // THEN
+        let error = result
+            .expect_err("config should've been rejected")
+            .to_string();
+        assert_snapshot!(error, @r#""F" is bound to both command "format" and command "find" in the explorer pane"#);
+    }
+
+    #[test]
+    fn parsing_a_config_with_unknown_keys_fails() {
+        // GIVEN
+        let contents = r#"
+show_hidden = true
+colour = "blue"
+"#;
+
+        // WHEN
+        let result = parse_config(contents);
+
+        // THEN
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,35 @@
+use etcetera::{BaseStrategy, choose_base_strategy};
+use std::path::PathBuf;
+
+#[cfg(not(target_os = "windows"))]
+pub fn get_state_dir() -> anyhow::Result<PathBuf> {
+    let strategy = choose_base_strategy()?;
+
+    // XDG spec suggests using XDG_STATE_HOME for logs and other state
+    // https://specifications.freedesktop.org/basedir-spec/latest/#variables
+    let state_dir = strategy
+        .state_dir() // this always returns Some on unix, but adding a fallback regardless
+        .map(|d| d.join("atls"))
+        .unwrap_or_else(|| strategy.home_dir().join(".atls"));
+
+    Ok(state_dir)
+}
+
+#[cfg(target_os = "windows")]
+pub fn get_state_dir() -> anyhow::Result<PathBuf> {
+    let strategy = choose_base_strategy()?;
+
+    let state_dir = strategy.cache_dir().join("atls");
+
+    Ok(state_dir)
+}
+
+pub fn get_home_dir() -> Option<PathBuf> {
+    etcetera::home_dir().ok()
+}
+
+pub fn get_config_dir() -> anyhow::Result<PathBuf> {
+    let strategy = choose_base_strategy()?;
+
+    Ok(strategy.config_dir().join("atls"))
+}
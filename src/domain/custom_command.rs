@@ -0,0 +1,66 @@
+use serde::Deserialize;
+
+/// A user defined command, as written in the config file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomCommandSpec {
+    pub name: String,
+    pub key: String,
+    pub command: String,
+    #[serde(default)]
+    pub confirm: bool,
+    #[serde(default = "default_true")]
+    pub capture_output: bool,
+    #[serde(default = "default_true")]
+    pub refresh: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// A user defined command bound to a key sequence.
+///
+/// The command is a template that supports the same placeholders as shell commands run from the
+/// prompt (see [`super::PlaceholderValues`]).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "CustomCommandSpec")]
+pub struct CustomCommand {
+    pub name: String,
+    pub key: String,
+    pub command: String,
+    // ask for confirmation before running
+    pub confirm: bool,
+    // show output in a pane, rather than handing the terminal over to the command
+    pub capture_output: bool,
+    // re-read session directories once the command finishes
+    pub refresh: bool,
+}
+
+impl TryFrom<CustomCommandSpec> for CustomCommand {
+    type Error = String;
+
+    fn try_from(spec: CustomCommandSpec) -> Result<Self, Self::Error> {
+        if spec.name.trim().is_empty() {
+            return Err("custom command name cannot be empty".to_string());
+        }
+
+        if spec.command.trim().is_empty() {
+            return Err(format!(r#"command for "{}" cannot be empty"#, spec.name));
+        }
+
+        // the key itself is parsed along with the rest of the keymap
+        if spec.key.is_empty() {
+            return Err(format!(r#"key for "{}" cannot be empty"#, spec.name));
+        }
+
+        Ok(Self {
+            name: spec.name,
+            key: spec.key,
+            command: spec.command,
+            confirm: spec.confirm,
+            capture_output: spec.capture_output,
+            refresh: spec.refresh,
+        })
+    }
+}
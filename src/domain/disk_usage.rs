@@ -0,0 +1,124 @@
+use super::Entry;
+use std::path::Path;
+
+/// An entry along with the total size of everything under it, as scanned for the disk usage view.
+#[derive(Debug, Clone)]
+pub struct UsageNode {
+    pub entry: Entry,
+    pub size: u64,
+    // largest first
+    pub children: Vec<UsageNode>,
+}
+
+impl UsageNode {
+    pub fn new(entry: Entry, size: u64, mut children: Vec<UsageNode>) -> Self {
+        sort_by_size(&mut children);
+
+        Self {
+            entry,
+            size,
+            children,
+        }
+    }
+
+    /// Returns the node at `path`, which is either this node or one under it.
+    pub fn find(&self, path: &Path) -> Option<&UsageNode> {
+        let mut node = self;
+        while node.entry.path() != path {
+            node = node
+                .children
+                .iter()
+                .find(|child| path.starts_with(child.entry.path()))?;
+        }
+
+        Some(node)
+    }
+
+    /// Removes the node at `path` from under this node, subtracting its size from the nodes
+    /// above it; returns whether it was found.
+    pub fn remove(&mut self, path: &Path) -> bool {
+        let Some(index) = self
+            .children
+            .iter()
+            .position(|child| path.starts_with(child.entry.path()))
+        else {
+            return false;
+        };
+
+        let removed_size = if self.children[index].entry.path() == path {
+            self.children.remove(index).size
+        } else {
+            let child = &mut self.children[index];
+            let size_before = child.size;
+            if !child.remove(path) {
+                return false;
+            }
+            let removed_size = size_before - child.size;
+            // sizes have changed, so the order might have as well
+            sort_by_size(&mut self.children);
+            removed_size
+        };
+
+        self.size = self.size.saturating_sub(removed_size);
+        true
+    }
+}
+
+fn sort_by_size(nodes: &mut [UsageNode]) {
+    nodes.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.entry.cmp(&b.entry)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::EntryKind;
+    use std::path::PathBuf;
+
+    fn file(path: &str, size: u64) -> UsageNode {
+        UsageNode::new(
+            Entry::new(PathBuf::from(path), EntryKind::File),
+            size,
+            vec![],
+        )
+    }
+
+    fn dir(path: &str, children: Vec<UsageNode>) -> UsageNode {
+        let size = children.iter().map(|c| c.size).sum();
+        UsageNode::new(
+            Entry::new(PathBuf::from(path), EntryKind::Directory),
+            size,
+            children,
+        )
+    }
+
+    #[test]
+    fn removing_a_node_updates_sizes_of_the_ones_above_it() {
+        // GIVEN
+        let mut root = dir(
+            "/r",
+            vec![
+                dir("/r/a", vec![file("/r/a/big", 300), file("/r/a/small", 10)]),
+                file("/r/b", 200),
+            ],
+        );
+
+        // WHEN
+        let removed = root.remove(Path::new("/r/a/big"));
+        let removed_again = root.remove(Path::new("/r/a/big"));
+
+        // THEN
+        assert!(removed);
+        assert!(!removed_again);
+        assert_eq!(root.size, 210);
+        let sizes = root
+            .children
+            .iter()
+            .map(|c| (c.entry.path_str(), c.size))
+            .collect::<Vec<_>>();
+        assert_eq!(sizes, [("b".to_string(), 200), ("a/".to_string(), 10)]);
+        assert_eq!(
+            root.find(Path::new("/r/a")).map(|n| n.children.len()),
+            Some(1)
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// A program to be run outside of atls, eg. an editor or a pager.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExternalCommand {
+    pub program: String,
+    pub args: Vec<OsString>,
+    pub cwd: Option<PathBuf>,
+}
+
+/// Output of a command run with its output captured.
+#[derive(Clone, Debug)]
+pub struct CommandOutput {
+    pub success: bool,
+    pub status: String,
+    // stdout, followed by stderr
+    pub output: String,
+}
+
+impl ExternalCommand {
+    /// Builds a command that runs `command_line` via the system shell.
+    pub fn shell(command_line: &str, cwd: Option<PathBuf>) -> Self {
+        #[cfg(not(target_os = "windows"))]
+        let (program, flag) = ("sh", "-c");
+        #[cfg(target_os = "windows")]
+        let (program, flag) = ("cmd", "/C");
+
+        Self {
+            program: program.to_string(),
+            args: vec![OsString::from(flag), OsString::from(command_line)],
+            cwd,
+        }
+    }
+
+    /// Builds a command out of a command line like "code --wait", with `path` as its last argument.
+    /// The command line is split into words the way a POSIX shell would, so that quoted arguments
+    /// (eg. `EDITOR='vim -c "set nowrap"'`) stay intact.
+    ///
+    /// Returns None if the command line is empty, or has unterminated quotes.
+    pub fn for_path(command_line: &str, path: &Path) -> Option<Self> {
+        let mut parts = split_command_line(command_line)?.into_iter();
+        let program = parts.next()?;
+
+        let mut args = parts.map(OsString::from).collect::<Vec<_>>();
+        args.push(path.as_os_str().to_os_string());
+
+        Some(Self {
+            program,
+            args,
+            cwd: path.parent().map(|p| p.to_path_buf()),
+        })
+    }
+}
+
+fn split_command_line(command_line: &str) -> Option<Vec<String>> {
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut chars = command_line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if let Some(w) = word.take() {
+                    words.push(w);
+                }
+            }
+            '\'' => {
+                let w = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        c => w.push(c),
+                    }
+                }
+            }
+            '"' => {
+                let w = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => match chars.next()? {
+                            c @ ('"' | '\\' | '$' | '`') => w.push(c),
+                            c => {
+                                w.push('\\');
+                                w.push(c);
+                            }
+                        },
+                        c => w.push(c),
+                    }
+                }
+            }
+            '\\' => {
+                let escaped = chars.next()?;
+                word.get_or_insert_with(String::new).push(escaped);
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    words.extend(word);
+
+    Some(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn building_a_command_for_a_path_works() {
+        // GIVEN
+        let path = Path::new("/home/user/atls/src/main.rs");
+
+        // WHEN
+        let command = ExternalCommand::for_path(" code  --wait ", path);
+
+        // THEN
+        assert_eq!(
+            command,
+            Some(ExternalCommand {
+                program: "code".to_string(),
+                args: vec![
+                    OsString::from("--wait"),
+                    OsString::from("/home/user/atls/src/main.rs")
+                ],
+                cwd: Some(PathBuf::from("/home/user/atls/src")),
+            })
+        );
+    }
+
+    #[test]
+    fn command_lines_are_split_like_a_shell_would() {
+        // GIVEN
+        let path = Path::new("/tmp/file.txt");
+
+        // WHEN
+        let command = ExternalCommand::for_path(
+            r#"'/opt/my editor/bin/ed' -c "set \"nowrap\"" it\'s """#,
+            path,
+        );
+
+        // THEN
+        assert_eq!(
+            command,
+            Some(ExternalCommand {
+                program: "/opt/my editor/bin/ed".to_string(),
+                args: vec![
+                    OsString::from("-c"),
+                    OsString::from(r#"set "nowrap""#),
+                    OsString::from("it's"),
+                    OsString::from(""),
+                    OsString::from("/tmp/file.txt"),
+                ],
+                cwd: Some(PathBuf::from("/tmp")),
+            })
+        );
+    }
+
+    #[test]
+    fn building_a_command_from_a_command_line_with_unterminated_quotes_fails() {
+        // GIVEN
+        // WHEN
+        let command = ExternalCommand::for_path("vim 'file", Path::new("/tmp/file.txt"));
+
+        // THEN
+        assert!(command.is_none());
+    }
+
+    #[test]
+    fn building_a_command_from_an_empty_command_line_fails() {
+        // GIVEN
+        // WHEN
+        let command = ExternalCommand::for_path("  ", Path::new("/tmp/file.txt"));
+
+        // THEN
+        assert!(command.is_none());
+    }
+}
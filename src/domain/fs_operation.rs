@@ -11,4 +11,10 @@ pub enum FSOperation {
         items: Vec<Entry>,
         destination: PathBuf,
     },
+    Trash {
+        items: Vec<Entry>,
+    },
+    Delete {
+        items: Vec<Entry>,
+    },
 }
@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// The status of an entry in a git repository, as shown in listings.
+///
+/// Variants are ordered by precedence; a directory takes on the status of its most important
+/// child.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GitFileStatus {
+    Ignored,
+    Untracked,
+    Staged,
+    Modified,
+    Conflicted,
+}
+
+impl GitFileStatus {
+    pub fn indicator(&self) -> char {
+        match self {
+            GitFileStatus::Ignored => '!',
+            GitFileStatus::Untracked => '?',
+            GitFileStatus::Staged => '+',
+            GitFileStatus::Modified => 'M',
+            GitFileStatus::Conflicted => 'U',
+        }
+    }
+}
+
+/// Something that can be done to changes in a git repository from the explorer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitAction {
+    Stage,
+    Unstage,
+    Discard,
+}
+
+/// The state of the git repository a directory is in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitStatus {
+    // None if HEAD is detached
+    pub branch: Option<String>,
+    // commits ahead of, and behind the upstream branch, if there's one
+    pub ahead_behind: Option<(u64, u64)>,
+    // statuses of changed entries, and of the directories they're in, by absolute path
+    statuses: HashMap<PathBuf, GitFileStatus>,
+    // untracked and ignored directories git lists as a whole, rather than entry by entry
+    whole_dirs: HashMap<PathBuf, GitFileStatus>,
+}
+
+impl GitStatus {
+    /// Parses the output of `git status --porcelain=v2 --branch -z`, whose paths are relative to
+    /// `root`.
+    pub fn parse(root: &Path, output: &[u8]) -> Self {
+        let mut status = Self::default();
+        let mut records = output.split(|b| *b == 0);
+
+        while let Some(record) = records.next() {
+            let mut fields = record.split(|b| *b == b' ');
+            let (file_status, num_fields) = match fields.next() {
+                Some(b"#") => {
+                    status.parse_header(record);
+                    continue;
+                }
+                Some(b"1") => (get_change_status(fields.next()), 9),
+                Some(b"2") => {
+                    // renames and copies are followed by the original path, as a record of its own
+                    records.next();
+                    (get_change_status(fields.next()), 10)
+                }
+                Some(b"u") => (Some(GitFileStatus::Conflicted), 11),
+                Some(b"?") => (Some(GitFileStatus::Untracked), 2),
+                Some(b"!") => (Some(GitFileStatus::Ignored), 2),
+                _ => continue,
+            };
+
+            // paths can contain spaces, so everything after the fixed fields is the path
+            let Some(path) = record
+                .splitn(num_fields, |b| *b == b' ')
+                .nth(num_fields - 1)
+            else {
+                continue;
+            };
+            if let Some(file_status) = file_status {
+                let dir_path = path.strip_suffix(b"/");
+                let path = root.join(path_from_bytes(dir_path.unwrap_or(path)));
+                if dir_path.is_some() {
+                    status.whole_dirs.insert(path.clone(), file_status);
+                }
+                status.insert(root, path, file_status);
+            }
+        }
+
+        status
+    }
+
+    fn parse_header(&mut self, record: &[u8]) {
+        let record = String::from_utf8_lossy(record);
+        if let Some(head) = record.strip_prefix("# branch.head ") {
+            self.branch = (head != "(detached)").then(|| head.to_string());
+        } else if let Some(ab) = record.strip_prefix("# branch.ab ") {
+            let mut counts = ab
+                .split(' ')
+                .map(|c| c.trim_start_matches(['+', '-']).parse::<u64>().ok());
+            if let (Some(Some(ahead)), Some(Some(behind))) = (counts.next(), counts.next()) {
+                self.ahead_behind = Some((ahead, behind));
+            }
+        }
+    }
+
+    // records the entry's status, and aggregates it into the directories above it
+    fn insert(&mut self, root: &Path, path: PathBuf, file_status: GitFileStatus) {
+        // ignored files don't make the directories they're in ignored
+        if file_status != GitFileStatus::Ignored {
+            for dir in path
+                .ancestors()
+                .skip(1)
+                .take_while(|dir| dir.starts_with(root))
+            {
+                let dir_status = self
+                    .statuses
+                    .entry(dir.to_path_buf())
+                    .or_insert(file_status);
+                *dir_status = (*dir_status).max(file_status);
+            }
+        }
+
+        let entry_status = self.statuses.entry(path).or_insert(file_status);
+        *entry_status = (*entry_status).max(file_status);
+    }
+
+    /// Returns the status of the entry at `path`; entries in untracked or ignored directories take
+    /// on their directory's status.
+    pub fn status_of(&self, path: &Path) -> Option<GitFileStatus> {
+        if let Some(status) = self.statuses.get(path) {
+            return Some(*status);
+        }
+
+        path.ancestors()
+            .skip(1)
+            .find_map(|dir| self.whole_dirs.get(dir))
+            .copied()
+    }
+}
+
+// the XY field of a change, where X is the status in the index, and Y the one in the work tree
+fn get_change_status(xy: Option<&[u8]>) -> Option<GitFileStatus> {
+    match xy? {
+        [_, y] if *y != b'.' => Some(GitFileStatus::Modified),
+        [x, _] if *x != b'.' => Some(GitFileStatus::Staged),
+        _ => None,
+    }
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> &Path {
+    use std::os::unix::ffi::OsStrExt;
+
+    Path::new(OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(OsStr::new(&*String::from_utf8_lossy(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_yaml_snapshot;
+
+    #[test]
+    fn porcelain_output_is_parsed_into_statuses_aggregated_by_directory() {
+        // GIVEN
+        let root = Path::new("/repo");
+        let output = [
+            "# branch.oid 1234567890abcdef",
+            "# branch.head main",
+            "# branch.upstream origin/main",
+            "# branch.ab +2 -1",
+            "1 .M N... 100644 100644 100644 abc abc src/main.rs",
+            "1 M. N... 100644 100644 100644 abc def src/tui/view.rs",
+            "2 R. N... 100644 100644 100644 abc abc R100 docs/new name.md",
+            "docs/old name.md",
+            "u UU N... 100644 100644 100644 100644 abc def ghi src/tui/model.rs",
+            "? notes/",
+            "? lib/new.rs",
+            "! target/",
+            "",
+        ]
+        .join("\0");
+
+        // WHEN
+        let status = GitStatus::parse(root, output.as_bytes());
+
+        // THEN
+        let paths = [
+            "/repo/src",
+            "/repo/src/main.rs",
+            "/repo/src/tui",
+            "/repo/src/tui/view.rs",
+            "/repo/docs",
+            "/repo/docs/new name.md",
+            "/repo/notes",
+            "/repo/notes/todo.md",
+            "/repo/lib",
+            "/repo/lib/old.rs",
+            "/repo/target/debug",
+            "/repo/README.md",
+        ];
+        let statuses = paths
+            .map(|p| format!("{p}: {:?}", status.status_of(Path::new(p))))
+            .to_vec();
+        assert_eq!(status.branch.as_deref(), Some("main"));
+        assert_eq!(status.ahead_behind, Some((2, 1)));
+        assert_yaml_snapshot!(statuses, @r#"
+        - "/repo/src: Some(Conflicted)"
+        - "/repo/src/main.rs: Some(Modified)"
+        - "/repo/src/tui: Some(Conflicted)"
+        - "/repo/src/tui/view.rs: Some(Staged)"
+        - "/repo/docs: Some(Staged)"
+        - "/repo/docs/new name.md: Some(Staged)"
+        - "/repo/notes: Some(Untracked)"
+        - "/repo/notes/todo.md: Some(Untracked)"
+        - "/repo/lib: Some(Untracked)"
+        - "/repo/lib/old.rs: None"
+        - "/repo/target/debug: Some(Ignored)"
+        - "/repo/README.md: None"
+        "#);
+    }
+}
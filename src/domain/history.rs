@@ -0,0 +1,96 @@
+use std::path::{Path, PathBuf};
+
+// older directories are forgotten past this
+const MAX_HISTORY_LEN: usize = 100;
+
+/// The directories a session has been in, which can be gone back and forward through, like a
+/// browser's history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct History {
+    // oldest first
+    dirs: Vec<PathBuf>,
+    // index of the directory the session is in
+    current: usize,
+}
+
+impl History {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dirs: vec![dir],
+            current: 0,
+        }
+    }
+
+    pub fn dirs(&self) -> &[PathBuf] {
+        &self.dirs
+    }
+
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Records going to `dir`, which forgets the directories that could be gone forward to.
+    pub fn visit(&mut self, dir: &Path) {
+        if self.dirs[self.current] == dir {
+            return;
+        }
+
+        self.dirs.truncate(self.current + 1);
+        self.dirs.push(dir.to_path_buf());
+        if self.dirs.len() > MAX_HISTORY_LEN {
+            self.dirs.remove(0);
+        }
+        self.current = self.dirs.len() - 1;
+    }
+
+    /// Records going to the directory at `index`, which keeps the ones around it.
+    pub fn move_to(&mut self, index: usize) {
+        if index < self.dirs.len() {
+            self.current = index;
+        }
+    }
+
+    /// Returns the index of the directory before the current one, if there's one.
+    pub fn back(&self) -> Option<usize> {
+        self.current.checked_sub(1)
+    }
+
+    /// Returns the index of the directory after the current one, if there's one.
+    pub fn forward(&self) -> Option<usize> {
+        (self.current + 1 < self.dirs.len()).then_some(self.current + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visiting_a_directory_after_going_back_forgets_the_ones_ahead() {
+        // GIVEN
+        let mut history = History::new(PathBuf::from("/a"));
+        history.visit(Path::new("/a/b"));
+        history.visit(Path::new("/a/b/c"));
+
+        // WHEN
+        let back = history.back();
+        history.move_to(back.expect("there should be a directory to go back to"));
+        history.visit(Path::new("/a/b"));
+        let forward_before_visiting = history.forward();
+        history.visit(Path::new("/d"));
+
+        // THEN
+        assert_eq!(back, Some(1));
+        assert_eq!(forward_before_visiting, Some(2));
+        assert_eq!(history.forward(), None);
+        assert_eq!(history.current(), 2);
+        assert_eq!(
+            history.dirs(),
+            [
+                PathBuf::from("/a"),
+                PathBuf::from("/a/b"),
+                PathBuf::from("/d")
+            ]
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use super::Entry;
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SortMode {
+    #[default]
+    Name,
+    Extension,
+    Size,
+    Modified,
+}
+
+impl SortMode {
+    pub fn next(self) -> Self {
+        match self {
+            SortMode::Name => SortMode::Extension,
+            SortMode::Extension => SortMode::Size,
+            SortMode::Size => SortMode::Modified,
+            SortMode::Modified => SortMode::Name,
+        }
+    }
+}
+
+impl std::fmt::Display for SortMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortMode::Name => write!(f, "name"),
+            SortMode::Extension => write!(f, "extension"),
+            SortMode::Size => write!(f, "size"),
+            SortMode::Modified => write!(f, "modified"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ListingOptions {
+    pub show_hidden: bool,
+    pub sort_mode: SortMode,
+}
+
+impl ListingOptions {
+    /// Filters out entries that shouldn't be shown, and sorts the rest.
+    pub fn apply(&self, mut entries: Vec<Entry>) -> Vec<Entry> {
+        if !self.show_hidden {
+            entries.retain(|e| !e.is_hidden());
+        }
+
+        sort_entries(&mut entries, self.sort_mode);
+
+        entries
+    }
+}
+
+/// Sorts entries as per the sort mode; entries are always grouped by their kind first.
+pub fn sort_entries(entries: &mut [Entry], mode: SortMode) {
+    entries.sort_by(|a, b| compare_entries(a, b, mode));
+}
+
+/// Compares entries as per the sort mode, the way [`sort_entries`] does.
+pub fn compare_entries(a: &Entry, b: &Entry, mode: SortMode) -> Ordering {
+    match mode {
+        SortMode::Name => a.cmp(b),
+        SortMode::Extension => a
+            .kind()
+            .sort_group()
+            .cmp(&b.kind().sort_group())
+            .then_with(|| a.path().extension().cmp(&b.path().extension()))
+            .then_with(|| a.cmp(b)),
+        SortMode::Size => a
+            .kind()
+            .sort_group()
+            .cmp(&b.kind().sort_group())
+            .then_with(|| Reverse(a.size()).cmp(&Reverse(b.size())))
+            .then_with(|| a.cmp(b)),
+        SortMode::Modified => a
+            .kind()
+            .sort_group()
+            .cmp(&b.kind().sort_group())
+            .then_with(|| match (a.modified(), b.modified()) {
+                (Some(x), Some(y)) => y.cmp(&x),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            })
+            .then_with(|| a.cmp(b)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::EntryKind;
+    use insta::assert_yaml_snapshot;
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
+
+    fn get_entries() -> Vec<Entry> {
+        let epoch = SystemTime::UNIX_EPOCH;
+        vec![
+            Entry::new(PathBuf::from("/home/user/atls/Cargo.lock"), EntryKind::File)
+                .with_metadata(4000, Some(epoch + Duration::from_secs(30))),
+            Entry::new(PathBuf::from("/home/user/atls/src"), EntryKind::Directory)
+                .with_metadata(96, Some(epoch + Duration::from_secs(10))),
+            Entry::new(PathBuf::from("/home/user/atls/README.md"), EntryKind::File)
+                .with_metadata(300, Some(epoch + Duration::from_secs(20))),
+            Entry::new(PathBuf::from("/home/user/atls/Cargo.toml"), EntryKind::File)
+                .with_metadata(500, Some(epoch + Duration::from_secs(40))),
+            Entry::new(PathBuf::from("/home/user/atls/.git"), EntryKind::Directory)
+                .with_metadata(128, Some(epoch + Duration::from_secs(50))),
+        ]
+    }
+
+    fn sorted_paths(mode: SortMode) -> Vec<String> {
+        let mut entries = get_entries();
+        sort_entries(&mut entries, mode);
+        entries.into_iter().map(|e| e.path_str()).collect()
+    }
+
+    #[test]
+    fn listing_options_hide_hidden_entries() {
+        // GIVEN
+        let options = ListingOptions {
+            show_hidden: false,
+            sort_mode: SortMode::Name,
+        };
+
+        // WHEN
+        let paths = options
+            .apply(get_entries())
+            .into_iter()
+            .map(|e| e.path_str())
+            .collect::<Vec<_>>();
+
+        // THEN
+        assert_yaml_snapshot!(paths, @r#"
+        - src/
+        - Cargo.lock
+        - Cargo.toml
+        - README.md
+        "#);
+    }
+
+    #[test]
+    fn sorting_by_extension_works() {
+        // GIVEN
+        // WHEN
+        let paths = sorted_paths(SortMode::Extension);
+
+        // THEN
+        assert_yaml_snapshot!(paths, @r#"
+        - ".git/"
+        - src/
+        - Cargo.lock
+        - README.md
+        - Cargo.toml
+        "#);
+    }
+
+    #[test]
+    fn sorting_by_size_works() {
+        // GIVEN
+        // WHEN
+        let paths = sorted_paths(SortMode::Size);
+
+        // THEN
+        assert_yaml_snapshot!(paths, @r#"
+        - ".git/"
+        - src/
+        - Cargo.lock
+        - Cargo.toml
+        - README.md
+        "#);
+    }
+
+    #[test]
+    fn sorting_by_modification_time_works() {
+        // GIVEN
+        // WHEN
+        let paths = sorted_paths(SortMode::Modified);
+
+        // THEN
+        assert_yaml_snapshot!(paths, @r#"
+        - ".git/"
+        - src/
+        - Cargo.toml
+        - Cargo.lock
+        - README.md
+        "#);
+    }
+}
@@ -1,5 +1,25 @@
+mod custom_command;
+mod disk_usage;
+mod external_command;
 mod fs_operation;
+mod git;
+mod history;
+mod listing;
+mod opener;
 mod path;
+mod placeholders;
+mod size;
+mod workspace;
 
+pub use custom_command::*;
+pub use disk_usage::*;
+pub use external_command::*;
 pub use fs_operation::*;
+pub use git::*;
+pub use history::*;
+pub use listing::*;
+pub use opener::*;
 pub use path::*;
+pub use placeholders::*;
+pub use size::*;
+pub use workspace::*;
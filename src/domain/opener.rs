@@ -0,0 +1,205 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::Deserialize;
+use std::path::Path;
+
+/// A rule for opening files, as written in the config file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OpenerSpec {
+    pub name: Option<String>,
+    pub command: String,
+    #[serde(default)]
+    pub mime: Vec<String>,
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    #[serde(default)]
+    pub globs: Vec<String>,
+    #[serde(default = "default_blocking")]
+    pub blocking: bool,
+}
+
+fn default_blocking() -> bool {
+    true
+}
+
+/// A command to open files with, along with the files it applies to.
+///
+/// Blocking openers take over the terminal until they exit (eg. editors), while non-blocking ones
+/// are detached from atls (eg. GUI applications).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "OpenerSpec")]
+pub struct Opener {
+    pub name: String,
+    pub command: String,
+    pub blocking: bool,
+    mime_types: Vec<String>,
+    extensions: Vec<String>,
+    globs: GlobSet,
+}
+
+impl TryFrom<OpenerSpec> for Opener {
+    type Error = String;
+
+    fn try_from(spec: OpenerSpec) -> Result<Self, Self::Error> {
+        if spec.command.trim().is_empty() {
+            return Err("opener command cannot be empty".to_string());
+        }
+
+        for mime_type in &spec.mime {
+            if mime_type.split_once('/').is_none() {
+                return Err(format!(
+                    r#"invalid mime type "{mime_type}"; expected something like "image/png" or "image/*""#
+                ));
+            }
+        }
+
+        let mut builder = GlobSetBuilder::new();
+        for glob in &spec.globs {
+            let glob =
+                Glob::new(glob).map_err(|e| format!(r#"invalid glob pattern "{glob}": {e}"#))?;
+            builder.add(glob);
+        }
+        let globs = builder
+            .build()
+            .map_err(|e| format!("couldn't build glob patterns: {e}"))?;
+
+        let name = spec.name.unwrap_or_else(|| spec.command.clone());
+
+        Ok(Self {
+            name,
+            command: spec.command,
+            blocking: spec.blocking,
+            mime_types: spec.mime.iter().map(|m| m.to_lowercase()).collect(),
+            extensions: spec
+                .extensions
+                .iter()
+                .map(|e| e.trim_start_matches('.').to_lowercase())
+                .collect(),
+            globs,
+        })
+    }
+}
+
+impl Opener {
+    pub fn matches<P>(&self, path: P) -> bool
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+
+        if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+            let extension = extension.to_lowercase();
+            if self.extensions.contains(&extension) {
+                return true;
+            }
+        }
+
+        if self.globs.is_match(path) {
+            return true;
+        }
+
+        if !self.mime_types.is_empty() {
+            return mime_guess::from_path(path)
+                .iter()
+                .any(|guess| self.mime_types.iter().any(|m| mime_matches(m, &guess)));
+        }
+
+        false
+    }
+}
+
+/// Returns the openers applicable to `path`, in the order they were defined in.
+pub fn get_matching_openers<P>(openers: &[Opener], path: P) -> Vec<&Opener>
+where
+    P: AsRef<Path>,
+{
+    openers
+        .iter()
+        .filter(|o| o.matches(path.as_ref()))
+        .collect()
+}
+
+fn mime_matches(pattern: &str, mime: &mime_guess::Mime) -> bool {
+    let Some((type_, subtype)) = pattern.split_once('/') else {
+        return false;
+    };
+
+    (type_ == "*" || type_ == mime.type_().as_str())
+        && (subtype == "*" || subtype == mime.subtype().as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_yaml_snapshot;
+
+    fn get_opener(name: &str, mime: &[&str], extensions: &[&str], globs: &[&str]) -> Opener {
+        Opener::try_from(OpenerSpec {
+            name: Some(name.to_string()),
+            command: name.to_string(),
+            mime: mime.iter().map(|s| s.to_string()).collect(),
+            extensions: extensions.iter().map(|s| s.to_string()).collect(),
+            globs: globs.iter().map(|s| s.to_string()).collect(),
+            blocking: false,
+        })
+        .expect("opener should've been built")
+    }
+
+    #[test]
+    fn openers_are_matched_by_extension_glob_and_mime_type() {
+        // GIVEN
+        let openers = vec![
+            get_opener("zathura", &[], &[], &["*.pdf"]),
+            get_opener("imv", &["image/*"], &[], &[]),
+            get_opener("gimp", &[], &[".PNG", "xcf"], &[]),
+            get_opener("mpv", &["video/mp4", "audio/*"], &[], &[]),
+        ];
+
+        // WHEN
+        let result = [
+            "/home/user/docs/report.pdf",
+            "/home/user/pics/cat.png",
+            "/home/user/pics/dog.JPG",
+            "/home/user/music/song.mp3",
+            "/home/user/src/main.rs",
+        ]
+        .into_iter()
+        .map(|p| {
+            let names = get_matching_openers(&openers, p)
+                .into_iter()
+                .map(|o| o.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{p}: {names}")
+        })
+        .collect::<Vec<_>>();
+
+        // THEN
+        assert_yaml_snapshot!(result, @r#"
+        - "/home/user/docs/report.pdf: zathura"
+        - "/home/user/pics/cat.png: imv, gimp"
+        - "/home/user/pics/dog.JPG: imv"
+        - "/home/user/music/song.mp3: mpv"
+        - "/home/user/src/main.rs: "
+        "#);
+    }
+
+    #[test]
+    fn openers_with_invalid_patterns_are_rejected() {
+        // GIVEN
+        let spec = OpenerSpec {
+            name: None,
+            command: "zathura".to_string(),
+            mime: vec![],
+            extensions: vec![],
+            globs: vec!["*.{pdf".to_string()],
+            blocking: true,
+        };
+
+        // WHEN
+        let result = Opener::try_from(spec);
+
+        // THEN
+        assert!(result.is_err());
+    }
+}
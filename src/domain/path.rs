@@ -1,18 +1,90 @@
-use std::path::{Path, PathBuf};
+use std::ffi::OsStr;
+use std::fmt::Write;
+use std::hash::{Hash, Hasher};
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
 pub enum EntryKind {
     Directory,
     File,
+    // a regular file with at least one of its execute bits set
+    Executable,
     Symlink,
+    Fifo,
+    Socket,
+    BlockDevice,
+    CharDevice,
     Unknown,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+impl EntryKind {
+    /// Entries are listed in groups: directories, files, symlinks, special files, and then
+    /// everything else.
+    pub fn sort_group(&self) -> u8 {
+        match self {
+            EntryKind::Directory => 0,
+            EntryKind::File | EntryKind::Executable => 1,
+            EntryKind::Symlink => 2,
+            EntryKind::Fifo
+            | EntryKind::Socket
+            | EntryKind::BlockDevice
+            | EntryKind::CharDevice => 3,
+            EntryKind::Unknown => 4,
+        }
+    }
+
+    /// The character `ls -F` appends to names of entries of this kind.
+    pub fn indicator(&self) -> Option<char> {
+        match self {
+            EntryKind::Directory => Some('/'),
+            EntryKind::Executable => Some('*'),
+            EntryKind::Symlink => Some('@'),
+            EntryKind::Fifo => Some('|'),
+            EntryKind::Socket => Some('='),
+            EntryKind::File
+            | EntryKind::BlockDevice
+            | EntryKind::CharDevice
+            | EntryKind::Unknown => None,
+        }
+    }
+
+    /// Whether entries of this kind can be opened in an editor, pager, etc.
+    pub fn is_openable(&self) -> bool {
+        matches!(
+            self,
+            EntryKind::File | EntryKind::Executable | EntryKind::Symlink
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Entry {
     inner: PathBuf,
     kind: EntryKind,
-    path_str: String,
+    // the file name, escaped for display
+    name: String,
+    size: u64,
+    modified: Option<SystemTime>,
+    // whether the entry is a symlink to a directory
+    links_to_dir: bool,
+}
+
+// entries are identified by their path and kind only; metadata like size and
+// modification time is allowed to change between reads of the same directory
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner && self.kind == other.kind
+    }
+}
+
+impl Eq for Entry {}
+
+impl Hash for Entry {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.hash(state);
+        self.kind.hash(state);
+    }
 }
 
 impl PartialOrd for Entry {
@@ -24,34 +96,50 @@ impl PartialOrd for Entry {
 impl Ord for Entry {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         self.kind
-            .cmp(&other.kind)
+            .sort_group()
+            .cmp(&other.kind.sort_group())
             .then_with(|| self.inner.cmp(&other.inner))
+            .then_with(|| self.kind.cmp(&other.kind))
     }
 }
 
 impl Entry {
     pub fn new(path: PathBuf, kind: EntryKind) -> Self {
-        let path_str = path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("unknown")
-            .to_string();
+        let name = escape_os_str(path.file_name().unwrap_or(path.as_os_str()));
 
         Self {
             inner: path,
             kind,
-            path_str,
+            name,
+            size: 0,
+            modified: None,
+            links_to_dir: false,
         }
     }
 
+    pub fn with_metadata(mut self, size: u64, modified: Option<SystemTime>) -> Self {
+        self.size = size;
+        self.modified = modified;
+        self
+    }
+
+    pub fn with_links_to_dir(mut self, links_to_dir: bool) -> Self {
+        self.links_to_dir = links_to_dir;
+        self
+    }
+
     pub fn path_str(&self) -> String {
-        match self.kind() {
-            EntryKind::Directory => format!("{}/", self.path_str),
-            EntryKind::Symlink => format!("{}@", self.path_str),
-            _ => self.path_str.clone(),
+        match self.kind.indicator() {
+            Some(indicator) => format!("{}{indicator}", self.name),
+            None => self.name.clone(),
         }
     }
 
+    /// The entry's file name as it is on disk, which might not be valid UTF-8.
+    pub fn file_name(&self) -> &OsStr {
+        self.inner.file_name().unwrap_or(self.inner.as_os_str())
+    }
+
     pub fn kind(&self) -> EntryKind {
         self.kind
     }
@@ -59,6 +147,153 @@ impl Entry {
     pub fn path(&self) -> &Path {
         self.inner.as_path()
     }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    /// Whether the entry is a directory, or a symlink to one; ie, whether it can be entered.
+    pub fn leads_to_dir(&self) -> bool {
+        self.kind == EntryKind::Directory || self.links_to_dir
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.file_name().as_encoded_bytes().starts_with(b".")
+    }
+}
+
+/// Returns a printable version of `value`, which is lossless: bytes that aren't valid UTF-8 are
+/// shown as `\xNN`, and control characters are escaped so that they don't mess up the terminal.
+pub fn escape_os_str(value: &OsStr) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for chunk in value.as_encoded_bytes().utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                c if c.is_ascii_control() => {
+                    let _ = write!(escaped, "\\x{:02x}", c as u8);
+                }
+                c if c.is_control() => escaped.extend(c.escape_unicode()),
+                c => escaped.push(c),
+            }
+        }
+
+        for byte in chunk.invalid() {
+            let _ = write!(escaped, "\\x{byte:02x}");
+        }
+    }
+
+    escaped
+}
+
+/// Returns a printable, lossless version of `path`; see [`escape_os_str`].
+pub fn display_path(path: &Path) -> String {
+    escape_os_str(path.as_os_str())
+}
+
+/// Expands a path typed in by the user: a leading `~` stands for the home directory, `$VAR` and
+/// `${VAR}` for environment variables (looked up via `get_var`), and relative paths are relative to
+/// `cwd`. `.` and `..` are resolved without looking at the filesystem, like shells do.
+pub fn expand_path<F>(
+    input: &str,
+    cwd: &Path,
+    home_dir: Option<&Path>,
+    get_var: F,
+) -> Result<PathBuf, String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let (base, rest) = match input.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', std::path::MAIN_SEPARATOR]) => {
+            let home_dir = home_dir.ok_or("couldn't determine the home directory")?;
+            (
+                home_dir,
+                rest.trim_start_matches(['/', std::path::MAIN_SEPARATOR]),
+            )
+        }
+        _ => (cwd, input),
+    };
+
+    let mut expanded = String::with_capacity(rest.len());
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            expanded.push(c);
+            continue;
+        }
+
+        let braced = chars.next_if_eq(&'{').is_some();
+        let mut name = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+            name.push(c);
+        }
+        if braced && chars.next_if_eq(&'}').is_none() {
+            return Err(format!(r#"missing "}}" after "${{{name}""#));
+        }
+
+        if name.is_empty() && !braced {
+            expanded.push('$');
+            continue;
+        }
+        match get_var(&name) {
+            Some(value) => expanded.push_str(&value),
+            None => return Err(format!("environment variable {name} is not set")),
+        }
+    }
+
+    let mut path = base.to_path_buf();
+    for component in Path::new(&expanded).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                path.pop();
+            }
+            component => path.push(component),
+        }
+    }
+
+    Ok(path)
+}
+
+/// A directory in a path, as shown in the explorer's header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breadcrumb {
+    pub name: String,
+    pub dir: PathBuf,
+}
+
+/// Splits `path` into the directories it's made up of, starting from the root; the home directory
+/// stands in for everything above it as `~`.
+pub fn get_breadcrumbs(path: &Path, home_dir: Option<&Path>) -> Vec<Breadcrumb> {
+    let mut ancestors = path.ancestors().collect::<Vec<_>>();
+    ancestors.reverse();
+
+    let home_index = home_dir.and_then(|home| ancestors.iter().position(|dir| *dir == home));
+    let mut breadcrumbs = vec![];
+    for (i, dir) in ancestors.into_iter().enumerate() {
+        let name = match home_index {
+            Some(home_index) if i < home_index => continue,
+            Some(home_index) if i == home_index => "~".to_string(),
+            _ => dir
+                .file_name()
+                .map(escape_os_str)
+                .unwrap_or_else(|| display_path(dir)),
+        };
+
+        breadcrumbs.push(Breadcrumb {
+            name,
+            dir: dir.to_path_buf(),
+        });
+    }
+
+    breadcrumbs
 }
 
 #[cfg(test)]
@@ -78,6 +313,16 @@ mod tests {
             Entry::new(PathBuf::from("/home/user/atls/link-a"), EntryKind::Symlink),
             Entry::new(PathBuf::from("/home/user/atls/Cargo.toml"), EntryKind::File),
             Entry::new(PathBuf::from("/home/user/atls/unknown"), EntryKind::Unknown),
+            Entry::new(
+                PathBuf::from("/home/user/atls/run.sh"),
+                EntryKind::Executable,
+            ),
+            Entry::new(PathBuf::from("/home/user/atls/pipe"), EntryKind::Fifo),
+            Entry::new(
+                PathBuf::from("/home/user/atls/atls.sock"),
+                EntryKind::Socket,
+            ),
+            Entry::new(PathBuf::from("/home/user/atls/sda"), EntryKind::BlockDevice),
             Entry::new(
                 PathBuf::from("/home/user/atls/target"),
                 EntryKind::Directory,
@@ -102,9 +347,124 @@ mod tests {
         - ".fdignore"
         - Cargo.lock
         - Cargo.toml
+        - run.sh*
         - link-a@
         - link-b@
+        - atls.sock=
+        - pipe|
+        - sda
         - unknown
         "#);
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn names_that_are_not_valid_utf8_are_escaped_losslessly() {
+        use std::ffi::OsString;
+        use std::os::unix::ffi::OsStringExt;
+
+        // GIVEN
+        let names = [
+            OsString::from_vec(b"caf\xe9.txt".to_vec()),
+            OsString::from_vec(b".\x8f\xff".to_vec()),
+            OsString::from("tab\there\nand\x1b[31m"),
+            OsString::from("日本語.md"),
+        ];
+
+        // WHEN
+        let entries = names
+            .into_iter()
+            .map(|n| Entry::new(PathBuf::from("/tmp").join(n), EntryKind::File))
+            .collect::<Vec<_>>();
+
+        // THEN
+        let names = entries
+            .iter()
+            .map(|e| format!("{} (hidden: {})", e.path_str(), e.is_hidden()))
+            .collect::<Vec<_>>();
+
+        assert_yaml_snapshot!(names, @r#"
+        - "caf\\xe9.txt (hidden: false)"
+        - ".\\x8f\\xff (hidden: true)"
+        - "tab\\there\\nand\\x1b[31m (hidden: false)"
+        - "日本語.md (hidden: false)"
+        "#);
+    }
+
+    #[test]
+    fn paths_under_the_home_directory_are_shortened_in_breadcrumbs() {
+        // GIVEN
+        let home_dir = Path::new("/home/user");
+
+        // WHEN
+        let breadcrumbs = [
+            "/home/user/projects/atls",
+            "/home/user",
+            "/home/username",
+            "/",
+        ]
+        .map(|path| {
+            get_breadcrumbs(Path::new(path), Some(home_dir))
+                .into_iter()
+                .map(|b| format!("{} ({})", b.name, b.dir.display()))
+                .collect::<Vec<_>>()
+                .join(" | ")
+        });
+
+        // THEN
+        assert_yaml_snapshot!(breadcrumbs, @r"
+        - ~ (/home/user) | projects (/home/user/projects) | atls (/home/user/projects/atls)
+        - ~ (/home/user)
+        - / (/) | home (/home) | username (/home/username)
+        - / (/)
+        ");
+    }
+
+    #[test]
+    fn typed_in_paths_are_expanded() {
+        // GIVEN
+        let cwd = Path::new("/home/user/projects");
+        let home_dir = Path::new("/home/user");
+        let get_var = |name: &str| match name {
+            "DATA" => Some("/var/data".to_string()),
+            "SUB_DIR" => Some("logs".to_string()),
+            _ => None,
+        };
+
+        // WHEN
+        let expanded = [
+            "atls/src",
+            "../notes/./todo.md",
+            "~",
+            "~/.config",
+            "~user",
+            "/tmp/../etc",
+            "$DATA/$SUB_DIR",
+            "${DATA}_backup",
+            "$UNSET",
+            "${DATA",
+            "costs$",
+        ]
+        .map(
+            |input| match expand_path(input, cwd, Some(home_dir), get_var) {
+                Ok(path) => format!("{input} -> {}", path.display()),
+                Err(e) => format!("{input} -> error: {e}"),
+            },
+        );
+
+        // THEN
+        assert_yaml_snapshot!(expanded, @r#"
+        - atls/src -> /home/user/projects/atls/src
+        - "../notes/./todo.md -> /home/user/notes/todo.md"
+        - ~ -> /home/user
+        - ~/.config -> /home/user/.config
+        - ~user -> /home/user/projects/~user
+        - /tmp/../etc -> /etc
+        - $DATA/$SUB_DIR -> /var/data/logs
+        - "${DATA}_backup -> /var/data_backup"
+        - "$UNSET -> error: environment variable UNSET is not set"
+        - "${DATA -> error: missing \"}\" after \"${DATA\""
+        - costs$ -> /home/user/projects/costs$
+        "#);
+    }
 }
@@ -0,0 +1,201 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Values that placeholders in command templates expand to.
+///
+/// - `%f`: entry under cursor
+/// - `%m`: marked paths
+/// - `%d`: current session's directory
+/// - `%D`: the other session's directory
+/// - `%%`: a literal `%`
+#[derive(Debug, Clone, Default)]
+pub struct PlaceholderValues {
+    pub selected: Option<PathBuf>,
+    pub marked: Vec<PathBuf>,
+    pub current_dir: Option<PathBuf>,
+    pub other_dir: Option<PathBuf>,
+}
+
+/// Expands placeholders in `template` with shell quoted values.
+///
+/// Unknown placeholders are left as is; an error is returned if a placeholder is used but has no
+/// value.
+pub fn expand_placeholders(template: &str, values: &PlaceholderValues) -> Result<String, String> {
+    let mut expanded = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+
+        match chars.peek() {
+            Some('%') => expanded.push('%'),
+            Some('f') => {
+                let path = values
+                    .selected
+                    .as_deref()
+                    .ok_or("%f used, but there's no entry under cursor")?;
+                expanded.push_str(&shell_quote_path(path));
+            }
+            Some('m') => {
+                if values.marked.is_empty() {
+                    return Err("%m used, but no paths are marked".to_string());
+                }
+                let marked = values
+                    .marked
+                    .iter()
+                    .map(|p| shell_quote_path(p))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                expanded.push_str(&marked);
+            }
+            Some('d') => {
+                let path = values
+                    .current_dir
+                    .as_deref()
+                    .ok_or("%d used, but there's no current directory")?;
+                expanded.push_str(&shell_quote_path(path));
+            }
+            Some('D') => {
+                let path = values
+                    .other_dir
+                    .as_deref()
+                    .ok_or("%D used, but there's no other session open")?;
+                expanded.push_str(&shell_quote_path(path));
+            }
+            _ => {
+                expanded.push('%');
+                continue;
+            }
+        }
+
+        chars.next();
+    }
+
+    Ok(expanded)
+}
+
+/// Quotes a string so that a POSIX shell treats it as a single word.
+pub fn shell_quote(value: &str) -> String {
+    let is_safe = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "/._-+=:,@".contains(c));
+
+    if is_safe {
+        return value.to_string();
+    }
+
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+// quotes a path without losing bytes that aren't valid UTF-8; these can't be part of a String, so
+// the shell is made to produce them with printf instead
+fn shell_quote_path(path: &Path) -> String {
+    let mut quoted = String::new();
+
+    for chunk in path.as_os_str().as_encoded_bytes().utf8_chunks() {
+        if !chunk.valid().is_empty() {
+            quoted.push_str(&shell_quote(chunk.valid()));
+        }
+
+        if !chunk.invalid().is_empty() {
+            quoted.push_str("\"$(printf '");
+            for byte in chunk.invalid() {
+                let _ = write!(quoted, "\\{byte:03o}");
+            }
+            quoted.push_str("')\"");
+        }
+    }
+
+    if quoted.is_empty() {
+        return shell_quote("");
+    }
+
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+
+    fn get_values() -> PlaceholderValues {
+        PlaceholderValues {
+            selected: Some(PathBuf::from("/home/user/it's a file.txt")),
+            marked: vec![
+                PathBuf::from("/home/user/a.txt"),
+                PathBuf::from("/home/user/b c.txt"),
+            ],
+            current_dir: Some(PathBuf::from("/home/user")),
+            other_dir: Some(PathBuf::from("/tmp/$HOME")),
+        }
+    }
+
+    #[test]
+    fn placeholders_are_expanded_and_quoted() {
+        // GIVEN
+        let template = "cp %m %D && wc -l %f > %d/100%%.txt";
+
+        // WHEN
+        let expanded =
+            expand_placeholders(template, &get_values()).expect("template should've expanded");
+
+        // THEN
+        assert_snapshot!(expanded, @r#"cp /home/user/a.txt '/home/user/b c.txt' '/tmp/$HOME' && wc -l '/home/user/it'\''s a file.txt' > /home/user/100%.txt"#);
+    }
+
+    #[test]
+    fn unknown_placeholders_are_left_as_is() {
+        // GIVEN
+        let template = "date +%Y%H && printf %s %";
+
+        // WHEN
+        let expanded =
+            expand_placeholders(template, &get_values()).expect("template should've expanded");
+
+        // THEN
+        assert_snapshot!(expanded, @"date +%Y%H && printf %s %");
+    }
+
+    #[test]
+    fn using_a_placeholder_without_a_value_fails() {
+        // GIVEN
+        let values = PlaceholderValues {
+            other_dir: None,
+            ..get_values()
+        };
+
+        // WHEN
+        let result = expand_placeholders("mv %f %D", &values);
+
+        // THEN
+        assert_eq!(
+            result,
+            Err("%D used, but there's no other session open".to_string())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn paths_that_are_not_valid_utf8_are_quoted_losslessly() {
+        use std::ffi::OsString;
+        use std::os::unix::ffi::OsStringExt;
+
+        // GIVEN
+        let values = PlaceholderValues {
+            selected: Some(PathBuf::from(OsString::from_vec(
+                b"/tmp/caf\xe9 menu.txt".to_vec(),
+            ))),
+            ..get_values()
+        };
+
+        // WHEN
+        let expanded = expand_placeholders("cat %f", &values).expect("template should've expanded");
+
+        // THEN
+        assert_snapshot!(expanded, @r#"cat /tmp/caf"$(printf '\351')"' menu.txt'"#);
+    }
+}
@@ -0,0 +1,56 @@
+const UNITS: [&str; 6] = ["K", "M", "G", "T", "P", "E"];
+
+/// Formats a size in bytes the way `ls -h` does, eg. "512B", "4.0K", "12M".
+pub fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        return format!("{bytes}B");
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    // sizes that'd be rounded up to 1024 are shown in the next unit instead
+    while size >= 1023.5 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if size < 10.0 {
+        format!("{size:.1}{}", UNITS[unit])
+    } else {
+        format!("{size:.0}{}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_yaml_snapshot;
+
+    #[test]
+    fn sizes_are_formatted_like_ls_does() {
+        // GIVEN
+        let sizes = [
+            0,
+            1023,
+            1024,
+            4096,
+            12 * 1024 * 1024 + 300_000,
+            1024 * 1024 - 1,
+            3 * 1024u64.pow(4),
+        ];
+
+        // WHEN
+        let formatted = sizes.map(format_size);
+
+        // THEN
+        assert_yaml_snapshot!(formatted, @r"
+        - 0B
+        - 1023B
+        - 1.0K
+        - 4.0K
+        - 12M
+        - 1.0M
+        - 3.0T
+        ");
+    }
+}
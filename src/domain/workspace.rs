@@ -0,0 +1,118 @@
+use super::SortMode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_WORKSPACE: &str = "default";
+const MAX_SAVED_SELECTIONS: usize = 1000;
+
+/// Snapshot of everything needed to bring back an explorer session across runs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkspaceState {
+    #[serde(default)]
+    pub sessions: Vec<Option<SessionState>>,
+    #[serde(default)]
+    pub current_session_index: usize,
+    #[serde(default)]
+    pub last_selections: HashMap<PathBuf, PathBuf>,
+    #[serde(default)]
+    pub marked_paths: Vec<PathBuf>,
+    #[serde(default)]
+    pub show_hidden: Option<bool>,
+    #[serde(default)]
+    pub sort_mode: Option<SortMode>,
+}
+
+impl WorkspaceState {
+    /// Drops selections of paths that no longer exist, and keeps the rest from growing without
+    /// bound across runs; selections in the saved sessions' directories are kept over others.
+    pub fn prune_last_selections<F>(&mut self, exists: F)
+    where
+        F: Fn(&Path) -> bool,
+    {
+        self.last_selections
+            .retain(|dir, selected| exists(dir) && exists(selected));
+
+        if self.last_selections.len() <= MAX_SAVED_SELECTIONS {
+            return;
+        }
+
+        let mut dirs = self.last_selections.keys().cloned().collect::<Vec<_>>();
+        dirs.sort_by_key(|dir| {
+            let in_session = self
+                .sessions
+                .iter()
+                .flatten()
+                .any(|s| s.path.starts_with(dir));
+            (!in_session, dir.clone())
+        });
+        for dir in dirs.into_iter().skip(MAX_SAVED_SELECTIONS) {
+            self.last_selections.remove(&dir);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionState {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub selected: Option<PathBuf>,
+}
+
+pub fn validate_workspace_name(name: &str) -> Result<String, String> {
+    if name.is_empty() {
+        return Err("workspace name cannot be empty".to_string());
+    }
+
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(
+            "workspace name can only contain ascii letters, digits, '-' and '_'".to_string(),
+        );
+    }
+
+    Ok(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pruning_last_selections_drops_missing_paths_and_caps_the_rest() {
+        // GIVEN
+        let mut state = WorkspaceState {
+            sessions: vec![Some(SessionState {
+                path: PathBuf::from("/session/dir"),
+                selected: None,
+            })],
+            ..Default::default()
+        };
+        for i in 0..MAX_SAVED_SELECTIONS + 10 {
+            let dir = PathBuf::from(format!("/dirs/{i:04}"));
+            state.last_selections.insert(dir.clone(), dir.join("file"));
+        }
+        state
+            .last_selections
+            .insert(PathBuf::from("/session"), PathBuf::from("/session/dir"));
+        state
+            .last_selections
+            .insert(PathBuf::from("/gone"), PathBuf::from("/gone/file"));
+
+        // WHEN
+        state.prune_last_selections(|p| !p.starts_with("/gone"));
+
+        // THEN
+        assert_eq!(state.last_selections.len(), MAX_SAVED_SELECTIONS);
+        assert!(state.last_selections.contains_key(Path::new("/session")));
+        assert!(!state.last_selections.contains_key(Path::new("/gone")));
+        assert!(state.last_selections.contains_key(Path::new("/dirs/0000")));
+        assert!(
+            !state
+                .last_selections
+                .contains_key(Path::new(&format!("/dirs/{:04}", MAX_SAVED_SELECTIONS)))
+        );
+    }
+}
@@ -1,21 +1,336 @@
+mod cli;
 mod common;
+mod config;
+mod dirs;
 mod domain;
 mod log;
 mod services;
+mod shell;
 mod tui;
 
 use anyhow::Context;
+use clap::Parser;
+use cli::{Args, AtlsCommand, ConfigCommand};
+use config::{get_default_config_path, load_config};
+use dirs::{get_home_dir, get_state_dir};
+use domain::ListingOptions;
 use log::setup_logging;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use tui::{ChooserMode, LS_COLORS_ENV_VAR, LsColors, MAX_NUM_SESSIONS, Settings, StartupOptions};
+
+const EXIT_CODE_CHOICE_CANCELLED: u8 = 1;
+const EXIT_CODE_INVALID_START_PATH: u8 = 2;
+const STDOUT_PATH: &str = "-";
+
+#[derive(Debug)]
+struct InvalidStartPathError {
+    path: PathBuf,
+    source: Option<std::io::Error>,
+}
+
+impl std::fmt::Display for InvalidStartPathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = self.path.to_string_lossy();
+        match &self.source {
+            Some(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                write!(f, "path doesn't exist: {path}")
+            }
+            Some(_) => write!(f, "couldn't access path: {path}"),
+            None => write!(f, "invalid path: {path}"),
+        }
+    }
+}
+
+impl std::error::Error for InvalidStartPathError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|e| e as &(dyn std::error::Error + 'static))
+    }
+}
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            if e.is::<InvalidStartPathError>() {
+                ExitCode::from(EXIT_CODE_INVALID_START_PATH)
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+    }
+}
+
+async fn run() -> anyhow::Result<ExitCode> {
+    let args = Args::parse();
+
+    if let Some(command) = args.command {
+        match command {
+            AtlsCommand::Init {
+                shell,
+                function_name,
+            } => print!("{}", shell::get_shell_init(shell, &function_name)),
+            AtlsCommand::Config {
+                command: ConfigCommand::Check { path },
+            } => check_config(path)?,
+        }
+
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let chooser = match (&args.choose_files, &args.choose_dir) {
+        (Some(path), _) => Some((ChooserMode::Files, path.clone())),
+        (_, Some(path)) => Some((ChooserMode::Directory, path.clone())),
+        _ => None,
+    };
+
     setup_logging().context("couldn't set up logging")?;
 
-    let path = tokio::fs::canonicalize(".")
-        .await
-        .context("couldn't canonicalize path")?;
+    let config = load_config(args.config.as_deref())?;
+    let keymap = config.keymap()?;
+    let theme = config.theme()?;
+    let ls_colors = if config.ls_colors {
+        std::env::var(LS_COLORS_ENV_VAR)
+            .ok()
+            .map(|v| LsColors::parse(&v))
+    } else {
+        None
+    };
+
+    let (paths, selections) = resolve_start_paths(&args.paths, args.select.as_deref()).await?;
+
+    let state_dir = get_state_dir().context("couldn't determine state directory")?;
+    let workspace_state = if args.restore {
+        services::load_workspace_state(&state_dir, &args.workspace)
+            .context("couldn't load workspace state")?
+    } else {
+        None
+    };
+
+    // precedence: command line flags > restored workspace > config
+    let mut listing = ListingOptions {
+        show_hidden: config.show_hidden,
+        sort_mode: config.sort,
+    };
+    if let Some(state) = &workspace_state {
+        listing.show_hidden = state.show_hidden.unwrap_or(listing.show_hidden);
+        listing.sort_mode = state.sort_mode.unwrap_or(listing.sort_mode);
+    }
+    if args.show_hidden {
+        listing.show_hidden = true;
+    }
+    if let Some(sort_mode) = args.sort_mode {
+        listing.sort_mode = sort_mode;
+    }
+
+    let options = StartupOptions {
+        paths,
+        selections,
+        workspace_state,
+        settings: Settings {
+            listing,
+            read_only: args.read_only || config.read_only,
+            chooser: chooser.as_ref().map(|(mode, _)| *mode),
+            openers: config.openers,
+            custom_commands: config.commands,
+            preview: config.preview,
+            layout: config.layout,
+            auto_dir_sizes: config.auto_dir_sizes,
+            disable_mouse: args.no_mouse || config.disable_mouse,
+            home_dir: get_home_dir(),
+            theme,
+            ls_colors,
+            keymap,
+        },
+    };
+
+    let outcome = tui::run(options).await?;
+
+    if let Some(final_dir) = &outcome.final_dir {
+        if let Some(cwd_file) = &args.cwd_file {
+            std::fs::write(cwd_file, final_dir.as_os_str().as_encoded_bytes())
+                .with_context(|| format!("couldn't write to {}", cwd_file.to_string_lossy()))?;
+        }
+
+        if args.print_cwd {
+            let mut stdout = std::io::stdout().lock();
+            stdout
+                .write_all(final_dir.as_os_str().as_encoded_bytes())
+                .and_then(|_| stdout.write_all(b"\n"))
+                .and_then(|_| stdout.flush())
+                .context("couldn't write the final directory to stdout")?;
+        }
+    }
+
+    if let Some((_, output_path)) = chooser {
+        // choosers are usually run from other programs; they shouldn't clobber the user's workspace
+        let Some(chosen_paths) = outcome.chosen_paths else {
+            return Ok(ExitCode::from(EXIT_CODE_CHOICE_CANCELLED));
+        };
+
+        let separator = if args.print0 { b'\0' } else { b'\n' };
+        write_chosen_paths(&chosen_paths, &output_path, separator)?;
+
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // state is saved on every run, not just restored ones, so that `--restore` can pick up
+    // wherever atls was last left off
+    let mut workspace_state = outcome.workspace_state;
+    if !args.save_marked {
+        workspace_state.marked_paths.clear();
+    }
+    workspace_state.prune_last_selections(|p| p.exists());
+
+    services::save_workspace_state(&state_dir, &args.workspace, &workspace_state)
+        .context("couldn't save workspace state")?;
 
-    tui::run(path).await?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn check_config(path: Option<PathBuf>) -> anyhow::Result<()> {
+    let path = match path {
+        Some(p) => p,
+        None => {
+            let default_path = get_default_config_path()?;
+            if !default_path.exists() {
+                println!(
+                    "no config file at {}; atls will use its defaults",
+                    default_path.to_string_lossy()
+                );
+                return Ok(());
+            }
+
+            default_path
+        }
+    };
+
+    load_config(Some(&path))?;
+    println!("{} is valid", path.to_string_lossy());
 
     Ok(())
 }
+
+fn write_chosen_paths(paths: &[PathBuf], output_path: &Path, separator: u8) -> anyhow::Result<()> {
+    let mut contents = vec![];
+    for path in paths {
+        contents.extend_from_slice(path.as_os_str().as_encoded_bytes());
+        contents.push(separator);
+    }
+
+    if output_path.as_os_str() == STDOUT_PATH {
+        let mut stdout = std::io::stdout().lock();
+        stdout
+            .write_all(&contents)
+            .and_then(|_| stdout.flush())
+            .context("couldn't write chosen paths to stdout")?;
+    } else {
+        std::fs::write(output_path, contents).with_context(|| {
+            format!(
+                "couldn't write chosen paths to {}",
+                output_path.to_string_lossy()
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
+// returns directories to open sessions at, and entries to highlight in them
+async fn resolve_start_paths(
+    paths: &[PathBuf],
+    select: Option<&Path>,
+) -> anyhow::Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let mut directories = vec![];
+    let mut selections = vec![];
+
+    let paths = paths
+        .iter()
+        .map(|p| (p.as_path(), false))
+        .chain(select.map(|p| (p, true)));
+
+    for (path, is_selection) in paths {
+        let metadata =
+            tokio::fs::symlink_metadata(path)
+                .await
+                .map_err(|e| InvalidStartPathError {
+                    path: path.to_path_buf(),
+                    source: Some(e),
+                })?;
+
+        if !is_selection && metadata.is_dir() {
+            let directory = tokio::fs::canonicalize(path)
+                .await
+                .with_context(|| format!("couldn't canonicalize {}", path.to_string_lossy()))?;
+            directories.push(directory);
+            continue;
+        }
+
+        // canonicalizing the parent instead of the path itself means symlinks get highlighted,
+        // rather than their targets
+        let parent = match path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p,
+            _ => Path::new("."),
+        };
+        let parent = tokio::fs::canonicalize(parent)
+            .await
+            .with_context(|| format!("couldn't canonicalize {}", parent.to_string_lossy()))?;
+
+        let Some(file_name) = path.file_name() else {
+            return Err(InvalidStartPathError {
+                path: path.to_path_buf(),
+                source: None,
+            }
+            .into());
+        };
+
+        selections.push(parent.join(file_name));
+        if !directories.contains(&parent) {
+            directories.push(parent);
+        }
+    }
+
+    if directories.is_empty() {
+        let cwd = tokio::fs::canonicalize(".")
+            .await
+            .context("couldn't canonicalize path")?;
+        directories.push(cwd);
+    }
+
+    if directories.len() > MAX_NUM_SESSIONS {
+        anyhow::bail!("atls supports at most {MAX_NUM_SESSIONS} sessions");
+    }
+
+    Ok((directories, selections))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chosen_paths_are_written_with_the_given_separator() {
+        // GIVEN
+        let dir = tempfile::tempdir().expect("temporary directory should've been created");
+        let paths = vec![PathBuf::from("/home/a b.txt"), PathBuf::from("/home/c\nd")];
+        let newline_output = dir.path().join("newline");
+        let nul_output = dir.path().join("nul");
+
+        // WHEN
+        write_chosen_paths(&paths, &newline_output, b'\n')
+            .expect("chosen paths should've been written");
+        write_chosen_paths(&paths, &nul_output, b'\0')
+            .expect("chosen paths should've been written");
+
+        // THEN
+        let newline_contents = std::fs::read(&newline_output).expect("output should be readable");
+        let nul_contents = std::fs::read(&nul_output).expect("output should be readable");
+        assert_eq!(newline_contents, b"/home/a b.txt\n/home/c\nd\n");
+        assert_eq!(nul_contents, b"/home/a b.txt\0/home/c\nd\0");
+    }
+}
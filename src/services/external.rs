@@ -0,0 +1,114 @@
+use crate::domain::{CommandOutput, ExternalCommand};
+use anyhow::Context;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use tracing::debug;
+
+const DEFAULT_EDITOR: &str = "vi";
+const DEFAULT_PAGER: &str = "less";
+
+pub fn get_editor_command_line() -> String {
+    ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string())
+}
+
+pub fn get_pager_command_line() -> String {
+    std::env::var("PAGER")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PAGER.to_string())
+}
+
+/// Runs the command, inheriting atls' stdio, and waits for it to finish.
+pub fn run_external_command(command: &ExternalCommand) -> anyhow::Result<()> {
+    debug!("running external command: {:?}", command);
+
+    let mut cmd = Command::new(&command.program);
+    cmd.args(&command.args);
+    if let Some(cwd) = &command.cwd {
+        cmd.current_dir(cwd);
+    }
+
+    let status = cmd
+        .status()
+        .with_context(|| format!("couldn't run \"{}\"", command.program))?;
+
+    if !status.success() {
+        anyhow::bail!("\"{}\" exited with {}", command.program, status);
+    }
+
+    Ok(())
+}
+
+/// Starts the command without waiting for it, detached from atls' stdio and terminal.
+pub fn spawn_detached_command(command: &ExternalCommand) -> anyhow::Result<()> {
+    debug!("spawning detached command: {:?}", command);
+
+    let mut cmd = Command::new(&command.program);
+    cmd.args(&command.args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    if let Some(cwd) = &command.cwd {
+        cmd.current_dir(cwd);
+    }
+
+    // a separate process group keeps the command alive when signals are sent to atls' group
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+
+    let mut child = cmd
+        .spawn()
+        .with_context(|| format!("couldn't run \"{}\"", command.program))?;
+
+    // reap the child once it exits, so it doesn't linger around as a zombie
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+
+    Ok(())
+}
+
+/// Runs the command to completion, capturing its stdout and stderr.
+pub fn run_external_command_capturing_output(
+    command: &ExternalCommand,
+) -> anyhow::Result<CommandOutput> {
+    debug!("running external command capturing output: {:?}", command);
+
+    let mut cmd = Command::new(&command.program);
+    cmd.args(&command.args).stdin(Stdio::null());
+    if let Some(cwd) = &command.cwd {
+        cmd.current_dir(cwd);
+    }
+
+    let output = cmd
+        .output()
+        .with_context(|| format!("couldn't run \"{}\"", command.program))?;
+
+    let mut combined = String::from_utf8_lossy(&output.stdout).to_string();
+    combined.push_str(&String::from_utf8_lossy(&output.stderr));
+
+    Ok(CommandOutput {
+        success: output.status.success(),
+        status: output.status.to_string(),
+        output: combined,
+    })
+}
+
+/// Blocks till the user presses enter; meant to be used while atls' TUI is suspended.
+pub fn wait_for_enter() -> anyhow::Result<()> {
+    let mut stderr = std::io::stderr();
+    write!(stderr, "\n[press enter to return to atls]")?;
+    stderr.flush()?;
+
+    let mut buf = String::new();
+    std::io::stdin().read_line(&mut buf)?;
+
+    Ok(())
+}
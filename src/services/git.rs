@@ -0,0 +1,282 @@
+use super::preview::TAB_REPLACEMENT;
+use crate::domain::{GitAction, GitStatus};
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::debug;
+
+/// Returns the status of the git repository `dir` is in, or `None` if it isn't in one, or git
+/// isn't available.
+pub fn read_git_status(dir: &Path) -> Option<GitStatus> {
+    // the prefix is the path to `dir` from the repository's root, eg. "src/tui/"; using it rather
+    // than the root git reports keeps paths as they are in listings, eg. when `dir` is reached via
+    // a symlink
+    let prefix = run_git(dir, &["rev-parse", "--show-prefix"])?;
+    let depth = prefix
+        .split(|b| *b == b'/')
+        .filter(|c| !c.trim_ascii().is_empty())
+        .count();
+    let mut root = dir.to_path_buf();
+    for _ in 0..depth {
+        root.pop();
+    }
+
+    // only what's under `dir` is listed, so the rest of the repository isn't walked
+    let output = run_git(
+        dir,
+        &[
+            "status",
+            "--porcelain=v2",
+            "--branch",
+            "-z",
+            "--ignored=matching",
+            "--",
+            ".",
+        ],
+    )?;
+
+    Some(GitStatus::parse(&root, &output))
+}
+
+/// Returns up to `max_lines` lines of the diff between the file at `path` and its last committed
+/// version, covering both staged and unstaged changes.
+pub fn read_git_diff(path: &Path, max_lines: usize) -> anyhow::Result<Vec<String>> {
+    let dir = path.parent().context("file has no parent directory")?;
+    let output = git_command(dir)
+        .args(["diff", "HEAD", "--no-color", "--no-ext-diff", "--"])
+        .arg(path)
+        .output()
+        .context("couldn't run git")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "git diff failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let lines = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .take(max_lines)
+        .map(|l| l.replace('\t', TAB_REPLACEMENT))
+        .collect::<Vec<_>>();
+
+    if lines.is_empty() {
+        return Ok(vec!["(no changes)".to_string()]);
+    }
+
+    Ok(lines)
+}
+
+/// Stages, unstages, or discards changes to `paths`, which can be in different repositories.
+pub fn run_git_action(action: GitAction, paths: &[PathBuf]) -> anyhow::Result<()> {
+    debug!("running git action {:?} on paths: {:?}", action, paths);
+
+    let args: &[&str] = match action {
+        // -A stages deletions as well
+        GitAction::Stage => &["add", "-A"],
+        GitAction::Unstage => &["reset", "--quiet"],
+        // changes are discarded in the work tree only, so staged changes are kept
+        GitAction::Discard => &["restore", "--worktree"],
+    };
+
+    // paths are grouped by directory, so that every group is in a single repository
+    let mut paths_by_dir: BTreeMap<&Path, Vec<&Path>> = BTreeMap::new();
+    for path in paths {
+        let dir = path.parent().unwrap_or(path);
+        paths_by_dir.entry(dir).or_default().push(path);
+    }
+
+    for (dir, paths) in paths_by_dir {
+        let output = git_command(dir)
+            .args(args)
+            .arg("--")
+            .args(paths)
+            .output()
+            .context("couldn't run git")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "git {} failed: {}",
+                args[0],
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn git_command(dir: &Path) -> Command {
+    let mut command = Command::new("git");
+    command
+        .arg("-C")
+        .arg(dir)
+        // optional locks (eg. the one status takes to refresh the index) can get in the way of git
+        // commands being run at the same time
+        .env("GIT_OPTIONAL_LOCKS", "0");
+
+    command
+}
+
+fn run_git(dir: &Path, args: &[&str]) -> Option<Vec<u8>> {
+    let output = git_command(dir)
+        .args(args)
+        .output()
+        .inspect_err(|e| debug!("couldn't run git: {e}"))
+        .ok()?;
+
+    if !output.status.success() {
+        debug!(
+            "git {:?} failed in {:?}: {}",
+            args,
+            dir,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return None;
+    }
+
+    Some(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::GitFileStatus;
+
+    #[test]
+    fn statuses_are_read_from_subdirectories_of_repositories() {
+        // GIVEN
+        let dir = tempfile::tempdir().expect("temporary directory should've been created");
+        let repo = dir.path();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(repo)
+                .args(args)
+                .status()
+                .expect("git should've run");
+            assert!(status.success());
+        };
+        // --initial-branch needs git 2.28
+        git(&["init", "--quiet"]);
+        git(&["symbolic-ref", "HEAD", "refs/heads/main"]);
+        std::fs::create_dir(repo.join("src")).expect("directory should've been created");
+        std::fs::write(repo.join("src/new.rs"), "").expect("file should've been written");
+        std::fs::write(repo.join("outside.rs"), "").expect("file should've been written");
+
+        // WHEN
+        let status = read_git_status(&repo.join("src"));
+        let outside = read_git_status(Path::new("/"));
+
+        // THEN
+        let status = status.expect("status should've been read");
+        assert_eq!(status.branch.as_deref(), Some("main"));
+        assert_eq!(
+            status.status_of(&repo.join("src/new.rs")),
+            Some(GitFileStatus::Untracked)
+        );
+        assert_eq!(status.status_of(&repo.join("outside.rs")), None);
+        assert!(outside.is_none());
+    }
+
+    #[test]
+    fn changes_can_be_staged_and_unstaged() {
+        // GIVEN
+        let dir = tempfile::tempdir().expect("temporary directory should've been created");
+        let repo = dir.path().to_path_buf();
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(&repo)
+            .args(["init", "--quiet"])
+            .status()
+            .expect("git should've run");
+        assert!(status.success());
+        let file = repo.join("new.rs");
+        std::fs::write(&file, "").expect("file should've been written");
+
+        // WHEN
+        let staged = run_git_action(GitAction::Stage, std::slice::from_ref(&file))
+            .map(|_| read_git_status(&repo).and_then(|s| s.status_of(&file)));
+        let unstaged = run_git_action(GitAction::Unstage, std::slice::from_ref(&file))
+            .map(|_| read_git_status(&repo).and_then(|s| s.status_of(&file)));
+
+        // THEN
+        assert_eq!(
+            staged.expect("file should've been staged"),
+            Some(GitFileStatus::Staged)
+        );
+        assert_eq!(
+            unstaged.expect("file should've been unstaged"),
+            Some(GitFileStatus::Untracked)
+        );
+    }
+
+    fn committed_repo(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().expect("temporary directory should've been created");
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(dir.path())
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .status()
+                .expect("git should've run");
+            assert!(status.success());
+        };
+        git(&["init", "--quiet"]);
+        for (name, contents) in files {
+            std::fs::write(dir.path().join(name), contents).expect("file should've been written");
+        }
+        git(&["add", "-A"]);
+        git(&["commit", "--quiet", "-m", "initial"]);
+
+        dir
+    }
+
+    #[test]
+    fn discarding_restores_modified_files_and_leaves_untracked_ones_alone() {
+        // GIVEN
+        let dir = committed_repo(&[("tracked.txt", "committed\n")]);
+        let tracked = dir.path().join("tracked.txt");
+        let untracked = dir.path().join("untracked.txt");
+        std::fs::write(&tracked, "changed\n").expect("file should've been written");
+        std::fs::write(&untracked, "new\n").expect("file should've been written");
+
+        // WHEN
+        let result = run_git_action(GitAction::Discard, std::slice::from_ref(&tracked));
+
+        // THEN
+        result.expect("changes should've been discarded");
+        assert_eq!(
+            std::fs::read_to_string(&tracked).expect("file should've been read"),
+            "committed\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(&untracked).expect("file should've been read"),
+            "new\n"
+        );
+    }
+
+    #[test]
+    fn diffs_cover_changes_since_the_last_commit() {
+        // GIVEN
+        let dir = committed_repo(&[("file.txt", "one\ntwo\n"), ("same.txt", "same\n")]);
+        let file = dir.path().join("file.txt");
+        std::fs::write(&file, "one\nthree\n").expect("file should've been written");
+
+        // WHEN
+        let diff = read_git_diff(&file, 100).expect("diff should've been read");
+        let no_diff =
+            read_git_diff(&dir.path().join("same.txt"), 100).expect("diff should've been read");
+
+        // THEN
+        let changes = diff
+            .iter()
+            .filter(|l| l.starts_with(['+', '-']) && !l.starts_with("+++") && !l.starts_with("---"))
+            .collect::<Vec<_>>();
+        assert_eq!(changes, ["-two", "+three"]);
+        assert_eq!(no_diff, ["(no changes)"]);
+    }
+}
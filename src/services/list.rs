@@ -1,48 +1,195 @@
 use crate::domain::{Entry, EntryKind};
 use anyhow::Context;
+use std::fs::{DirEntry, Metadata};
 use std::path::Path;
 use tokio::fs;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
 use tracing::debug;
 
-pub async fn list_entries_at_directory<P>(path: P) -> anyhow::Result<Vec<Entry>>
+// the first chunk is small so that a screenful of entries shows up quickly, even for huge
+// directories; chunks double in size after that, so that huge directories don't result in too
+// many of them, since every chunk has to be sorted into the entries shown so far
+const FIRST_CHUNK_SIZE: usize = 256;
+const MAX_CHUNK_SIZE: usize = 32768;
+
+/// Reads the entries at `path`, calling `on_chunk` with them in chunks as soon as they're read,
+/// along with whether it's the last chunk. Metadata for different chunks is fetched concurrently,
+/// so chunks can come in any order, and entries in them aren't sorted.
+///
+/// At least one chunk is always passed to `on_chunk`, even if the directory is empty.
+pub async fn stream_entries_at_directory<P, F, Fut>(path: P, mut on_chunk: F) -> anyhow::Result<()>
 where
     P: AsRef<Path>,
+    F: FnMut(Vec<Entry>, bool) -> Fut,
+    Fut: Future<Output = ()>,
 {
-    debug!("reading directory: {:?}", path.as_ref());
-    let mut read_dir_result = fs::read_dir(&path)
+    let path = path.as_ref().to_path_buf();
+    debug!("reading directory: {:?}", &path);
+
+    let read_dir = tokio::task::spawn_blocking(move || std::fs::read_dir(path))
         .await
+        .context("couldn't get entries at path")?
         .inspect_err(|e| debug!("couldn't read directory: {:?}", e))
         .context("couldn't get entries at path")?;
 
-    let mut entries = vec![];
-    while let Some(entry) = read_dir_result.next_entry().await? {
-        let entry_path = entry.path();
-        match fs::symlink_metadata(&entry_path).await {
-            Ok(m) => {
-                let path_kind = if m.is_file() {
-                    EntryKind::File
-                } else if m.is_dir() {
-                    EntryKind::Directory
-                } else if m.is_symlink() {
-                    EntryKind::Symlink
-                } else {
-                    EntryKind::Unknown
-                };
-
-                entries.push(Entry::new(entry_path, path_kind));
+    let (batches_tx, mut batches_rx) = mpsc::channel::<Vec<DirEntry>>(4);
+    tokio::task::spawn_blocking(move || {
+        let mut batch_size = FIRST_CHUNK_SIZE;
+        let mut batch = Vec::with_capacity(batch_size);
+        // entries that vanish, or can't be read, while the directory is being read are skipped
+        for dir_entry in read_dir.flatten() {
+            batch.push(dir_entry);
+            if batch.len() == batch_size {
+                if batches_tx
+                    .blocking_send(std::mem::take(&mut batch))
+                    .is_err()
+                {
+                    return;
+                }
+                batch_size = (batch_size * 2).min(MAX_CHUNK_SIZE);
+            }
+        }
+
+        if !batch.is_empty() {
+            let _ = batches_tx.blocking_send(batch);
+        }
+    });
+
+    let mut metadata_tasks = JoinSet::new();
+    let mut reading = true;
+    // chunks are passed on one behind, so that the last one can be marked as such
+    let mut held_chunk = None;
+    let mut num_entries = 0;
+    loop {
+        tokio::select! {
+            batch = batches_rx.recv(), if reading => match batch {
+                Some(batch) => {
+                    metadata_tasks.spawn_blocking(move || {
+                        batch.iter().filter_map(get_entry).collect::<Vec<_>>()
+                    });
+                }
+                None => reading = false,
+            },
+            Some(result) = metadata_tasks.join_next() => {
+                let chunk = result.context("couldn't read metadata of entries")?;
+                num_entries += chunk.len();
+                if let Some(previous_chunk) = held_chunk.replace(chunk) {
+                    on_chunk(previous_chunk, false).await;
+                }
             }
-            Err(_e) => {} // TODO: handle this error
+            else => break,
         }
     }
 
-    entries.sort();
+    on_chunk(held_chunk.unwrap_or_default(), true).await;
+
+    debug!("found {} entries in directory", num_entries);
+    Ok(())
+}
+
+fn get_entry(dir_entry: &DirEntry) -> Option<Entry> {
+    // doesn't follow symlinks, same as symlink_metadata
+    let metadata = dir_entry.metadata().ok()?;
+
+    let path = dir_entry.path();
+    let links_to_dir = metadata.is_symlink() && path.is_dir();
+
+    Some(
+        Entry::new(path, get_entry_kind(&metadata))
+            .with_metadata(metadata.len(), metadata.modified().ok())
+            .with_links_to_dir(links_to_dir),
+    )
+}
+
+/// Returns the entry at `path`, or `None` if it no longer exists.
+pub async fn get_entry_at_path<P>(path: P) -> Option<Entry>
+where
+    P: AsRef<Path>,
+{
+    let metadata = fs::symlink_metadata(&path).await.ok()?;
+    let links_to_dir = metadata.is_symlink() && fs::metadata(&path).await.is_ok_and(|m| m.is_dir());
+
+    Some(
+        Entry::new(path.as_ref().to_path_buf(), get_entry_kind(&metadata))
+            .with_metadata(metadata.len(), metadata.modified().ok())
+            .with_links_to_dir(links_to_dir),
+    )
+}
+
+/// Returns the names of entries in `dir` that start with `prefix`, sorted, with directories (and
+/// symlinks to them) ending in a separator. Hidden entries are left out unless asked for, or unless
+/// `prefix` starts with a dot.
+pub fn read_path_completions(
+    dir: &Path,
+    prefix: &str,
+    show_hidden: bool,
+) -> anyhow::Result<Vec<String>> {
+    let show_hidden = show_hidden || prefix.starts_with('.');
 
-    debug!(
-        "found {} entries in directory {:?}",
-        entries.len(),
-        path.as_ref()
-    );
-    Ok(entries)
+    let mut names = std::fs::read_dir(dir)
+        .context("couldn't read directory")?
+        .flatten()
+        .filter_map(|dir_entry| {
+            // names that aren't valid UTF-8 can't be typed in anyway
+            let name = dir_entry.file_name().into_string().ok()?;
+            if !name.starts_with(prefix) || (!show_hidden && name.starts_with('.')) {
+                return None;
+            }
+
+            if dir_entry.path().is_dir() {
+                Some(format!("{name}{}", std::path::MAIN_SEPARATOR))
+            } else {
+                Some(name)
+            }
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+
+    Ok(names)
+}
+
+#[cfg(unix)]
+pub(super) fn get_entry_kind(metadata: &Metadata) -> EntryKind {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    const EXECUTABLE_BITS: u32 = 0o111;
+
+    let file_type = metadata.file_type();
+    if file_type.is_file() {
+        if metadata.permissions().mode() & EXECUTABLE_BITS != 0 {
+            EntryKind::Executable
+        } else {
+            EntryKind::File
+        }
+    } else if file_type.is_dir() {
+        EntryKind::Directory
+    } else if file_type.is_symlink() {
+        EntryKind::Symlink
+    } else if file_type.is_fifo() {
+        EntryKind::Fifo
+    } else if file_type.is_socket() {
+        EntryKind::Socket
+    } else if file_type.is_block_device() {
+        EntryKind::BlockDevice
+    } else if file_type.is_char_device() {
+        EntryKind::CharDevice
+    } else {
+        EntryKind::Unknown
+    }
+}
+
+#[cfg(not(unix))]
+pub(super) fn get_entry_kind(metadata: &Metadata) -> EntryKind {
+    if metadata.is_file() {
+        EntryKind::File
+    } else if metadata.is_dir() {
+        EntryKind::Directory
+    } else if metadata.is_symlink() {
+        EntryKind::Symlink
+    } else {
+        EntryKind::Unknown
+    }
 }
 
 #[cfg(test)]
@@ -51,6 +198,22 @@ mod tests {
     use insta::assert_yaml_snapshot;
     use std::path::PathBuf;
 
+    async fn list_entries_at_directory<P>(path: P) -> anyhow::Result<Vec<Entry>>
+    where
+        P: AsRef<Path>,
+    {
+        let mut entries = vec![];
+        stream_entries_at_directory(path, |chunk, _| {
+            entries.extend(chunk);
+            std::future::ready(())
+        })
+        .await?;
+
+        entries.sort();
+
+        Ok(entries)
+    }
+
     #[tokio::test]
     async fn listing_entries_works() {
         // GIVEN
@@ -77,4 +240,107 @@ mod tests {
         - file-c.txt@
         "#);
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn listing_entries_classifies_executables_and_sockets() {
+        // GIVEN
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().expect("temporary directory should've been created");
+        let script = dir.path().join("run.sh");
+        std::fs::write(&script, "#!/bin/sh\n").expect("file should've been written");
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755))
+            .expect("permissions should've been set");
+        std::fs::write(dir.path().join("notes.txt"), "").expect("file should've been written");
+        let _listener = std::os::unix::net::UnixListener::bind(dir.path().join("atls.sock"))
+            .expect("socket should've been bound");
+
+        // WHEN
+        let entries = list_entries_at_directory(dir.path())
+            .await
+            .expect("entries should've been listed");
+
+        // THEN
+        let paths = entries
+            .into_iter()
+            .map(|e| e.path_str())
+            .collect::<Vec<_>>();
+
+        assert_yaml_snapshot!(paths, @r#"
+        - notes.txt
+        - run.sh*
+        - atls.sock=
+        "#);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn symlinks_to_directories_lead_to_them() {
+        // GIVEN
+        let dir = tempfile::tempdir().expect("temporary directory should've been created");
+        std::fs::create_dir(dir.path().join("target")).expect("directory should've been created");
+        std::fs::write(dir.path().join("file.txt"), "").expect("file should've been written");
+        std::os::unix::fs::symlink("target", dir.path().join("dir-link"))
+            .expect("symlink should've been created");
+        std::os::unix::fs::symlink("file.txt", dir.path().join("file-link"))
+            .expect("symlink should've been created");
+        std::os::unix::fs::symlink("missing", dir.path().join("broken-link"))
+            .expect("symlink should've been created");
+
+        // WHEN
+        let entries = list_entries_at_directory(dir.path())
+            .await
+            .expect("entries should've been listed");
+        let entry_at_path = get_entry_at_path(dir.path().join("dir-link"))
+            .await
+            .expect("entry should've been found");
+
+        // THEN
+        let leads_to_dir = entries
+            .into_iter()
+            .map(|e| format!("{}: {}", e.path_str(), e.leads_to_dir()))
+            .collect::<Vec<_>>();
+
+        assert_yaml_snapshot!(leads_to_dir, @r#"
+        - "target/: true"
+        - "file.txt: false"
+        - "broken-link@: false"
+        - "dir-link@: true"
+        - "file-link@: false"
+        "#);
+        assert!(entry_at_path.leads_to_dir());
+    }
+
+    #[tokio::test]
+    async fn huge_directories_are_streamed_in_chunks() {
+        // GIVEN
+        let dir = tempfile::tempdir().expect("temporary directory should've been created");
+        let num_files = FIRST_CHUNK_SIZE * 3 + 10;
+        for i in 0..num_files {
+            std::fs::write(dir.path().join(format!("file-{i}")), "")
+                .expect("file should've been written");
+        }
+
+        // WHEN
+        let mut chunks = vec![];
+        stream_entries_at_directory(dir.path(), |chunk, last| {
+            chunks.push((chunk.len(), last));
+            std::future::ready(())
+        })
+        .await
+        .expect("entries should've been streamed");
+
+        // THEN
+        let mut chunk_sizes = chunks.iter().map(|(size, _)| *size).collect::<Vec<_>>();
+        chunk_sizes.sort();
+        assert_eq!(
+            chunk_sizes,
+            vec![10, FIRST_CHUNK_SIZE, FIRST_CHUNK_SIZE * 2]
+        );
+        assert_eq!(
+            chunks.iter().map(|(_, last)| *last).collect::<Vec<_>>(),
+            vec![false, false, true]
+        );
+    }
 }
@@ -1,5 +1,19 @@
 mod copy;
+mod external;
+mod git;
 mod list;
+mod preview;
+mod remove;
+mod size;
+mod watch;
+mod workspace;
 
 pub use copy::*;
+pub use external::*;
+pub use git::*;
 pub use list::*;
+pub use preview::*;
+pub use remove::*;
+pub use size::*;
+pub use watch::*;
+pub use workspace::*;
@@ -0,0 +1,121 @@
+use crate::domain::escape_os_str;
+use anyhow::Context;
+use std::io::Read;
+use std::path::Path;
+
+const MAX_PREVIEW_BYTES: u64 = 64 * 1024;
+pub(super) const TAB_REPLACEMENT: &str = "    ";
+
+/// Returns up to `max_lines` lines previewing the file or directory at `path`.
+///
+/// Directories are previewed as a listing of their entries, text files by their first few lines.
+/// Binary files aren't previewed.
+pub fn read_preview(
+    path: &Path,
+    max_lines: usize,
+    show_hidden: bool,
+) -> anyhow::Result<Vec<String>> {
+    let metadata = std::fs::metadata(path).context("couldn't get metadata")?;
+
+    if metadata.is_dir() {
+        return read_directory_preview(path, max_lines, show_hidden);
+    }
+
+    if !metadata.is_file() {
+        return Ok(vec!["(not a regular file)".to_string()]);
+    }
+
+    let mut contents = vec![];
+    std::fs::File::open(path)
+        .context("couldn't open file")?
+        .take(MAX_PREVIEW_BYTES)
+        .read_to_end(&mut contents)
+        .context("couldn't read file")?;
+
+    if contents.contains(&0) {
+        return Ok(vec!["(binary file)".to_string()]);
+    }
+
+    let lines = String::from_utf8_lossy(&contents)
+        .lines()
+        .take(max_lines)
+        .map(|l| l.replace('\t', TAB_REPLACEMENT))
+        .collect::<Vec<_>>();
+
+    if lines.is_empty() {
+        return Ok(vec!["(empty file)".to_string()]);
+    }
+
+    Ok(lines)
+}
+
+fn read_directory_preview(
+    path: &Path,
+    max_lines: usize,
+    show_hidden: bool,
+) -> anyhow::Result<Vec<String>> {
+    let mut names = vec![];
+    for entry in std::fs::read_dir(path).context("couldn't read directory")? {
+        let entry = entry.context("couldn't read directory entry")?;
+        let file_name = entry.file_name();
+        if !show_hidden && file_name.as_encoded_bytes().starts_with(b".") {
+            continue;
+        }
+        let name = escape_os_str(&file_name);
+
+        let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+        names.push((!is_dir, name));
+    }
+
+    if names.is_empty() {
+        return Ok(vec!["(empty directory)".to_string()]);
+    }
+
+    // directories first, like in the explorer
+    names.sort();
+
+    Ok(names
+        .into_iter()
+        .take(max_lines)
+        .map(|(is_file, name)| if is_file { name } else { format!("{name}/") })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_yaml_snapshot;
+
+    #[test]
+    fn previewing_a_directory_lists_directories_first() {
+        // GIVEN
+        let dir = tempfile::tempdir().expect("temporary directory should've been created");
+        std::fs::write(dir.path().join("b.txt"), "b").expect("file should've been written");
+        std::fs::write(dir.path().join(".hidden"), "").expect("file should've been written");
+        std::fs::create_dir(dir.path().join("z")).expect("directory should've been created");
+
+        // WHEN
+        let result = read_preview(dir.path(), 10, false).expect("preview should've been read");
+
+        // THEN
+        assert_yaml_snapshot!(result, @r#"
+        - z/
+        - b.txt
+        "#);
+    }
+
+    #[test]
+    fn previewing_a_binary_file_doesnt_show_its_contents() {
+        // GIVEN
+        let dir = tempfile::tempdir().expect("temporary directory should've been created");
+        let path = dir.path().join("image.png");
+        std::fs::write(&path, [0x89, b'P', b'N', b'G', 0x00, 0x01])
+            .expect("file should've been written");
+
+        // WHEN
+        let result = read_preview(&path, 10, false).expect("preview should've been read");
+
+        // THEN
+        assert_yaml_snapshot!(result, @r#"- (binary file)"#);
+    }
+}
@@ -0,0 +1,35 @@
+use crate::domain::{Entry, EntryKind, display_path};
+use anyhow::Context;
+use tracing::{debug, error};
+
+pub fn trash_entries(entries: &[Entry]) -> anyhow::Result<()> {
+    let paths = entries.iter().map(|e| e.path()).collect::<Vec<_>>();
+
+    debug!("trashing paths: {:?}", &paths);
+
+    trash::delete_all(paths)
+        .context("couldn't move items to trash")
+        .inspect_err(|e| {
+            error!("trashing items failed: {:?}", e);
+        })
+}
+
+pub fn delete_entries(entries: &[Entry]) -> anyhow::Result<()> {
+    debug!("deleting entries: {:?}", entries);
+
+    for entry in entries {
+        // symlinks to directories are removed themselves, rather than what they point to
+        let result = match entry.kind() {
+            EntryKind::Directory => std::fs::remove_dir_all(entry.path()),
+            _ => std::fs::remove_file(entry.path()),
+        };
+
+        result
+            .with_context(|| format!("couldn't delete {}", display_path(entry.path())))
+            .inspect_err(|e| {
+                error!("deleting items failed: {:?}", e);
+            })?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use super::list::get_entry_kind;
+use crate::domain::{Entry, UsageNode, display_path};
+use anyhow::Context;
+use std::fs::{Metadata, ReadDir};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Returns the total size of the files under `path`, recursively, without following symlinks.
+/// Directories that can't be read are skipped.
+///
+/// `on_progress` is called with the size counted so far every now and then. Counting stops as
+/// soon as `cancelled` is set, in which case `None` is returned.
+pub fn compute_dir_size<F>(path: &Path, cancelled: &AtomicBool, mut on_progress: F) -> Option<u64>
+where
+    F: FnMut(u64),
+{
+    let mut size = 0;
+    let mut dirs_to_visit = vec![path.to_path_buf()];
+    let mut last_progress = Instant::now();
+
+    while let Some(dir) = dirs_to_visit.pop() {
+        let Ok(read_dir) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for dir_entry in read_dir.flatten() {
+            if cancelled.load(Ordering::Relaxed) {
+                return None;
+            }
+
+            let Ok(metadata) = dir_entry.metadata() else {
+                continue;
+            };
+
+            if metadata.is_dir() {
+                dirs_to_visit.push(dir_entry.path());
+            } else {
+                size += metadata.len();
+            }
+        }
+
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            on_progress(size);
+            last_progress = Instant::now();
+        }
+    }
+
+    Some(size)
+}
+
+/// Scans the tree under `path` for the disk usage view, without following symlinks. Directories
+/// that can't be read are counted as empty.
+///
+/// `on_progress` is called with the number of entries and the size scanned so far every now and
+/// then. Scanning stops as soon as `cancelled` is set, in which case `None` is returned.
+pub fn scan_disk_usage<F>(
+    path: &Path,
+    cancelled: &AtomicBool,
+    on_progress: F,
+) -> anyhow::Result<Option<UsageNode>>
+where
+    F: FnMut(u64, u64),
+{
+    let metadata =
+        std::fs::metadata(path).with_context(|| format!("couldn't scan {}", display_path(path)))?;
+
+    let mut scan = UsageScan {
+        cancelled,
+        on_progress,
+        num_entries: 0,
+        size: 0,
+        last_progress: Instant::now(),
+    };
+
+    Ok(scan.scan(path.to_path_buf(), &metadata))
+}
+
+struct UsageScan<'a, F> {
+    cancelled: &'a AtomicBool,
+    on_progress: F,
+    num_entries: u64,
+    size: u64,
+    last_progress: Instant,
+}
+
+// a directory whose entries are still being scanned
+struct DirToScan {
+    entry: Entry,
+    // None if the directory couldn't be read
+    read_dir: Option<ReadDir>,
+    children: Vec<UsageNode>,
+}
+
+impl DirToScan {
+    fn new(entry: Entry) -> Self {
+        let read_dir = std::fs::read_dir(entry.path()).ok();
+        Self {
+            entry,
+            read_dir,
+            children: vec![],
+        }
+    }
+
+    fn into_node(self) -> UsageNode {
+        let size = self.children.iter().map(|child| child.size).sum();
+        UsageNode::new(self.entry, size, self.children)
+    }
+}
+
+impl<F> UsageScan<'_, F>
+where
+    F: FnMut(u64, u64),
+{
+    // walks the tree depth first with a stack of its own, so deep trees can't overflow the
+    // thread's stack
+    fn scan(&mut self, path: PathBuf, metadata: &Metadata) -> Option<UsageNode> {
+        let entry = self.count(path, metadata)?;
+        if !metadata.is_dir() {
+            return Some(UsageNode::new(entry, metadata.len(), vec![]));
+        }
+
+        let mut dirs_to_scan = vec![DirToScan::new(entry)];
+        while let Some(dir) = dirs_to_scan.last_mut() {
+            let next = dir
+                .read_dir
+                .as_mut()
+                .and_then(|read_dir| read_dir.find_map(Result::ok));
+
+            let Some(dir_entry) = next else {
+                let node = dirs_to_scan.pop()?.into_node();
+                match dirs_to_scan.last_mut() {
+                    Some(parent) => parent.children.push(node),
+                    None => return Some(node),
+                }
+                continue;
+            };
+
+            let Ok(metadata) = dir_entry.metadata() else {
+                continue;
+            };
+            let entry = self.count(dir_entry.path(), &metadata)?;
+            if metadata.is_dir() {
+                dirs_to_scan.push(DirToScan::new(entry));
+            } else {
+                dir.children
+                    .push(UsageNode::new(entry, metadata.len(), vec![]));
+            }
+        }
+
+        None
+    }
+
+    // returns None if the scan has been cancelled
+    fn count(&mut self, path: PathBuf, metadata: &Metadata) -> Option<Entry> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        self.num_entries += 1;
+        if !metadata.is_dir() {
+            self.size += metadata.len();
+        }
+        if self.last_progress.elapsed() >= PROGRESS_INTERVAL {
+            (self.on_progress)(self.num_entries, self.size);
+            self.last_progress = Instant::now();
+        }
+
+        Some(
+            Entry::new(path, get_entry_kind(metadata))
+                .with_metadata(metadata.len(), metadata.modified().ok()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dir_sizes_include_nested_files() {
+        // GIVEN
+        let dir = tempfile::tempdir().expect("temporary directory should've been created");
+        let nested = dir.path().join("a/b");
+        std::fs::create_dir_all(&nested).expect("directories should've been created");
+        std::fs::write(dir.path().join("one.txt"), [0; 100]).expect("file should've been written");
+        std::fs::write(nested.join("two.txt"), [0; 250]).expect("file should've been written");
+
+        // WHEN
+        let size = compute_dir_size(dir.path(), &AtomicBool::new(false), |_| {});
+        let cancelled_size = compute_dir_size(dir.path(), &AtomicBool::new(true), |_| {});
+
+        // THEN
+        assert_eq!(size, Some(350));
+        assert_eq!(cancelled_size, None);
+    }
+
+    #[test]
+    fn disk_usage_is_scanned_into_a_tree_sorted_by_size() {
+        // GIVEN
+        let dir = tempfile::tempdir().expect("temporary directory should've been created");
+        let nested = dir.path().join("a/b");
+        std::fs::create_dir_all(&nested).expect("directories should've been created");
+        std::fs::write(dir.path().join("one.txt"), [0; 100]).expect("file should've been written");
+        std::fs::write(nested.join("two.txt"), [0; 250]).expect("file should've been written");
+
+        // WHEN
+        let tree = scan_disk_usage(dir.path(), &AtomicBool::new(false), |_, _| {})
+            .expect("scan should've succeeded")
+            .expect("scan shouldn't have been cancelled");
+
+        // THEN
+        let sizes = tree
+            .children
+            .iter()
+            .map(|c| (c.entry.path_str(), c.size))
+            .collect::<Vec<_>>();
+        assert_eq!(tree.size, 350);
+        assert_eq!(
+            sizes,
+            [("a/".to_string(), 250), ("one.txt".to_string(), 100)]
+        );
+    }
+}
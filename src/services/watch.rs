@@ -0,0 +1,245 @@
+use anyhow::Context;
+use notify::{
+    Event, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher, recommended_watcher,
+};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender, channel};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+// changes are collected for this long before being reported, so that a burst of them (eg. from a
+// build writing to target/) results in a single refresh
+const DEBOUNCE_DURATION: Duration = Duration::from_millis(250);
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+enum Backend {
+    Native(RecommendedWatcher),
+    Polling(PollWatcher),
+}
+
+/// Watches directories (non recursively) for changes to their entries.
+///
+/// Uses the platform's native mechanism (eg. inotify), and falls back to polling if that isn't
+/// available or runs out of watches. Directories that can't be watched for other reasons (eg.
+/// missing permissions) are skipped.
+pub struct DirWatcher {
+    backend: Backend,
+    events_tx: Sender<PathBuf>,
+    watched: HashSet<PathBuf>,
+}
+
+impl DirWatcher {
+    /// Creates a watcher that calls `on_change` with the directories that changed, at most once
+    /// every debounce period.
+    pub fn new<F>(on_change: F) -> anyhow::Result<Self>
+    where
+        F: Fn(Vec<PathBuf>) + Send + 'static,
+    {
+        let (events_tx, events_rx) = channel();
+
+        let backend = match recommended_watcher(get_event_handler(events_tx.clone())) {
+            Ok(watcher) => Backend::Native(watcher),
+            Err(e) => {
+                debug!("native file watcher unavailable, polling instead: {e}");
+                Backend::Polling(
+                    get_poll_watcher(events_tx.clone())
+                        .context("couldn't create a file watcher")?,
+                )
+            }
+        };
+
+        std::thread::spawn(move || debounce_changes(events_rx, on_change));
+
+        Ok(Self {
+            backend,
+            events_tx,
+            watched: HashSet::new(),
+        })
+    }
+
+    /// Watches exactly `dirs`, stopping watching directories not in it.
+    pub fn watch_only(&mut self, dirs: &[PathBuf]) {
+        let dirs = dirs.iter().cloned().collect::<HashSet<_>>();
+        let previously_watched = std::mem::take(&mut self.watched);
+
+        for dir in previously_watched.difference(&dirs) {
+            // the directory might not exist anymore, in which case it isn't watched either
+            let _ = self.backend().unwatch(dir);
+        }
+
+        for dir in dirs.difference(&previously_watched) {
+            if let Err(e) = self.backend().watch(dir, RecursiveMode::NonRecursive) {
+                debug!("couldn't watch {:?}: {e}", dir);
+                if matches!(self.backend, Backend::Native(_))
+                    && is_out_of_watches(&e)
+                    && self.fall_back_to_polling(&dirs)
+                {
+                    break;
+                }
+            }
+        }
+
+        self.watched = dirs;
+    }
+
+    fn backend(&mut self) -> &mut dyn Watcher {
+        match &mut self.backend {
+            Backend::Native(watcher) => watcher,
+            Backend::Polling(watcher) => watcher,
+        }
+    }
+
+    // returns whether the native watcher was replaced; if it wasn't, it's kept for the directories
+    // it's still able to watch
+    fn fall_back_to_polling(&mut self, dirs: &HashSet<PathBuf>) -> bool {
+        debug!("falling back to polling for file changes");
+        let watcher = match get_poll_watcher(self.events_tx.clone()) {
+            Ok(w) => w,
+            Err(e) => {
+                debug!("couldn't create a poll watcher: {e}");
+                return false;
+            }
+        };
+        self.backend = Backend::Polling(watcher);
+
+        for dir in dirs {
+            let _ = self.backend().watch(dir, RecursiveMode::NonRecursive);
+        }
+
+        true
+    }
+}
+
+// eg. when the inotify watch limit is reached
+fn is_out_of_watches(error: &notify::Error) -> bool {
+    match &error.kind {
+        notify::ErrorKind::MaxFilesWatch => true,
+        notify::ErrorKind::Io(e) => e.kind() == std::io::ErrorKind::StorageFull,
+        _ => false,
+    }
+}
+
+fn get_poll_watcher(events_tx: Sender<PathBuf>) -> notify::Result<PollWatcher> {
+    let config = notify::Config::default().with_poll_interval(POLL_INTERVAL);
+    PollWatcher::new(get_event_handler(events_tx), config)
+}
+
+// forwards the directories that might've been affected by an event; the paths in an event are
+// either the watched directory itself or entries in it
+fn get_event_handler(
+    events_tx: Sender<PathBuf>,
+) -> impl FnMut(notify::Result<Event>) + Send + 'static {
+    move |result| {
+        let Ok(event) = result else {
+            return;
+        };
+
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+
+        for path in event.paths {
+            if let Some(parent) = path.parent() {
+                let _ = events_tx.send(parent.to_path_buf());
+            }
+            let _ = events_tx.send(path);
+        }
+    }
+}
+
+fn debounce_changes<F>(events_rx: Receiver<PathBuf>, on_change: F)
+where
+    F: Fn(Vec<PathBuf>),
+{
+    while let Ok(path) = events_rx.recv() {
+        let mut changed = HashSet::from([path]);
+        let deadline = Instant::now() + DEBOUNCE_DURATION;
+
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match events_rx.recv_timeout(timeout) {
+                Ok(path) => {
+                    changed.insert(path);
+                }
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+
+        on_change(changed.into_iter().collect());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_in_watched_directories_are_reported() {
+        // GIVEN
+        let dir = tempfile::tempdir().expect("temporary directory should've been created");
+        let dir_path = dir.path().canonicalize().expect("path should be canonical");
+        let (changes_tx, changes_rx) = channel();
+        let mut watcher = DirWatcher::new(move |dirs| {
+            let _ = changes_tx.send(dirs);
+        })
+        .expect("watcher should've been created");
+        watcher.watch_only(std::slice::from_ref(&dir_path));
+        let files = ["a.txt", "b.txt", "c.txt"].map(|name| dir_path.join(name));
+
+        // WHEN
+        for file in &files {
+            std::fs::write(file, "content").expect("file should've been written");
+        }
+
+        // THEN
+        // slow machines (or polling) can take a while to report changes, so batches are collected
+        // till every file shows up, rather than for a fixed amount of time; how many batches that
+        // takes depends on timing, so it's left to the debouncing test below
+        let deadline = Instant::now() + Duration::from_secs(30);
+        let mut batches = vec![];
+        while !files
+            .iter()
+            .all(|f| batches.iter().any(|b: &Vec<PathBuf>| b.contains(f)))
+        {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let batch = changes_rx
+                .recv_timeout(timeout)
+                .expect("changes should've been reported");
+            batches.push(batch);
+        }
+
+        assert!(batches.iter().any(|b| b.contains(&dir_path)));
+    }
+
+    #[test]
+    fn changes_arriving_together_are_reported_in_one_batch() {
+        // GIVEN
+        let (events_tx, events_rx) = channel();
+        let (changes_tx, changes_rx) = channel();
+        let paths = ["/a", "/a/b.txt", "/a", "/c"].map(PathBuf::from);
+        for path in &paths {
+            events_tx
+                .send(path.clone())
+                .expect("event should've been sent");
+        }
+
+        // WHEN
+        let debouncer = std::thread::spawn(move || {
+            debounce_changes(events_rx, move |dirs| {
+                let _ = changes_tx.send(dirs);
+            })
+        });
+        let mut batch = changes_rx
+            .recv_timeout(Duration::from_secs(10))
+            .expect("changes should've been reported");
+        drop(events_tx);
+        debouncer.join().expect("debouncer should've stopped");
+
+        // THEN
+        batch.sort();
+        assert_eq!(batch, ["/a", "/a/b.txt", "/c"].map(PathBuf::from));
+        assert!(changes_rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,118 @@
+use crate::domain::WorkspaceState;
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+const WORKSPACES_DIR: &str = "workspaces";
+
+pub fn save_workspace_state<P>(
+    state_dir: P,
+    name: &str,
+    state: &WorkspaceState,
+) -> anyhow::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let path = get_workspace_file_path(state_dir, name);
+    debug!("saving workspace state to {:?}", &path);
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).context("couldn't create workspaces directory")?;
+    }
+
+    let contents =
+        serde_json::to_string_pretty(state).context("couldn't serialize workspace state")?;
+
+    // write to a temporary file first so that a crash mid-write doesn't leave a corrupt state file behind
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, contents).context("couldn't write workspace state")?;
+    std::fs::rename(&tmp_path, &path).context("couldn't persist workspace state")?;
+
+    Ok(())
+}
+
+pub fn load_workspace_state<P>(state_dir: P, name: &str) -> anyhow::Result<Option<WorkspaceState>>
+where
+    P: AsRef<Path>,
+{
+    let path = get_workspace_file_path(state_dir, name);
+    debug!("loading workspace state from {:?}", &path);
+
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).context("couldn't read workspace state"),
+    };
+
+    let state = serde_json::from_str(&contents)
+        .with_context(|| format!("workspace state at {} is malformed", path.to_string_lossy()))?;
+
+    Ok(Some(state))
+}
+
+fn get_workspace_file_path<P>(state_dir: P, name: &str) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    state_dir
+        .as_ref()
+        .join(WORKSPACES_DIR)
+        .join(format!("{name}.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{SessionState, SortMode};
+    use std::collections::HashMap;
+
+    #[test]
+    fn saved_workspace_state_can_be_loaded_back() {
+        // GIVEN
+        let state_dir = tempfile::tempdir().expect("temporary directory should've been created");
+        let state = WorkspaceState {
+            sessions: vec![
+                Some(SessionState {
+                    path: PathBuf::from("/home/user/atls"),
+                    selected: Some(PathBuf::from("/home/user/atls/src")),
+                }),
+                None,
+                Some(SessionState {
+                    path: PathBuf::from("/tmp"),
+                    selected: None,
+                }),
+                None,
+            ],
+            current_session_index: 2,
+            last_selections: HashMap::from([(
+                PathBuf::from("/home/user"),
+                PathBuf::from("/home/user/atls"),
+            )]),
+            marked_paths: vec![PathBuf::from("/home/user/atls/Cargo.toml")],
+            show_hidden: Some(true),
+            sort_mode: Some(SortMode::Modified),
+        };
+
+        // WHEN
+        save_workspace_state(state_dir.path(), "work", &state)
+            .expect("workspace state should've been saved");
+        let loaded = load_workspace_state(state_dir.path(), "work")
+            .expect("workspace state should've been loaded");
+
+        // THEN
+        assert_eq!(loaded, Some(state));
+    }
+
+    #[test]
+    fn loading_a_missing_workspace_returns_none() {
+        // GIVEN
+        let state_dir = tempfile::tempdir().expect("temporary directory should've been created");
+
+        // WHEN
+        let loaded = load_workspace_state(state_dir.path(), "absent")
+            .expect("loading workspace state shouldn't fail");
+
+        // THEN
+        assert!(loaded.is_none());
+    }
+}
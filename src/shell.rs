@@ -0,0 +1,91 @@
+const POSIX_INIT_RAW: &str = include_str!("static/init.sh");
+const FISH_INIT_RAW: &str = include_str!("static/init.fish");
+const FUNCTION_PLACEHOLDER: &str = "{{function}}";
+
+pub const DEFAULT_FUNCTION_NAME: &str = "atlscd";
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Returns a shell function that runs atls, and changes the shell's working directory to the one
+/// atls was in when it exited.
+pub fn get_shell_init(shell: Shell, function_name: &str) -> String {
+    let template = match shell {
+        Shell::Bash | Shell::Zsh => POSIX_INIT_RAW,
+        Shell::Fish => FISH_INIT_RAW,
+    };
+
+    template.replace(FUNCTION_PLACEHOLDER, function_name)
+}
+
+pub fn validate_function_name(name: &str) -> Result<String, String> {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+
+    if !valid {
+        return Err(
+            "function name must start with a letter or '_', and can only contain ascii letters, digits, '-' and '_'"
+                .to_string(),
+        );
+    }
+
+    Ok(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+
+    #[test]
+    fn posix_init_uses_the_provided_function_name() {
+        // GIVEN
+        // WHEN
+        let init = get_shell_init(Shell::Bash, "a");
+
+        // THEN
+        assert_snapshot!(init, @r#"
+        a() {
+            local cwd_file cwd exit_code
+            cwd_file="$(mktemp -t atls-cwd.XXXXXX)" || return
+            command atls --cwd-file "$cwd_file" "$@"
+            exit_code=$?
+            cwd="$(command cat -- "$cwd_file")"
+            command rm -f -- "$cwd_file"
+            if [ -n "$cwd" ] && [ "$cwd" != "$PWD" ]; then
+                builtin cd -- "$cwd" || return
+            fi
+            return $exit_code
+        }
+        "#);
+    }
+
+    #[test]
+    fn fish_init_uses_the_provided_function_name() {
+        // GIVEN
+        // WHEN
+        let init = get_shell_init(Shell::Fish, "a");
+
+        // THEN
+        assert_snapshot!(init, @r#"
+        function a
+            set -l cwd_file (mktemp -t atls-cwd.XXXXXX); or return
+            command atls --cwd-file $cwd_file $argv
+            set -l exit_code $status
+            set -l cwd (command cat -- $cwd_file)
+            command rm -f -- $cwd_file
+            if test -n "$cwd"; and test "$cwd" != "$PWD"
+                builtin cd -- $cwd; or return
+            end
+            return $exit_code
+        end
+        "#);
+    }
+}
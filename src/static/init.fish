@@ -0,0 +1,11 @@
+function {{function}}
+    set -l cwd_file (mktemp -t atls-cwd.XXXXXX); or return
+    command atls --cwd-file $cwd_file $argv
+    set -l exit_code $status
+    set -l cwd (command cat -- $cwd_file)
+    command rm -f -- $cwd_file
+    if test -n "$cwd"; and test "$cwd" != "$PWD"
+        builtin cd -- $cwd; or return
+    end
+    return $exit_code
+end
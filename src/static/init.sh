@@ -0,0 +1,12 @@
+{{function}}() {
+    local cwd_file cwd exit_code
+    cwd_file="$(mktemp -t atls-cwd.XXXXXX)" || return
+    command atls --cwd-file "$cwd_file" "$@"
+    exit_code=$?
+    cwd="$(command cat -- "$cwd_file")"
+    command rm -f -- "$cwd_file"
+    if [ -n "$cwd" ] && [ "$cwd" != "$PWD" ]; then
+        builtin cd -- "$cwd" || return
+    fi
+    return $exit_code
+}
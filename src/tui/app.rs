@@ -1,122 +1,319 @@
-use super::cmd::{Cmd, handle_command};
+use super::cmd::{
+    Cmd, handle_command, spawn_dir_read, spawn_dir_size_calculation, spawn_disk_usage_scan,
+};
 use super::common::*;
 use super::model::*;
 use super::msg::{Msg, get_event_handling_msg};
-use super::update::update;
+use super::terminal::{AppTerminal, init_terminal, restore_terminal, resume_terminal};
+use super::update::{get_read_dir_cmd, update};
 use super::view::view;
-use ratatui::Terminal;
-use ratatui::backend::CrosstermBackend;
-use ratatui::crossterm::event::poll;
+use crate::domain::{Entry, ExternalCommand, WorkspaceState};
+use crate::services;
+use crate::services::DirWatcher;
+use futures_core::Stream;
+use ratatui::crossterm::event::{Event, EventStream};
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 use tokio::sync::mpsc;
 use tokio::sync::mpsc::{Receiver, Sender};
+use tokio::task::AbortHandle;
+use tokio::time::MissedTickBehavior;
 
-const EVENT_POLL_DURATION_MS: u64 = 16;
+// the view is redrawn at most once per frame, however many messages come in during it
+const FRAME_DURATION: Duration = Duration::from_millis(16);
+// background tasks wait for room in the channel when it's full, rather than piling up messages
+// faster than they can be handled
+const EVENT_CHANNEL_CAPACITY: usize = 256;
 
-pub async fn run(root: PathBuf) -> anyhow::Result<()> {
-    let mut tui = AppTui::new(root)?;
+pub async fn run(options: StartupOptions) -> anyhow::Result<Outcome> {
+    let workspace_state = match options.workspace_state {
+        Some(state) => Some(prepare_workspace_state(state).await),
+        None => None,
+    };
+
+    let mut tui = AppTui::new(
+        options.paths,
+        options.selections,
+        workspace_state,
+        options.settings,
+    )?;
     tui.run().await
 }
 
+// drops sessions whose directories don't exist anymore, and resolves marked paths into entries
+async fn prepare_workspace_state(mut state: WorkspaceState) -> (WorkspaceState, Vec<Entry>) {
+    for session in state.sessions.iter_mut() {
+        if let Some(s) = session
+            && !tokio::fs::metadata(&s.path).await.is_ok_and(|m| m.is_dir())
+        {
+            *session = None;
+        }
+    }
+
+    let mut marked_entries = vec![];
+    for path in state.marked_paths.drain(..) {
+        if let Some(entry) = services::get_entry_at_path(&path).await {
+            marked_entries.push(entry);
+        }
+    }
+
+    (state, marked_entries)
+}
+
+// what the event loop does next
+enum Next {
+    Message(Msg),
+    TerminalEvent(Option<std::io::Result<Event>>),
+    Render,
+}
+
 struct AppTui {
-    pub(super) terminal: Terminal<CrosstermBackend<std::io::Stdout>>,
+    pub(super) terminal: AppTerminal,
     pub(super) event_tx: Sender<Msg>,
     pub(super) event_rx: Receiver<Msg>,
     pub(super) model: Model,
+    terminal_events: EventStream,
+    watcher: DirWatcher,
+    // whether mouse events are captured, rather than left to the terminal
+    capture_mouse: bool,
+    // directory reads in progress, by session index
+    dir_read_tasks: HashMap<usize, AbortHandle>,
+    // set to cancel the calculation of directory sizes in progress
+    dir_size_cancellation: Option<Arc<AtomicBool>>,
+    // set to cancel the disk usage scan in progress
+    disk_usage_cancellation: Option<Arc<AtomicBool>>,
 }
 
 impl AppTui {
-    pub fn new(root: PathBuf) -> anyhow::Result<Self> {
-        let terminal = ratatui::try_init()?;
-        let (event_tx, event_rx) = mpsc::channel(10);
+    pub fn new(
+        paths: Vec<PathBuf>,
+        selections: Vec<PathBuf>,
+        workspace_state: Option<(WorkspaceState, Vec<Entry>)>,
+        settings: Settings,
+    ) -> anyhow::Result<Self> {
+        // terminal events are read straight off the terminal, so only background results go
+        // through the channel; nothing is dropped when it's full, senders wait for room instead
+        let (event_tx, event_rx) = mpsc::channel(EVENT_CHANNEL_CAPACITY);
+
+        let watcher_tx = event_tx.clone();
+        // runs on the watcher's own thread, which can block till there's room in the channel
+        let watcher = DirWatcher::new(move |dirs| {
+            let _ = watcher_tx.blocking_send(Msg::DirectoriesChanged(dirs));
+        })?;
 
+        let capture_mouse = !settings.disable_mouse;
+        let terminal = init_terminal(capture_mouse)?;
         let (width, height) = ratatui::crossterm::terminal::size()?;
 
         let terminal_dimensions = TerminalDimensions { width, height };
 
         let debug = std::env::var("ATLS_DEBUG").unwrap_or_default().trim() == "1";
 
-        let model = Model::new(root, terminal_dimensions, debug);
+        let mut model = Model::new(paths, settings, terminal_dimensions, debug);
+        model.select_paths_on_read(selections);
+        if let Some((state, marked_entries)) = workspace_state {
+            model.restore_workspace_state(state, marked_entries);
+        }
 
         Ok(Self {
             terminal,
             event_tx,
             event_rx,
             model,
+            terminal_events: EventStream::new(),
+            watcher,
+            capture_mouse,
+            dir_read_tasks: HashMap::new(),
+            dir_size_cancellation: None,
+            disk_usage_cancellation: None,
         })
     }
 
-    pub async fn run(&mut self) -> anyhow::Result<()> {
+    pub async fn run(&mut self) -> anyhow::Result<Outcome> {
         let _ = self.terminal.clear();
 
         // first render
         self.terminal.draw(|f| view(&mut self.model, f))?;
         self.model.render_counter += 1;
 
-        let mut initial_cmds = vec![];
-        match self.model.current_session() {
-            Session::Uninitialized => {}
-            Session::Initialized {
-                path,
-                entries: _,
-                state: _,
-            } => {
-                initial_cmds.push(Cmd::ReadDir((
-                    SessionInfo {
-                        index: 0,
-                        path: path.clone(),
-                    },
-                    false,
-                )));
+        let sessions_to_read = self
+            .model
+            .sessions
+            .iter()
+            .enumerate()
+            .filter_map(|(index, session)| match session {
+                Session::Uninitialized => None,
+                Session::Initialized { path, .. } => Some(SessionInfo {
+                    index,
+                    path: path.clone(),
+                }),
+            })
+            .collect::<Vec<_>>();
+
+        for session_info in sessions_to_read {
+            if let Some(cmd) = get_read_dir_cmd(&mut self.model, session_info, true) {
+                self.handle_command(cmd).await?;
             }
         }
 
-        for cmd in initial_cmds {
-            handle_command(cmd.clone(), self.event_tx.clone()).await;
-        }
+        let mut frames = tokio::time::interval(FRAME_DURATION);
+        frames.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut needs_render = false;
 
         loop {
-            tokio::select! {
-                Some(message) = self.event_rx.recv() => {
-                    let cmds = update(&mut self.model, message);
+            let next = tokio::select! {
+                Some(message) = self.event_rx.recv() => Next::Message(message),
+                event = next_terminal_event(&mut self.terminal_events) => Next::TerminalEvent(event),
+                _ = frames.tick(), if needs_render => Next::Render,
+            };
 
-                    if self.model.running_state == RunningState::Done {
-                        break;
+            let message = match next {
+                Next::Message(message) => message,
+                Next::TerminalEvent(Some(event)) => {
+                    self.model.event_counter += 1;
+                    match get_event_handling_msg(&mut self.model, event?) {
+                        Some(message) => message,
+                        None => continue,
                     }
+                }
+                Next::TerminalEvent(None) => {
+                    return Err(anyhow::anyhow!("terminal events stopped coming in"));
+                }
+                Next::Render => {
+                    self.terminal.draw(|f| view(&mut self.model, f))?;
+                    self.model.render_counter += 1;
+                    needs_render = false;
+                    continue;
+                }
+            };
 
-                        self.terminal.draw(|f| view(&mut self.model, f))?;
-                        self.model.render_counter += 1;
+            let cmds = update(&mut self.model, message);
+            if self.model.running_state == RunningState::Done {
+                break;
+            }
+            needs_render = true;
 
-                    for cmd in cmds {
-                        handle_command(cmd.clone(), self.event_tx.clone()).await;
-                    }
-                }
+            for cmd in cmds {
+                self.handle_command(cmd).await?;
+            }
+        }
 
-                Ok(ready) = tokio::task::spawn_blocking(|| poll(Duration::from_millis(EVENT_POLL_DURATION_MS))) => {
-                    match ready {
-                        Ok(true) => {
-                            // non blocking read since poll returned Ok(true)
-                            let event = ratatui::crossterm::event::read()?;
-                            self.model.event_counter += 1;
-                            if let Some(handling_msg) = get_event_handling_msg(&self.model, event) {
-                                self.event_tx.try_send(handling_msg)?;
-                            }
-                        }
-                        Ok(false) => continue,
-                        Err(e) => {
-                                return Err(anyhow::anyhow!(e));
-                        }
-                    }
+        self.exit()?;
+
+        Ok(Outcome {
+            workspace_state: self.model.workspace_state(),
+            final_dir: self.model.final_dir(),
+            chosen_paths: self.model.chosen_paths.take(),
+        })
+    }
+
+    async fn handle_command(&mut self, cmd: Cmd) -> anyhow::Result<()> {
+        match cmd {
+            Cmd::ReadDir {
+                session_info,
+                read_id,
+            } => {
+                let index = session_info.index;
+                let task = spawn_dir_read(session_info, read_id, self.event_tx.clone());
+                // a newer read for the session supersedes the one in progress
+                if let Some(superseded) = self.dir_read_tasks.insert(index, task.abort_handle()) {
+                    superseded.abort();
                 }
             }
+            Cmd::CancelDirRead(index) => {
+                if let Some(task) = self.dir_read_tasks.remove(&index) {
+                    task.abort();
+                }
+            }
+            Cmd::RunInTerminal {
+                command,
+                pause_after,
+                refresh,
+            } => self.run_in_terminal(command, pause_after, refresh).await?,
+            Cmd::WatchDirs(dirs) => self.watcher.watch_only(&dirs),
+            Cmd::ComputeDirSizes {
+                calculation_id,
+                dirs,
+            } => {
+                cancel(&mut self.dir_size_cancellation);
+                let cancelled = Arc::new(AtomicBool::new(false));
+                spawn_dir_size_calculation(
+                    calculation_id,
+                    dirs,
+                    Arc::clone(&cancelled),
+                    self.event_tx.clone(),
+                );
+                self.dir_size_cancellation = Some(cancelled);
+            }
+            Cmd::CancelDirSizes => cancel(&mut self.dir_size_cancellation),
+            Cmd::ScanDiskUsage { scan_id, path } => {
+                cancel(&mut self.disk_usage_cancellation);
+                let cancelled = Arc::new(AtomicBool::new(false));
+                spawn_disk_usage_scan(scan_id, path, Arc::clone(&cancelled), self.event_tx.clone());
+                self.disk_usage_cancellation = Some(cancelled);
+            }
+            Cmd::CancelDiskUsageScan => cancel(&mut self.disk_usage_cancellation),
+            cmd => handle_command(cmd, self.event_tx.clone()).await,
         }
 
-        self.exit()
+        Ok(())
+    }
+
+    // suspends the TUI while the command runs, since it needs the terminal to itself
+    async fn run_in_terminal(
+        &mut self,
+        command: ExternalCommand,
+        pause_after: bool,
+        refresh: bool,
+    ) -> anyhow::Result<()> {
+        // the command needs all of the terminal's input; replacing the event stream stops the
+        // current one from reading it, and the new one only starts reading once it's polled
+        self.terminal_events = EventStream::new();
+
+        restore_terminal()?;
+        // the command is waited on off the runtime's threads, so that background tasks (eg.
+        // directory reads) keep making progress in the meantime
+        let (result, waited) = tokio::task::spawn_blocking(move || {
+            let result = services::run_external_command(&command);
+            let waited = if pause_after {
+                services::wait_for_enter()
+            } else {
+                Ok(())
+            };
+            (result, waited)
+        })
+        .await?;
+        waited?;
+        resume_terminal(&mut self.terminal, self.capture_mouse)?;
+
+        // the event loop is the channel's only reader, so it can't wait for room in it itself
+        let event_tx = self.event_tx.clone();
+        tokio::spawn(async move {
+            let _ = event_tx
+                .send(Msg::ExternalCommandFinished { result, refresh })
+                .await;
+        });
+
+        Ok(())
     }
 
     fn exit(&mut self) -> anyhow::Result<()> {
-        ratatui::try_restore()?;
+        restore_terminal()?;
         Ok(())
     }
 }
+
+async fn next_terminal_event(events: &mut EventStream) -> Option<std::io::Result<Event>> {
+    std::future::poll_fn(|cx| Pin::new(&mut *events).poll_next(cx)).await
+}
+
+// cancels the background computation `cancellation` belongs to, if there's one in progress
+fn cancel(cancellation: &mut Option<Arc<AtomicBool>>) {
+    if let Some(cancelled) = cancellation.take() {
+        cancelled.store(true, Ordering::Relaxed);
+    }
+}
@@ -1,51 +1,376 @@
 use super::common::SessionInfo;
 
+use super::model::{DirSize, DirToSize, PreviewRequest};
 use super::msg::Msg;
-use crate::domain::FSOperation;
+use crate::domain::{ExternalCommand, FSOperation, GitAction};
 use crate::services;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 use tokio::sync::mpsc::Sender;
+use tokio::task::JoinHandle;
 
 #[derive(Clone, Debug)]
 pub enum Cmd {
     RunFSOperation(FSOperation),
-    ReadDir((SessionInfo, bool)),
+    // handled by the app itself, since it aborts reads that have been superseded
+    ReadDir {
+        session_info: SessionInfo,
+        read_id: u64,
+    },
+    // handled by the app itself, for the same reason; sent when a session is closed
+    CancelDirRead(usize),
+    // handled by the app itself, since the terminal needs to be handed over to the command
+    RunInTerminal {
+        command: ExternalCommand,
+        pause_after: bool,
+        refresh: bool,
+    },
+    RunCapturingOutput {
+        command_line: String,
+        command: ExternalCommand,
+        refresh: bool,
+    },
+    SpawnDetached(ExternalCommand),
+    LoadPreview {
+        request: PreviewRequest,
+        max_lines: usize,
+        show_hidden: bool,
+    },
+    RunGitAction {
+        action: GitAction,
+        paths: Vec<PathBuf>,
+    },
+    ReadGitStatus {
+        dir: PathBuf,
+        request_id: u64,
+    },
+    // checks whether a path typed into the go to prompt is a directory, or a file to select
+    CheckGoToPath(PathBuf),
+    ReadPathCompletions {
+        // the prompt's input the completions are for
+        input: String,
+        dir: PathBuf,
+        prefix: String,
+        show_hidden: bool,
+    },
+    // handled by the app itself, since it owns the watcher
+    WatchDirs(Vec<PathBuf>),
+    // handled by the app itself, since it cancels calculations that aren't needed anymore
+    ComputeDirSizes {
+        calculation_id: u64,
+        dirs: Vec<DirToSize>,
+    },
+    CancelDirSizes,
+    // handled by the app itself, since it cancels scans that aren't needed anymore
+    ScanDiskUsage {
+        scan_id: u64,
+        path: PathBuf,
+    },
+    CancelDiskUsageScan,
 }
 
 pub async fn handle_command(command: Cmd, event_tx: Sender<Msg>) {
     match command {
         Cmd::RunFSOperation(operation) => {
             tokio::task::spawn_blocking(move || {
-                let result = match operation {
-                    FSOperation::Copy { items, destination } => {
+                let (result, removing) = match operation {
+                    FSOperation::Copy { items, destination } => (
                         services::copy_entries_to_destination(
                             items.as_slice(),
                             destination.as_path(),
-                        )
-                    }
-                    FSOperation::Move { items, destination } => {
+                        ),
+                        vec![],
+                    ),
+                    FSOperation::Move { items, destination } => (
                         services::move_entries_to_destination(
                             items.as_slice(),
                             destination.as_path(),
-                        )
+                        ),
+                        vec![],
+                    ),
+                    FSOperation::Trash { items } => {
+                        (services::trash_entries(items.as_slice()), items)
+                    }
+                    FSOperation::Delete { items } => {
+                        (services::delete_entries(items.as_slice()), items)
                     }
                 };
 
-                let _ = event_tx.try_send(Msg::FSOperationFinished(result));
+                // some of the entries might've been removed even if the operation failed midway
+                let removed = removing
+                    .iter()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.symlink_metadata().is_err())
+                    .map(Path::to_path_buf)
+                    .collect();
+
+                let _ = event_tx.blocking_send(Msg::FSOperationFinished { result, removed });
+            });
+        }
+        Cmd::ReadDir { .. }
+        | Cmd::CancelDirRead(_)
+        | Cmd::RunInTerminal { .. }
+        | Cmd::WatchDirs(_)
+        | Cmd::ComputeDirSizes { .. }
+        | Cmd::CancelDirSizes
+        | Cmd::ScanDiskUsage { .. }
+        | Cmd::CancelDiskUsageScan => {}
+        Cmd::RunCapturingOutput {
+            command_line,
+            command,
+            refresh,
+        } => {
+            tokio::task::spawn_blocking(move || {
+                let result = services::run_external_command_capturing_output(&command);
+                let _ = event_tx.blocking_send(Msg::CommandOutputCaptured {
+                    command_line,
+                    result,
+                    refresh,
+                });
+            });
+        }
+        Cmd::LoadPreview {
+            request,
+            max_lines,
+            show_hidden,
+        } => {
+            tokio::task::spawn_blocking(move || {
+                let result = if request.diff {
+                    services::read_git_diff(&request.path, max_lines)
+                } else {
+                    services::read_preview(&request.path, max_lines, show_hidden)
+                };
+                let _ = event_tx.blocking_send(Msg::PreviewLoaded { request, result });
+            });
+        }
+        Cmd::RunGitAction { action, paths } => {
+            tokio::task::spawn_blocking(move || {
+                let result = services::run_git_action(action, &paths);
+                let _ = event_tx.blocking_send(Msg::GitActionFinished(result));
+            });
+        }
+        Cmd::ReadGitStatus { dir, request_id } => {
+            tokio::task::spawn_blocking(move || {
+                let status = services::read_git_status(&dir);
+                let _ = event_tx.blocking_send(Msg::GitStatusRead {
+                    dir,
+                    request_id,
+                    status,
+                });
+            });
+        }
+        Cmd::CheckGoToPath(path) => {
+            tokio::task::spawn_blocking(move || {
+                // the path might be on a slow mount, so it's not checked on the event loop
+                let is_dir = std::fs::metadata(&path).map(|metadata| metadata.is_dir());
+                let _ = event_tx.blocking_send(Msg::GoToPathChecked { path, is_dir });
+            });
+        }
+        Cmd::ReadPathCompletions {
+            input,
+            dir,
+            prefix,
+            show_hidden,
+        } => {
+            tokio::task::spawn_blocking(move || {
+                let result = services::read_path_completions(&dir, &prefix, show_hidden);
+                let _ = event_tx.blocking_send(Msg::PathCompletionsRead { input, result });
+            });
+        }
+        Cmd::SpawnDetached(command) => {
+            tokio::task::spawn_blocking(move || {
+                if let Err(e) = services::spawn_detached_command(&command) {
+                    let _ = event_tx.blocking_send(Msg::ExternalCommandFinished {
+                        result: Err(e),
+                        refresh: false,
+                    });
+                }
             });
         }
-        Cmd::ReadDir((session_info, navigated_to)) => {
-            tokio::spawn(async move {
-                let msg = match services::list_entries_at_directory(&session_info.path).await {
-                    Ok(r) => Msg::DirectoryRead {
+    }
+}
+
+/// Reads a session's directory in the background, sending its entries in chunks.
+pub fn spawn_dir_read(
+    session_info: SessionInfo,
+    read_id: u64,
+    event_tx: Sender<Msg>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let path = session_info.path.clone();
+        let result = services::stream_entries_at_directory(&path, |entries, done| {
+            let event_tx = event_tx.clone();
+            let session_info = session_info.clone();
+            async move {
+                let _ = event_tx
+                    .send(Msg::DirectoryRead {
                         session_info,
-                        entries: r,
-                        navigated_to,
+                        read_id,
+                        entries,
+                        done,
+                    })
+                    .await;
+            }
+        })
+        .await;
+
+        if let Err(e) = result {
+            let _ = event_tx
+                .send(Msg::ReadingDirFailed {
+                    session_index: session_info.index,
+                    read_id,
+                    error: e.to_string(),
+                })
+                .await;
+        }
+    })
+}
+
+/// Computes the sizes of directories one after the other in the background, sending partial sizes
+/// as they're computed, until `cancelled` is set.
+pub fn spawn_dir_size_calculation(
+    calculation_id: u64,
+    dirs: Vec<DirToSize>,
+    cancelled: Arc<AtomicBool>,
+    event_tx: Sender<Msg>,
+) {
+    tokio::task::spawn_blocking(move || {
+        for (path, modified) in dirs {
+            let send_size = |bytes, complete| {
+                let _ = event_tx.blocking_send(Msg::DirSizeComputed {
+                    calculation_id,
+                    path: path.clone(),
+                    size: DirSize {
+                        bytes,
+                        modified,
+                        complete,
                     },
-                    Err(e) => Msg::ReadingDirFailed(e.to_string()),
-                };
+                });
+            };
+
+            match services::compute_dir_size(&path, &cancelled, |bytes| send_size(bytes, false)) {
+                Some(bytes) => send_size(bytes, true),
+                None => return,
+            }
+        }
+    });
+}
 
-                let _ = event_tx.try_send(msg);
+/// Scans the tree under `path` for the disk usage view in the background, sending progress every
+/// now and then, until `cancelled` is set.
+pub fn spawn_disk_usage_scan(
+    scan_id: u64,
+    path: PathBuf,
+    cancelled: Arc<AtomicBool>,
+    event_tx: Sender<Msg>,
+) {
+    tokio::task::spawn_blocking(move || {
+        let result = services::scan_disk_usage(&path, &cancelled, |num_scanned, size_scanned| {
+            let _ = event_tx.blocking_send(Msg::DiskUsageScanProgress {
+                scan_id,
+                num_scanned,
+                size_scanned,
             });
+        });
+
+        // the scan was cancelled
+        let Some(result) = result.transpose() else {
+            return;
+        };
+        let _ = event_tx.blocking_send(Msg::DiskUsageScanned { scan_id, result });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn directory_reads_wait_for_room_in_the_channel_rather_than_dropping_entries() {
+        // GIVEN
+        let dir = tempfile::tempdir().expect("temporary directory should've been created");
+        let num_files = 2000;
+        for i in 0..num_files {
+            std::fs::write(dir.path().join(format!("file-{i}")), "")
+                .expect("file should've been written");
+        }
+        let (event_tx, mut event_rx) = mpsc::channel(1);
+        let session_info = SessionInfo {
+            index: 0,
+            path: dir.path().to_path_buf(),
+        };
+
+        // WHEN
+        let read = spawn_dir_read(session_info, 7, event_tx);
+        let mut num_entries = 0;
+        let mut chunks_done = vec![];
+        while let Some(msg) = event_rx.recv().await {
+            // a slow reader, so that the channel fills up
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            match msg {
+                Msg::DirectoryRead {
+                    read_id,
+                    entries,
+                    done,
+                    ..
+                } => {
+                    assert_eq!(read_id, 7);
+                    num_entries += entries.len();
+                    chunks_done.push(done);
+                }
+                other => panic!("unexpected message: {other:?}"),
+            }
+        }
+
+        // THEN
+        read.await.expect("read should've finished");
+        assert_eq!(num_entries, num_files);
+        assert!(chunks_done.len() > 1, "entries should've come in chunks");
+        assert_eq!(chunks_done.iter().filter(|done| **done).count(), 1);
+        assert_eq!(chunks_done.last(), Some(&true));
+    }
+
+    #[tokio::test]
+    async fn blocking_tasks_wait_for_room_in_the_channel_rather_than_dropping_results() {
+        // GIVEN
+        let dir = tempfile::tempdir().expect("temporary directory should've been created");
+        let dirs = ["a", "b", "c"]
+            .map(|name| {
+                let path = dir.path().join(name);
+                std::fs::create_dir(&path).expect("directory should've been created");
+                std::fs::write(path.join("file"), "content").expect("file should've been written");
+                (path, None)
+            })
+            .to_vec();
+        let (event_tx, mut event_rx) = mpsc::channel(1);
+
+        // WHEN
+        spawn_dir_size_calculation(3, dirs, Arc::new(AtomicBool::new(false)), event_tx);
+        let mut completed = vec![];
+        while let Some(msg) = event_rx.recv().await {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            match msg {
+                Msg::DirSizeComputed {
+                    calculation_id,
+                    path,
+                    size,
+                } => {
+                    assert_eq!(calculation_id, 3);
+                    if size.complete {
+                        completed.push(path.file_name().map(|n| n.to_string_lossy().to_string()));
+                    }
+                }
+                other => panic!("unexpected message: {other:?}"),
+            }
         }
+
+        // THEN
+        assert_eq!(
+            completed,
+            [Some("a".into()), Some("b".into()), Some("c".into())]
+        );
     }
 }
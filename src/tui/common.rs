@@ -1,13 +1,69 @@
+use super::keymap::Keymap;
+use super::ls_colors::LsColors;
+use super::theme::Theme;
+use crate::domain::{CustomCommand, ListingOptions, Opener, WorkspaceState};
+use serde::Deserialize;
 use std::path::PathBuf;
 
 pub const MAX_NUM_SESSIONS: usize = 4;
 pub const MIN_TERMINAL_WIDTH: u16 = 50;
 pub const MIN_TERMINAL_HEIGHT: u16 = 24;
+// keys that jump to ancestors of the current directory, starting from its parent
+pub(super) const ANCESTOR_JUMP_KEYS: &str = "abcdefghijklmnopqrstuvwxyz";
 
-const HELP_CONTENT_RAW: &str = include_str!("static/help.txt");
+pub struct StartupOptions {
+    // directories to open sessions at
+    pub paths: Vec<PathBuf>,
+    // entries to highlight once their directories are read
+    pub selections: Vec<PathBuf>,
+    pub workspace_state: Option<WorkspaceState>,
+    pub settings: Settings,
+}
+
+pub struct Outcome {
+    pub workspace_state: WorkspaceState,
+    // directory the user ended up in, or chose to change into, when quitting
+    pub final_dir: Option<PathBuf>,
+    // paths confirmed in chooser mode; None if the user quit without confirming
+    pub chosen_paths: Option<Vec<PathBuf>>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Settings {
+    pub listing: ListingOptions,
+    pub read_only: bool,
+    pub chooser: Option<ChooserMode>,
+    pub openers: Vec<Opener>,
+    pub custom_commands: Vec<CustomCommand>,
+    pub preview: bool,
+    pub layout: PaneLayout,
+    pub auto_dir_sizes: bool,
+    // whether clicks and scrolls are left to the terminal rather than captured
+    pub disable_mouse: bool,
+    // shown as `~` in paths
+    pub home_dir: Option<PathBuf>,
+    pub theme: Theme,
+    // entry styles from LS_COLORS, which take precedence over the theme's
+    pub ls_colors: Option<LsColors>,
+    pub keymap: Keymap,
+}
+
+/// How the explorer and the preview pane are laid out relative to each other.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaneLayout {
+    // preview to the right of the explorer
+    #[default]
+    Horizontal,
+    // preview below the explorer
+    Vertical,
+}
 
-pub fn get_help_content() -> String {
-    HELP_CONTENT_RAW.to_string()
+/// What atls is being used to pick, when run as a file chooser.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChooserMode {
+    Files,
+    Directory,
 }
 
 #[derive(Clone, Debug)]
@@ -36,6 +92,13 @@ impl From<DirectoryAddress> for SessionInfo {
 pub enum Pane {
     Explorer,
     Help,
+    OpenWith,
+    Prompt,
+    CommandOutput,
+    Confirmation,
+    DiskUsage,
+    AncestorJump,
+    History,
 }
 
 impl std::fmt::Display for Pane {
@@ -43,6 +106,13 @@ impl std::fmt::Display for Pane {
         match self {
             Pane::Explorer => write!(f, "explorer"),
             Pane::Help => write!(f, "help"),
+            Pane::OpenWith => write!(f, "open with"),
+            Pane::Prompt => write!(f, "prompt"),
+            Pane::CommandOutput => write!(f, "command output"),
+            Pane::Confirmation => write!(f, "confirmation"),
+            Pane::DiskUsage => write!(f, "disk usage"),
+            Pane::AncestorJump => write!(f, "ancestor jump"),
+            Pane::History => write!(f, "history"),
         }
     }
 }
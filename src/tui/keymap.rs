@@ -0,0 +1,779 @@
+use super::common::Pane;
+use crate::domain::CustomCommand;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+const HELP_KEYS_WIDTH: usize = 20;
+const HELP_PLACEHOLDERS: &str = r#"
+Shell commands (and custom commands) expand these placeholders:
+    %f                   entry under cursor
+    %m                   marked paths
+    %d                   current directory
+    %D                   the other session's directory
+    %%                   a literal %
+"#;
+
+/// A single key press, along with the modifiers held down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let modifiers =
+            modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+
+        // the shift modifier is already accounted for in the case of characters, and in
+        // "backtab"; keeping it around would mean "G" and "<s-G>" are different keys
+        let modifiers = match code {
+            KeyCode::Char(_) | KeyCode::BackTab => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+
+        Self { code, modifiers }
+    }
+
+    pub fn is_esc(&self) -> bool {
+        self.code == KeyCode::Esc && self.modifiers.is_empty()
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self.code {
+            KeyCode::Char(' ') => "space".to_string(),
+            KeyCode::Char('<') => "lt".to_string(),
+            KeyCode::Char(c) if self.modifiers.is_empty() => return write!(f, "{c}"),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Enter => "enter".to_string(),
+            KeyCode::Esc => "esc".to_string(),
+            KeyCode::Tab => "tab".to_string(),
+            KeyCode::BackTab => "s-tab".to_string(),
+            KeyCode::Backspace => "bs".to_string(),
+            KeyCode::Delete => "del".to_string(),
+            KeyCode::Up => "up".to_string(),
+            KeyCode::Down => "down".to_string(),
+            KeyCode::Left => "left".to_string(),
+            KeyCode::Right => "right".to_string(),
+            KeyCode::Home => "home".to_string(),
+            KeyCode::End => "end".to_string(),
+            KeyCode::PageUp => "pageup".to_string(),
+            KeyCode::PageDown => "pagedown".to_string(),
+            KeyCode::F(n) => format!("f{n}"),
+            other => format!("{other:?}").to_lowercase(),
+        };
+
+        write!(f, "<")?;
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "c-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "a-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "s-")?;
+        }
+        write!(f, "{name}>")
+    }
+}
+
+/// One or more keys pressed in succession, eg. "gg", or "<c-w>j".
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeySequence(Vec<Key>);
+
+impl KeySequence {
+    fn starts_with(&self, keys: &[Key]) -> bool {
+        self.0.starts_with(keys)
+    }
+}
+
+impl std::str::FromStr for KeySequence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut keys = vec![];
+        let mut chars = s.chars();
+
+        while let Some(c) = chars.next() {
+            if c != '<' {
+                keys.push(Key::new(KeyCode::Char(c), KeyModifiers::NONE));
+                continue;
+            }
+
+            let mut name = String::new();
+            loop {
+                match chars.next() {
+                    Some('>') if !name.is_empty() => break,
+                    Some(c) => name.push(c),
+                    None => return Err(format!(r#"unterminated "<" in "{s}""#)),
+                }
+            }
+            keys.push(parse_special_key(&name)?);
+        }
+
+        if keys.is_empty() {
+            return Err("key sequence cannot be empty".to_string());
+        }
+
+        Ok(Self(keys))
+    }
+}
+
+impl Display for KeySequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for key in &self.0 {
+            write!(f, "{key}")?;
+        }
+
+        Ok(())
+    }
+}
+
+// parses the contents of "<...>", eg. "c-w", "enter", "s-tab"
+fn parse_special_key(name: &str) -> Result<Key, String> {
+    let mut parts = name.split('-').collect::<Vec<_>>();
+    let key_name = match parts.pop() {
+        // "<c-->" binds ctrl and the minus key
+        Some("") => {
+            parts.pop();
+            "-"
+        }
+        Some(k) => k,
+        None => return Err(format!(r#"invalid key "<{name}>""#)),
+    };
+
+    let mut modifiers = KeyModifiers::NONE;
+    for modifier in parts {
+        modifiers |= match modifier.to_lowercase().as_str() {
+            "c" | "ctrl" => KeyModifiers::CONTROL,
+            "a" | "alt" | "m" | "meta" => KeyModifiers::ALT,
+            "s" | "shift" => KeyModifiers::SHIFT,
+            _ => return Err(format!(r#"unknown modifier "{modifier}" in "<{name}>""#)),
+        };
+    }
+
+    let mut chars = key_name.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+            KeyCode::Char(c.to_ascii_uppercase())
+        }
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match key_name.to_lowercase().as_str() {
+            "space" => KeyCode::Char(' '),
+            "lt" => KeyCode::Char('<'),
+            "enter" | "cr" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            "tab" => KeyCode::Tab,
+            "bs" | "backspace" => KeyCode::Backspace,
+            "del" | "delete" => KeyCode::Delete,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            other => match other.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                _ => return Err(format!(r#"unknown key "<{name}>""#)),
+            },
+        },
+    };
+
+    Ok(Key::new(code, modifiers))
+}
+
+/// Something a key sequence can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    SelectNext,
+    SelectPrevious,
+    SelectFirst,
+    SelectLast,
+    Open,
+    Confirm,
+    Parent,
+    NextSession,
+    PreviousSession,
+    #[serde(rename = "go_to_session_1")]
+    GoToSession1,
+    #[serde(rename = "go_to_session_2")]
+    GoToSession2,
+    #[serde(rename = "go_to_session_3")]
+    GoToSession3,
+    #[serde(rename = "go_to_session_4")]
+    GoToSession4,
+    Mark,
+    CopyMarked,
+    MoveMarked,
+    Trash,
+    Delete,
+    View,
+    OpenWith,
+    ShellCommand,
+    InteractiveShellCommand,
+    ToggleHidden,
+    CycleSort,
+    TogglePreview,
+    ToggleDiffs,
+    GitStage,
+    GitUnstage,
+    GitDiscard,
+    ComputeDirSizes,
+    DiskUsage,
+    JumpToAncestor,
+    GoToPath,
+    HistoryBack,
+    HistoryForward,
+    ShowHistory,
+    Help,
+    Back,
+    QuitIntoDir,
+    Quit,
+    #[serde(skip)]
+    RunCustomCommand(usize),
+}
+
+impl Action {
+    fn description(&self, pane: Pane) -> &'static str {
+        match (self, pane) {
+            (Action::SelectNext, Pane::Help | Pane::CommandOutput) => "scroll down",
+            (Action::SelectPrevious, Pane::Help | Pane::CommandOutput) => "scroll up",
+            (Action::SelectFirst, Pane::Help | Pane::CommandOutput) => "scroll to top",
+            (Action::SelectLast, Pane::Help | Pane::CommandOutput) => "scroll to bottom",
+            (Action::SelectNext, _) => "select next entry",
+            (Action::SelectPrevious, _) => "select previous entry",
+            (Action::SelectFirst, _) => "select first entry",
+            (Action::SelectLast, _) => "select last entry",
+            (Action::Open, Pane::DiskUsage) => "go into directory",
+            (Action::Open, _) => {
+                "enter directory, or open file with the first matching opener (or $VISUAL/$EDITOR)"
+            }
+            (Action::Confirm, Pane::Explorer) => {
+                "confirm choice (when run as a chooser), otherwise same as open"
+            }
+            (Action::Confirm, Pane::OpenWith) => "open with the selected option",
+            (Action::Confirm, Pane::History) => "go to the selected directory",
+            (Action::Confirm, _) => "confirm",
+            (Action::Parent, _) => "go to parent directory",
+            (Action::NextSession, _) => "go to next session",
+            (Action::PreviousSession, _) => "go to previous session",
+            (Action::GoToSession1, _) => "go to 1st session",
+            (Action::GoToSession2, _) => "go to 2nd session",
+            (Action::GoToSession3, _) => "go to 3rd session",
+            (Action::GoToSession4, _) => "go to 4th session",
+            (Action::Mark, _) => "mark/unmark entry",
+            (Action::CopyMarked, _) => "copy marked entries into the current directory",
+            (Action::MoveMarked, _) => "move marked entries into the current directory",
+            (Action::Trash, _) => "move marked entries (or the one under cursor) to trash",
+            (Action::Delete, _) => "delete marked entries (or the one under cursor) permanently",
+            (Action::View, _) => "view file in $PAGER",
+            (Action::OpenWith, _) => r#"show "open with" menu for the file under cursor"#,
+            (Action::ShellCommand, _) => "run a shell command, and show its output",
+            (Action::InteractiveShellCommand, _) => {
+                "run a shell command in the terminal (for interactive commands)"
+            }
+            (Action::ToggleHidden, _) => "show/hide hidden entries",
+            (Action::CycleSort, _) => "cycle sort mode (name, extension, size, modified)",
+            (Action::TogglePreview, _) => "show/hide preview of the entry under cursor",
+            (Action::ToggleDiffs, _) => "preview changed files in git repositories by their diffs",
+            (Action::GitStage, _) => "stage changes to marked entries (or the one under cursor)",
+            (Action::GitUnstage, _) => {
+                "unstage changes to marked entries (or the one under cursor)"
+            }
+            (Action::GitDiscard, _) => {
+                "discard unstaged changes to marked entries (or the one under cursor)"
+            }
+            (Action::ComputeDirSizes, _) => "compute sizes of directories in the current directory",
+            (Action::DiskUsage, _) => "analyze disk usage of the current directory",
+            (Action::JumpToAncestor, _) => {
+                "jump to an ancestor directory, by its key in the header"
+            }
+            (Action::GoToPath, _) => "go to a path, typed in with <tab> completion",
+            (Action::HistoryBack, _) => {
+                "go back to the previous directory in the session's history"
+            }
+            (Action::HistoryForward, _) => {
+                "go forward to the next directory in the session's history"
+            }
+            (Action::ShowHistory, _) => "list the session's history, to go to any directory in it",
+            (Action::Help, _) => "show/hide help view",
+            (Action::Back, Pane::Confirmation) => "cancel",
+            (Action::Back, _) => "go back/exit",
+            (Action::QuitIntoDir, _) => {
+                "exit, and change directory to the one under cursor (via `atls init`)"
+            }
+            (Action::Quit, _) => "exit immediately",
+            (Action::RunCustomCommand(_), _) => "run custom command",
+        }
+    }
+}
+
+/// Key bindings overridden via the config file, per pane.
+///
+/// Binding an action replaces all of its default bindings; binding it to an empty list unbinds it.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeysConfig {
+    pub explorer: BTreeMap<Action, Vec<String>>,
+    pub help: BTreeMap<Action, Vec<String>>,
+    pub open_with: BTreeMap<Action, Vec<String>>,
+    pub history: BTreeMap<Action, Vec<String>>,
+    pub command_output: BTreeMap<Action, Vec<String>>,
+    pub confirmation: BTreeMap<Action, Vec<String>>,
+    pub disk_usage: BTreeMap<Action, Vec<String>>,
+}
+
+#[derive(Debug, Clone)]
+struct Binding {
+    keys: KeySequence,
+    action: Action,
+}
+
+/// The result of looking up keys pressed so far.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Lookup {
+    Matched(Action),
+    // the keys are the start of one or more bindings
+    Pending,
+    Unmatched,
+}
+
+/// Key bindings for every pane that responds to keys.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    explorer: Vec<Binding>,
+    help: Vec<Binding>,
+    open_with: Vec<Binding>,
+    history: Vec<Binding>,
+    command_output: Vec<Binding>,
+    confirmation: Vec<Binding>,
+    disk_usage: Vec<Binding>,
+    // names of custom commands, for listing them in help
+    custom_command_names: Vec<String>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(&KeysConfig::default(), &[]).expect("default keymap should be valid")
+    }
+}
+
+impl Keymap {
+    pub fn new(config: &KeysConfig, custom_commands: &[CustomCommand]) -> Result<Self, String> {
+        let custom_command_names = custom_commands
+            .iter()
+            .map(|c| c.name.clone())
+            .collect::<Vec<_>>();
+
+        let mut explorer = get_default_bindings(Pane::Explorer);
+        for (i, command) in custom_commands.iter().enumerate() {
+            explorer.push((Action::RunCustomCommand(i), vec![command.key.clone()]));
+        }
+
+        let build = |pane, defaults, overrides| {
+            build_bindings(pane, defaults, overrides, &custom_command_names)
+        };
+
+        Ok(Self {
+            explorer: build(Pane::Explorer, explorer, &config.explorer)?,
+            help: build(Pane::Help, get_default_bindings(Pane::Help), &config.help)?,
+            open_with: build(
+                Pane::OpenWith,
+                get_default_bindings(Pane::OpenWith),
+                &config.open_with,
+            )?,
+            history: build(
+                Pane::History,
+                get_default_bindings(Pane::History),
+                &config.history,
+            )?,
+            command_output: build(
+                Pane::CommandOutput,
+                get_default_bindings(Pane::CommandOutput),
+                &config.command_output,
+            )?,
+            confirmation: build(
+                Pane::Confirmation,
+                get_default_bindings(Pane::Confirmation),
+                &config.confirmation,
+            )?,
+            disk_usage: build(
+                Pane::DiskUsage,
+                get_default_bindings(Pane::DiskUsage),
+                &config.disk_usage,
+            )?,
+            custom_command_names,
+        })
+    }
+
+    pub fn lookup(&self, pane: Pane, keys: &[Key]) -> Lookup {
+        let bindings = self.bindings(pane);
+
+        if let Some(binding) = bindings.iter().find(|b| b.keys.0 == keys) {
+            return Lookup::Matched(binding.action);
+        }
+
+        if bindings.iter().any(|b| b.keys.starts_with(keys)) {
+            return Lookup::Pending;
+        }
+
+        Lookup::Unmatched
+    }
+
+    pub fn help_content(&self) -> String {
+        let mut content = String::from("Keymaps\n---\n");
+
+        let sections = [
+            (Pane::Explorer, "Explorer"),
+            (Pane::DiskUsage, "Disk usage"),
+            (Pane::OpenWith, "Open with menu"),
+            (Pane::History, "History"),
+            (Pane::CommandOutput, "Command output"),
+            (Pane::Confirmation, "Confirmation"),
+            (Pane::Help, "Help"),
+        ];
+
+        for (pane, title) in sections {
+            content.push_str(&format!("\n{title}\n"));
+
+            // an action's keys are listed together, in the order the action was first bound
+            let mut rows: Vec<(Action, Vec<String>)> = vec![];
+            for binding in self.bindings(pane) {
+                match rows.iter_mut().find(|(a, _)| *a == binding.action) {
+                    Some((_, keys)) => keys.push(binding.keys.to_string()),
+                    None => rows.push((binding.action, vec![binding.keys.to_string()])),
+                }
+            }
+
+            for (action, keys) in rows {
+                let description = match action {
+                    Action::RunCustomCommand(i) => self.custom_command_names[i].as_str(),
+                    _ => action.description(pane),
+                };
+                content.push_str(&format!(
+                    "    {:<width$} {description}\n",
+                    keys.join(" / "),
+                    width = HELP_KEYS_WIDTH
+                ));
+            }
+
+            if pane == Pane::Explorer {
+                content.push_str(HELP_PLACEHOLDERS);
+            }
+        }
+
+        content
+    }
+
+    fn bindings(&self, pane: Pane) -> &[Binding] {
+        match pane {
+            Pane::Explorer => &self.explorer,
+            Pane::Help => &self.help,
+            Pane::OpenWith => &self.open_with,
+            Pane::History => &self.history,
+            Pane::CommandOutput => &self.command_output,
+            Pane::Confirmation => &self.confirmation,
+            Pane::DiskUsage => &self.disk_usage,
+            // the prompt takes text input, and jumping to ancestors takes any key; neither is
+            // remappable
+            Pane::Prompt | Pane::AncestorJump => &[],
+        }
+    }
+}
+
+fn build_bindings(
+    pane: Pane,
+    defaults: Vec<(Action, Vec<String>)>,
+    overrides: &BTreeMap<Action, Vec<String>>,
+    custom_command_names: &[String],
+) -> Result<Vec<Binding>, String> {
+    let mut bound = defaults;
+    for (action, keys) in overrides {
+        match bound.iter_mut().find(|(a, _)| a == action) {
+            Some((_, default_keys)) => *default_keys = keys.clone(),
+            None => {
+                return Err(format!(
+                    "{} isn't available in the {pane} pane",
+                    get_action_name(*action, custom_command_names)
+                ));
+            }
+        }
+    }
+
+    let mut bindings: Vec<Binding> = vec![];
+    for (action, keys) in bound {
+        for key in keys {
+            let keys = key.parse::<KeySequence>().map_err(|e| {
+                format!(
+                    r#"invalid key "{key}" for {} in the {pane} pane: {e}"#,
+                    get_action_name(action, custom_command_names)
+                )
+            })?;
+
+            let conflict = bindings
+                .iter()
+                .find(|b| b.keys.starts_with(&keys.0) || keys.starts_with(&b.keys.0));
+            if let Some(other) = conflict {
+                let other_name = get_action_name(other.action, custom_command_names);
+                let name = get_action_name(action, custom_command_names);
+                return Err(if other.keys == keys {
+                    format!(
+                        r#""{keys}" is bound to both {other_name} and {name} in the {pane} pane"#
+                    )
+                } else {
+                    format!(
+                        "{other_name} ({}) and {name} ({keys}) have overlapping bindings in the {pane} pane",
+                        other.keys
+                    )
+                });
+            }
+
+            bindings.push(Binding { keys, action });
+        }
+    }
+
+    Ok(bindings)
+}
+
+#[cfg(test)]
+impl Keymap {
+    /// Looks up keys written the way they are in the config file, eg. "gg".
+    pub fn lookup_str(&self, pane: Pane, keys: &str) -> Lookup {
+        let keys = keys
+            .parse::<KeySequence>()
+            .expect("key sequence should've been parsed");
+
+        self.lookup(pane, &keys.0)
+    }
+}
+
+fn get_action_name(action: Action, custom_command_names: &[String]) -> String {
+    match action {
+        Action::RunCustomCommand(i) => custom_command_names
+            .get(i)
+            .map(|n| format!(r#"command "{n}""#))
+            .unwrap_or_else(|| "command".to_string()),
+        // mirrors the names used in the config file
+        Action::GoToSession1 => r#""go_to_session_1""#.to_string(),
+        Action::GoToSession2 => r#""go_to_session_2""#.to_string(),
+        Action::GoToSession3 => r#""go_to_session_3""#.to_string(),
+        Action::GoToSession4 => r#""go_to_session_4""#.to_string(),
+        other => {
+            let mut name = String::from('"');
+            for (i, c) in format!("{other:?}").chars().enumerate() {
+                if c.is_uppercase() && i > 0 {
+                    name.push('_');
+                }
+                name.push(c.to_ascii_lowercase());
+            }
+            name.push('"');
+            name
+        }
+    }
+}
+
+fn get_default_bindings(pane: Pane) -> Vec<(Action, Vec<String>)> {
+    let bindings: &[(Action, &[&str])] = match pane {
+        Pane::Explorer => &[
+            (Action::SelectNext, &["j", "<down>"]),
+            (Action::SelectPrevious, &["k", "<up>"]),
+            (Action::SelectFirst, &["g"]),
+            (Action::SelectLast, &["G"]),
+            (Action::Open, &["l", "<right>"]),
+            (Action::Confirm, &["<enter>"]),
+            (Action::Parent, &["h", "<left>"]),
+            (Action::NextSession, &["<tab>"]),
+            (Action::PreviousSession, &["<s-tab>"]),
+            (Action::GoToSession1, &["1"]),
+            (Action::GoToSession2, &["2"]),
+            (Action::GoToSession3, &["3"]),
+            (Action::GoToSession4, &["4"]),
+            (Action::Mark, &["<space>"]),
+            (Action::CopyMarked, &["p"]),
+            (Action::MoveMarked, &["v"]),
+            (Action::View, &["i"]),
+            (Action::OpenWith, &["o"]),
+            (Action::ShellCommand, &[":"]),
+            (Action::InteractiveShellCommand, &["!"]),
+            (Action::ToggleHidden, &["."]),
+            (Action::CycleSort, &["s"]),
+            (Action::TogglePreview, &["P"]),
+            (Action::ToggleDiffs, &["="]),
+            (Action::GitStage, &["a"]),
+            (Action::GitUnstage, &["u"]),
+            (Action::GitDiscard, &["X"]),
+            (Action::ComputeDirSizes, &["S"]),
+            (Action::DiskUsage, &["U"]),
+            (Action::JumpToAncestor, &["b"]),
+            (Action::GoToPath, &["c"]),
+            (Action::HistoryBack, &["H"]),
+            (Action::HistoryForward, &["L"]),
+            (Action::ShowHistory, &["'"]),
+            (Action::Help, &["?"]),
+            (Action::Back, &["q", "<esc>"]),
+            (Action::QuitIntoDir, &["Q"]),
+            (Action::Quit, &["<c-c>"]),
+        ],
+        Pane::Help => &[
+            (Action::SelectNext, &["j", "<down>"]),
+            (Action::SelectPrevious, &["k", "<up>"]),
+            (Action::SelectFirst, &["g"]),
+            (Action::SelectLast, &["G"]),
+            (Action::Help, &["?"]),
+            (Action::Back, &["q", "<esc>"]),
+            (Action::Quit, &["<c-c>"]),
+        ],
+        Pane::OpenWith => &[
+            (Action::SelectNext, &["j", "<down>"]),
+            (Action::SelectPrevious, &["k", "<up>"]),
+            (Action::Confirm, &["<enter>"]),
+            (Action::Back, &["q", "<esc>"]),
+            (Action::Quit, &["<c-c>"]),
+        ],
+        Pane::History => &[
+            (Action::SelectNext, &["j", "<down>"]),
+            (Action::SelectPrevious, &["k", "<up>"]),
+            (Action::SelectFirst, &["g"]),
+            (Action::SelectLast, &["G"]),
+            (Action::Confirm, &["<enter>", "l"]),
+            (Action::Back, &["q", "<esc>", "'"]),
+            (Action::Quit, &["<c-c>"]),
+        ],
+        Pane::CommandOutput => &[
+            (Action::SelectNext, &["j", "<down>"]),
+            (Action::SelectPrevious, &["k", "<up>"]),
+            (Action::SelectFirst, &["g"]),
+            (Action::SelectLast, &["G"]),
+            (Action::Back, &["q", "<esc>"]),
+            (Action::Quit, &["<c-c>"]),
+        ],
+        Pane::Confirmation => &[
+            (Action::Confirm, &["y", "Y", "<enter>"]),
+            (Action::Back, &["n", "N", "q", "<esc>"]),
+            (Action::Quit, &["<c-c>"]),
+        ],
+        Pane::DiskUsage => &[
+            (Action::SelectNext, &["j", "<down>"]),
+            (Action::SelectPrevious, &["k", "<up>"]),
+            (Action::SelectFirst, &["g"]),
+            (Action::SelectLast, &["G"]),
+            (Action::Open, &["l", "<right>", "<enter>"]),
+            (Action::Parent, &["h", "<left>"]),
+            (Action::Mark, &["<space>"]),
+            (Action::Trash, &["d"]),
+            (Action::Delete, &["D"]),
+            (Action::Help, &["?"]),
+            (Action::Back, &["q", "<esc>"]),
+            (Action::Quit, &["<c-c>"]),
+        ],
+        Pane::Prompt | Pane::AncestorJump => &[],
+    };
+
+    bindings
+        .iter()
+        .map(|(action, keys)| (*action, keys.iter().map(|k| k.to_string()).collect()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+
+    fn keys(s: &str) -> Vec<Key> {
+        s.parse::<KeySequence>()
+            .expect("key sequence should've been parsed")
+            .0
+    }
+
+    #[test]
+    fn parsing_key_sequences_works() {
+        // GIVEN
+        let sequences = [
+            "gg",
+            "<c-w>j",
+            "<s-tab>",
+            "<space>x",
+            "<a-enter>",
+            "<c-->",
+            "<lt>G",
+        ];
+
+        // WHEN
+        let parsed = sequences
+            .iter()
+            .map(|s| {
+                s.parse::<KeySequence>()
+                    .expect("key sequence should've been parsed")
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        // THEN
+        assert_snapshot!(parsed, @r"
+        gg
+        <c-w>j
+        <s-tab>
+        <space>x
+        <a-enter>
+        <c-->
+        <lt>G
+        ");
+    }
+
+    #[test]
+    fn looking_up_a_chord_waits_for_all_its_keys() {
+        // GIVEN
+        let mut config = KeysConfig::default();
+        config
+            .explorer
+            .insert(Action::SelectFirst, vec!["gg".to_string()]);
+        let keymap = Keymap::new(&config, &[]).expect("keymap should've been built");
+
+        // WHEN
+        let results = [
+            keymap.lookup(Pane::Explorer, &keys("g")),
+            keymap.lookup(Pane::Explorer, &keys("gg")),
+            keymap.lookup(Pane::Explorer, &keys("gx")),
+        ];
+
+        // THEN
+        assert_eq!(
+            results,
+            [
+                Lookup::Pending,
+                Lookup::Matched(Action::SelectFirst),
+                Lookup::Unmatched
+            ]
+        );
+    }
+
+    #[test]
+    fn keys_that_are_prefixes_of_other_bindings_are_reported_as_conflicts() {
+        // GIVEN
+        let mut config = KeysConfig::default();
+        config.explorer.insert(Action::Mark, vec!["gm".to_string()]);
+
+        // WHEN
+        let result = Keymap::new(&config, &[]);
+
+        // THEN
+        let error = result.expect_err("keymap should've been rejected");
+        assert_snapshot!(error, @r#""select_first" (g) and "mark" (gm) have overlapping bindings in the explorer pane"#);
+    }
+}
@@ -0,0 +1,196 @@
+use crate::domain::{Entry, EntryKind};
+use ratatui::style::{Color, Modifier, Style};
+use std::collections::HashMap;
+
+pub const LS_COLORS_ENV_VAR: &str = "LS_COLORS";
+
+/// Entry styles parsed from `LS_COLORS`, in the format used by GNU `dircolors`.
+#[derive(Debug, Clone, Default)]
+pub struct LsColors {
+    kinds: HashMap<EntryKind, Style>,
+    // suffixes (eg. ".tar.gz") with ASCII letters lowercased, matched case-insensitively against
+    // the raw bytes of file names
+    suffixes: Vec<(String, Style)>,
+}
+
+impl LsColors {
+    /// Parses the value of `LS_COLORS`; entries that can't be parsed are skipped, like `ls` does.
+    pub fn parse(value: &str) -> Self {
+        let mut ls_colors = Self::default();
+
+        for item in value.split(':') {
+            let Some((key, codes)) = item.split_once('=') else {
+                continue;
+            };
+            let Some(style) = parse_sgr_codes(codes) else {
+                continue;
+            };
+
+            if let Some(suffix) = key.strip_prefix('*') {
+                ls_colors
+                    .suffixes
+                    .push((suffix.to_ascii_lowercase(), style));
+                continue;
+            }
+
+            let kind = match key {
+                "di" => EntryKind::Directory,
+                "fi" => EntryKind::File,
+                "ex" => EntryKind::Executable,
+                "ln" => EntryKind::Symlink,
+                "pi" => EntryKind::Fifo,
+                "so" => EntryKind::Socket,
+                "bd" => EntryKind::BlockDevice,
+                "cd" => EntryKind::CharDevice,
+                _ => continue,
+            };
+            ls_colors.kinds.insert(kind, style);
+        }
+
+        ls_colors
+    }
+
+    /// Returns the style `ls` would use for the entry, if `LS_COLORS` has one for it.
+    pub fn style_for(&self, entry: &Entry) -> Option<Style> {
+        // like in `ls`, only regular, non executable files are coloured by their suffix
+        if entry.kind() == EntryKind::File {
+            let name = entry.file_name().as_encoded_bytes().to_ascii_lowercase();
+            let suffix_style = self
+                .suffixes
+                .iter()
+                .filter(|(suffix, _)| name.ends_with(suffix.as_bytes()))
+                .max_by_key(|(suffix, _)| suffix.len())
+                .map(|(_, style)| *style);
+
+            if suffix_style.is_some() {
+                return suffix_style;
+            }
+        }
+
+        self.kinds.get(&entry.kind()).copied()
+    }
+}
+
+// parses SGR parameters, eg. "01;34" or "38;5;208"; returns None if they're malformed
+fn parse_sgr_codes(codes: &str) -> Option<Style> {
+    let codes = codes
+        .split(';')
+        .filter(|c| !c.is_empty())
+        .map(|c| c.parse::<u8>().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    let mut style = Style::new();
+    let mut codes = codes.into_iter();
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => Style::new(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            5 => style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style.add_modifier(Modifier::REVERSED),
+            8 => style.add_modifier(Modifier::HIDDEN),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg(get_ansi_color(code - 30, false)),
+            90..=97 => style.fg(get_ansi_color(code - 90, true)),
+            40..=47 => style.bg(get_ansi_color(code - 40, false)),
+            100..=107 => style.bg(get_ansi_color(code - 100, true)),
+            39 => style.fg(Color::Reset),
+            49 => style.bg(Color::Reset),
+            38 => style.fg(parse_extended_color(&mut codes)?),
+            48 => style.bg(parse_extended_color(&mut codes)?),
+            _ => style,
+        };
+    }
+
+    Some(style)
+}
+
+// parses the rest of "38;5;<n>" or "38;2;<r>;<g>;<b>"
+fn parse_extended_color(codes: &mut impl Iterator<Item = u8>) -> Option<Color> {
+    match codes.next()? {
+        5 => Some(Color::Indexed(codes.next()?)),
+        2 => Some(Color::Rgb(codes.next()?, codes.next()?, codes.next()?)),
+        _ => None,
+    }
+}
+
+fn get_ansi_color(index: u8, bright: bool) -> Color {
+    match (index, bright) {
+        (0, false) => Color::Black,
+        (1, false) => Color::Red,
+        (2, false) => Color::Green,
+        (3, false) => Color::Yellow,
+        (4, false) => Color::Blue,
+        (5, false) => Color::Magenta,
+        (6, false) => Color::Cyan,
+        (7, false) => Color::Gray,
+        (0, true) => Color::DarkGray,
+        (1, true) => Color::LightRed,
+        (2, true) => Color::LightGreen,
+        (3, true) => Color::LightYellow,
+        (4, true) => Color::LightBlue,
+        (5, true) => Color::LightMagenta,
+        (6, true) => Color::LightCyan,
+        _ => Color::White,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn entry(name: &str, kind: EntryKind) -> Entry {
+        Entry::new(PathBuf::from("/tmp").join(name), kind)
+    }
+
+    #[test]
+    fn entries_are_styled_by_type_and_suffix() {
+        // GIVEN
+        let ls_colors =
+            LsColors::parse("di=01;34:ln=01;36:ex=01;32:pi=40;33:*.tar.gz=38;5;208:*.gz=31:*.RS=4");
+
+        // WHEN
+        let styles = [
+            ls_colors.style_for(&entry("src", EntryKind::Directory)),
+            ls_colors.style_for(&entry("build.sh", EntryKind::Executable)),
+            ls_colors.style_for(&entry("a.tar.gz", EntryKind::File)),
+            ls_colors.style_for(&entry("b.gz", EntryKind::File)),
+            ls_colors.style_for(&entry("main.rs", EntryKind::File)),
+            ls_colors.style_for(&entry("fifo", EntryKind::Fifo)),
+            ls_colors.style_for(&entry("notes.txt", EntryKind::File)),
+        ];
+
+        // THEN
+        assert_eq!(
+            styles,
+            [
+                Some(Style::new().bold().fg(Color::Blue)),
+                Some(Style::new().bold().fg(Color::Green)),
+                Some(Style::new().fg(Color::Indexed(208))),
+                Some(Style::new().fg(Color::Red)),
+                Some(Style::new().underlined()),
+                Some(Style::new().bg(Color::Black).fg(Color::Yellow)),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_entries_are_skipped() {
+        // GIVEN
+        let value = "di=01;34:fi=38;5:ln=nope:so=38;2;255;0;128:garbage";
+
+        // WHEN
+        let ls_colors = LsColors::parse(value);
+
+        // THEN
+        assert_eq!(ls_colors.kinds.len(), 2);
+        assert_eq!(
+            ls_colors.kinds.get(&EntryKind::Socket),
+            Some(&Style::new().fg(Color::Rgb(255, 0, 128)))
+        );
+    }
+}
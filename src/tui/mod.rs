@@ -1,9 +1,23 @@
 mod app;
 mod cmd;
 mod common;
+mod keymap;
+mod ls_colors;
 mod model;
 mod msg;
+mod prompt;
+mod terminal;
+mod theme;
 mod update;
 mod view;
 
 pub use app::run;
+pub use common::{ChooserMode, MAX_NUM_SESSIONS, PaneLayout, Settings, StartupOptions};
+pub use keymap::{Keymap, KeysConfig};
+pub use ls_colors::{LS_COLORS_ENV_VAR, LsColors};
+pub use theme::{DEFAULT_THEME, Theme, ThemeSpec};
+
+#[cfg(test)]
+pub use common::Pane;
+#[cfg(test)]
+pub use keymap::{Action, Lookup};
@@ -1,9 +1,19 @@
 use super::common::*;
+use super::keymap::{Key, Keymap};
+use super::ls_colors::LsColors;
+use super::prompt::{Prompt, PromptEdit, PromptKind};
+use super::theme::Theme;
 use crate::common::*;
-use crate::domain::{Entry, EntryKind};
+use crate::domain::{
+    CommandOutput, CustomCommand, Entry, EntryKind, GitFileStatus, GitStatus, History,
+    ListingOptions, Opener, PlaceholderValues, SessionState, SortMode, UsageNode, WorkspaceState,
+    compare_entries, get_breadcrumbs,
+};
+use ratatui::layout::{Position, Rect};
 use ratatui::widgets::ListState;
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
 use tracing::debug;
 
 const USER_MESSAGE_DEFAULT_FRAMES: u16 = 4;
@@ -68,12 +78,236 @@ impl UserMsg {
     }
 }
 
+/// A read of a session's directory that's still in progress.
+#[derive(Debug)]
+pub struct DirRead {
+    // reads are tagged with an id, so that results of ones that have been superseded by a newer
+    // read for the same session can be told apart, and discarded
+    pub id: u64,
+    pub navigated_to: bool,
+    // whether any entries have been read yet
+    pub started: bool,
+    pub num_entries: usize,
+    // entries read so far when refreshing; they're only shown once all of them are read, so that
+    // the listing doesn't shrink in the meantime
+    pub entries: Vec<Entry>,
+    // whether the entry last selected in the directory is yet to be read
+    pub selection_pending: bool,
+    // set when going back or forward through the session's history, rather than to a new
+    // directory
+    pub history_index: Option<usize>,
+}
+
+/// The size of a directory, as computed in the background.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirSize {
+    pub bytes: u64,
+    // the directory's modification time when its size was computed
+    pub modified: Option<SystemTime>,
+    // false while the size is still being computed, or if computing it was cancelled
+    pub complete: bool,
+}
+
+/// Sizes of directories computed so far, so that they don't have to be computed again when
+/// revisiting them.
+#[derive(Debug, Default)]
+pub struct DirSizeCache(HashMap<PathBuf, DirSize>);
+
+impl DirSizeCache {
+    /// Returns the size of the directory, unless it has changed since the size was computed.
+    pub fn get(&self, entry: &Entry) -> Option<DirSize> {
+        self.0
+            .get(entry.path())
+            .filter(|size| size.modified == entry.modified())
+            .copied()
+    }
+
+    fn is_up_to_date(&self, entry: &Entry) -> bool {
+        self.get(entry).is_some_and(|size| size.complete)
+    }
+
+    fn insert(&mut self, path: PathBuf, size: DirSize) {
+        self.0.insert(path, size);
+    }
+}
+
+/// A directory whose size is to be computed, along with its modification time.
+pub type DirToSize = (PathBuf, Option<SystemTime>);
+
+/// A calculation of the sizes of directories in a session's directory.
+#[derive(Debug)]
+pub struct DirSizeCalculation {
+    pub id: u64,
+    pub dir: PathBuf,
+    // number of directories whose sizes are yet to be computed
+    pub remaining: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct EntryItem {
     pub entry: Entry,
     pub marked: bool,
 }
 
+/// An entry in the "open with" menu.
+#[derive(Debug, Clone)]
+pub struct OpenOption {
+    pub label: String,
+    pub command_line: String,
+    pub blocking: bool,
+}
+
+/// An action that needs to be confirmed by the user before it's carried out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfirmAction {
+    RunCustomCommand(usize),
+    Trash(Vec<Entry>),
+    Delete(Vec<Entry>),
+    DiscardGitChanges(Vec<PathBuf>),
+}
+
+pub struct Confirmation {
+    pub message: String,
+    pub action: ConfirmAction,
+}
+
+/// Contents of the preview pane, for the entry under cursor.
+pub struct Preview {
+    pub path: PathBuf,
+    // whether the lines are the file's diff, rather than its contents
+    pub diff: bool,
+    pub lines: Vec<String>,
+}
+
+/// A preview that's been asked for; results of other requests are stale.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreviewRequest {
+    pub path: PathBuf,
+    pub diff: bool,
+}
+
+/// A session's history, listed most recent first to jump to any directory in it.
+pub struct HistoryMenu {
+    pub dirs: Vec<PathBuf>,
+    // index, in the list, of the directory the session is in
+    pub current: usize,
+    pub state: ListState,
+}
+
+impl HistoryMenu {
+    fn new(history: &History) -> Self {
+        let dirs = history.dirs().iter().rev().cloned().collect::<Vec<_>>();
+        let current = dirs.len() - 1 - history.current();
+
+        Self {
+            dirs,
+            current,
+            state: ListState::default().with_selected(Some(current)),
+        }
+    }
+
+    /// Returns the index of the selected directory in the session's history.
+    pub(super) fn selected_history_index(&self) -> Option<usize> {
+        let selected = self.state.selected()?;
+        (selected < self.dirs.len()).then(|| self.dirs.len() - 1 - selected)
+    }
+}
+
+pub struct OpenWithMenu {
+    pub path: PathBuf,
+    pub options: Vec<OpenOption>,
+    pub state: ListState,
+}
+
+impl OpenWithMenu {
+    fn new(path: PathBuf, options: Vec<OpenOption>) -> Self {
+        let mut state = ListState::default();
+        if !options.is_empty() {
+            state.select(Some(0));
+        }
+
+        Self {
+            path,
+            options,
+            state,
+        }
+    }
+
+    pub(super) fn selected_option(&self) -> Option<&OpenOption> {
+        self.state.selected().and_then(|i| self.options.get(i))
+    }
+}
+
+pub struct CommandOutputView {
+    pub title: String,
+    pub lines: Vec<String>,
+    pub scroll: usize,
+}
+
+/// The disk usage view: a tree of sizes scanned from a directory, which can be drilled down into.
+pub struct DiskUsageView {
+    pub scan_id: u64,
+    // None while the tree is being scanned
+    pub tree: Option<UsageNode>,
+    // entries and bytes scanned so far
+    pub num_scanned: u64,
+    pub size_scanned: u64,
+    // directory in the tree whose entries are listed
+    pub dir: PathBuf,
+    pub state: ListState,
+}
+
+impl DiskUsageView {
+    fn new(scan_id: u64, dir: PathBuf) -> Self {
+        Self {
+            scan_id,
+            tree: None,
+            num_scanned: 0,
+            size_scanned: 0,
+            dir,
+            state: ListState::default(),
+        }
+    }
+
+    /// The node whose entries are listed.
+    pub fn node(&self) -> Option<&UsageNode> {
+        self.tree.as_ref()?.find(&self.dir)
+    }
+
+    fn node_under_cursor(&self) -> Option<&UsageNode> {
+        self.node()?.children.get(self.state.selected()?)
+    }
+
+    fn num_listed(&self) -> usize {
+        self.node()
+            .map(|node| node.children.len())
+            .unwrap_or_default()
+    }
+
+    fn select_path(&mut self, path: &Path) {
+        let index = self
+            .node()
+            .and_then(|node| node.children.iter().position(|c| c.entry.path() == path));
+        self.state
+            .select(index.or_else(|| (self.num_listed() > 0).then_some(0)));
+    }
+}
+
+/// Something in the explorer's header that can be clicked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeaderTarget {
+    Session(usize),
+    Dir(PathBuf),
+}
+
+/// Where the clickable parts of the UI were last rendered, so mouse events can be mapped to them.
+#[derive(Debug, Default)]
+pub struct ClickAreas {
+    // the rows entries of the explorer (or of the disk usage view) are listed in
+    pub list: Option<Rect>,
+    pub header: Vec<(Rect, HeaderTarget)>,
+}
+
 #[derive(Debug, Clone)]
 pub enum Session {
     Uninitialized,
@@ -81,6 +315,7 @@ pub enum Session {
         path: PathBuf,
         entries: Vec<EntryItem>,
         state: ListState,
+        history: History,
     },
 }
 
@@ -90,13 +325,14 @@ impl Session {
         let items = vec![];
 
         Self::Initialized {
+            history: History::new(path.clone()),
             path,
             entries: items,
             state,
         }
     }
 
-    fn new(path: PathBuf, entries: Vec<Entry>) -> Self {
+    fn new(path: PathBuf, entries: Vec<Entry>, history: History) -> Self {
         let mut state = ListState::default();
         if !entries.is_empty() {
             state.select(Some(0));
@@ -113,6 +349,14 @@ impl Session {
             path,
             entries,
             state,
+            history,
+        }
+    }
+
+    fn history(&self) -> Option<&History> {
+        match self {
+            Session::Uninitialized => None,
+            Session::Initialized { history, .. } => Some(history),
         }
     }
 
@@ -123,6 +367,7 @@ impl Session {
                 path: _,
                 entries,
                 state,
+                ..
             } => {
                 if let Some(selected_index) = state.selected()
                     && selected_index < entries.len()
@@ -135,6 +380,51 @@ impl Session {
         }
     }
 
+    fn entries(&self) -> &[EntryItem] {
+        match self {
+            Session::Uninitialized => &[],
+            Session::Initialized { entries, .. } => entries,
+        }
+    }
+
+    // adds entries to those already listed, keeping the entry under cursor selected
+    fn add_entries(&mut self, new_entries: Vec<Entry>, sort_mode: SortMode) {
+        let selected_path = self.selected_path();
+        let Session::Initialized { entries, state, .. } = self else {
+            return;
+        };
+
+        // both lists are sorted already, which makes sorting them together cheap
+        entries.extend(new_entries.into_iter().map(|entry| EntryItem {
+            entry,
+            marked: false,
+        }));
+        entries.sort_by(|a, b| compare_entries(&a.entry, &b.entry, sort_mode));
+
+        match selected_path {
+            Some(selected_path) => {
+                self.select_path(selected_path);
+            }
+            None if !entries.is_empty() => state.select(Some(0)),
+            None => {}
+        }
+    }
+
+    fn selected_index(&self) -> Option<usize> {
+        match self {
+            Session::Uninitialized => None,
+            Session::Initialized { state, .. } => state.selected(),
+        }
+    }
+
+    fn select_index_clamped(&mut self, index: usize) {
+        if let Session::Initialized { entries, state, .. } = self
+            && !entries.is_empty()
+        {
+            state.select(Some(index.min(entries.len() - 1)));
+        }
+    }
+
     fn select_path<P>(&mut self, path_to_select: P) -> bool
     where
         P: AsRef<Path>,
@@ -145,6 +435,7 @@ impl Session {
                 path: _,
                 entries,
                 state,
+                ..
             } => {
                 for (i, item) in entries.iter().enumerate() {
                     if item.entry.path() == path_to_select.as_ref() {
@@ -182,15 +473,80 @@ pub struct Model {
     pub event_counter: u64,
     pub debug: bool,
     pub help_scroll: usize,
+    // generated from the keymap, which doesn't change while atls runs
+    pub help_content: String,
+    pub listing: ListingOptions,
+    pub read_only: bool,
+    // directory to change into on quitting, if chosen explicitly by the user
+    pub quit_dir: Option<PathBuf>,
+    pub chooser: Option<ChooserMode>,
+    pub chosen_paths: Option<Vec<PathBuf>>,
+    pub openers: Vec<Opener>,
+    pub open_with_menu: Option<OpenWithMenu>,
+    pub history_menu: Option<HistoryMenu>,
+    pub prompt: Option<Prompt>,
+    pub command_output: Option<CommandOutputView>,
+    pub custom_commands: Vec<CustomCommand>,
+    pub confirmation: Option<Confirmation>,
+    pub show_preview: bool,
+    pub layout: PaneLayout,
+    pub home_dir: Option<PathBuf>,
+    pub theme: Theme,
+    pub ls_colors: Option<LsColors>,
+    pub preview: Option<Preview>,
+    // path whose preview was last asked for; results for other paths are stale
+    pub preview_requested: Option<PreviewRequest>,
+    // whether changed files in git repositories are previewed by their diffs
+    pub show_diffs: bool,
+    pub keymap: Keymap,
+    // keys pressed so far that are the start of a multi-key binding
+    pub pending_keys: Vec<Key>,
+    pub click_areas: ClickAreas,
+    // when, and on which entry, the last click was; used to detect double clicks
+    pub last_click: Option<(Instant, usize)>,
+    // keys of the ancestors shown in the header while jumping, as last rendered
+    pub ancestor_jump_keys: Vec<(char, PathBuf)>,
+    // directories of open sessions, which are watched for changes
+    pub watched_dirs: Vec<PathBuf>,
+    // directory reads in progress, by session index
+    pub dir_reads: HashMap<usize, DirRead>,
+    pub next_dir_read_id: u64,
+    pub dir_sizes: DirSizeCache,
+    pub dir_size_calculation: Option<DirSizeCalculation>,
+    pub next_dir_size_calculation_id: u64,
+    // whether sizes of directories are computed on entering directories, rather than on demand
+    pub auto_dir_sizes: bool,
+    // the size column is only shown once sizes have been asked for
+    pub show_sizes: bool,
+    // directory whose sizes were last computed automatically
+    pub auto_sized_dir: Option<PathBuf>,
+    pub disk_usage: Option<DiskUsageView>,
+    pub next_disk_usage_scan_id: u64,
+    // git statuses of session directories that are in repositories
+    pub git_statuses: HashMap<PathBuf, GitStatus>,
+    // read of each directory's git status in progress; results of any others are discarded
+    pub git_status_requests: HashMap<PathBuf, u64>,
+    // directories that changed while their status was being read, which are read again after
+    pub outdated_git_statuses: HashSet<PathBuf>,
+    pub next_git_status_request_id: u64,
 }
 
 impl Model {
-    pub fn new(root: PathBuf, terminal_dimensions: TerminalDimensions, debug: bool) -> Self {
+    pub fn new(
+        paths: Vec<PathBuf>,
+        settings: Settings,
+        terminal_dimensions: TerminalDimensions,
+        debug: bool,
+    ) -> Self {
         let terminal_too_small = terminal_dimensions.width < MIN_TERMINAL_WIDTH
             || terminal_dimensions.height < MIN_TERMINAL_HEIGHT;
 
-        let mut sessions = vec![Session::new_empty(root.clone())];
-        for _ in 1..MAX_NUM_SESSIONS {
+        let mut sessions = paths
+            .into_iter()
+            .take(MAX_NUM_SESSIONS)
+            .map(Session::new_empty)
+            .collect::<Vec<_>>();
+        while sessions.len() < MAX_NUM_SESSIONS {
             sessions.push(Session::Uninitialized);
         }
 
@@ -209,9 +565,207 @@ impl Model {
             event_counter: 0,
             debug,
             help_scroll: 0,
+            help_content: settings.keymap.help_content(),
+            listing: settings.listing,
+            read_only: settings.read_only,
+            quit_dir: None,
+            chooser: settings.chooser,
+            chosen_paths: None,
+            openers: settings.openers,
+            open_with_menu: None,
+            history_menu: None,
+            prompt: None,
+            command_output: None,
+            custom_commands: settings.custom_commands,
+            confirmation: None,
+            show_preview: settings.preview,
+            layout: settings.layout,
+            home_dir: settings.home_dir,
+            theme: settings.theme,
+            ls_colors: settings.ls_colors,
+            preview: None,
+            preview_requested: None,
+            show_diffs: false,
+            keymap: settings.keymap,
+            pending_keys: vec![],
+            click_areas: ClickAreas::default(),
+            last_click: None,
+            ancestor_jump_keys: vec![],
+            watched_dirs: vec![],
+            dir_reads: HashMap::new(),
+            next_dir_read_id: 0,
+            dir_sizes: DirSizeCache::default(),
+            dir_size_calculation: None,
+            next_dir_size_calculation_id: 0,
+            auto_dir_sizes: settings.auto_dir_sizes,
+            show_sizes: settings.auto_dir_sizes,
+            auto_sized_dir: None,
+            disk_usage: None,
+            next_disk_usage_scan_id: 0,
+            git_statuses: HashMap::new(),
+            git_status_requests: HashMap::new(),
+            outdated_git_statuses: HashSet::new(),
+            next_git_status_request_id: 0,
+        }
+    }
+
+    // entries to highlight once their parent directories are read
+    pub(super) fn select_paths_on_read(&mut self, paths: Vec<PathBuf>) {
+        for path in paths {
+            if let Some(parent) = path.parent() {
+                self.last_selections.insert(parent.to_path_buf(), path);
+            }
+        }
+    }
+
+    pub(super) fn restore_workspace_state(
+        &mut self,
+        state: WorkspaceState,
+        marked_paths: Vec<Entry>,
+    ) {
+        if !state.sessions.iter().any(|s| s.is_some()) {
+            return;
+        }
+
+        self.last_selections = state.last_selections;
+
+        let mut current_session_selection = None;
+        for (i, session_state) in state
+            .sessions
+            .into_iter()
+            .take(MAX_NUM_SESSIONS)
+            .enumerate()
+        {
+            let Some(session_state) = session_state else {
+                self.sessions[i] = Session::Uninitialized;
+                continue;
+            };
+
+            // selections are restored via last_selections once the session's entries are read
+            if let Some(selected) = session_state.selected {
+                if i == state.current_session_index {
+                    current_session_selection = Some((session_state.path.clone(), selected));
+                } else {
+                    self.last_selections
+                        .insert(session_state.path.clone(), selected);
+                }
+            }
+
+            self.sessions[i] = Session::new_empty(session_state.path);
+        }
+
+        // the current session's selection takes precedence over others on the same path
+        if let Some((path, selected)) = current_session_selection {
+            self.last_selections.insert(path, selected);
+        }
+
+        self.current_session_index = if self
+            .sessions
+            .get(state.current_session_index)
+            .is_some_and(|s| s.is_initialized())
+        {
+            state.current_session_index
+        } else {
+            self.sessions
+                .iter()
+                .position(|s| s.is_initialized())
+                .unwrap_or_default()
+        };
+
+        self.marked_paths = marked_paths.into_iter().collect();
+    }
+
+    pub(super) fn workspace_state(&self) -> WorkspaceState {
+        let sessions = self
+            .sessions
+            .iter()
+            .map(|s| match s {
+                Session::Uninitialized => None,
+                Session::Initialized { path, .. } => Some(SessionState {
+                    path: path.clone(),
+                    selected: s.selected_path(),
+                }),
+            })
+            .collect();
+
+        let mut marked_paths = self
+            .marked_paths
+            .iter()
+            .map(|e| e.path().to_path_buf())
+            .collect::<Vec<_>>();
+        marked_paths.sort();
+
+        WorkspaceState {
+            sessions,
+            current_session_index: self.current_session_index,
+            last_selections: self.last_selections.clone(),
+            marked_paths,
+            show_hidden: Some(self.listing.show_hidden),
+            sort_mode: Some(self.listing.sort_mode),
+        }
+    }
+
+    pub(super) fn final_dir(&self) -> Option<PathBuf> {
+        if let Some(dir) = &self.quit_dir {
+            return Some(dir.clone());
+        }
+
+        match self.current_session() {
+            Session::Uninitialized => None,
+            Session::Initialized { path, .. } => Some(path.clone()),
         }
     }
 
+    pub(super) fn confirm_choice(&mut self) {
+        let Some(mode) = self.chooser else {
+            return;
+        };
+
+        let mut chosen = self
+            .marked_paths
+            .iter()
+            .filter(|e| is_choosable(mode, e))
+            .map(|e| e.path().to_path_buf())
+            .collect::<Vec<_>>();
+        chosen.sort();
+
+        if chosen.is_empty() {
+            let fallback = match mode {
+                ChooserMode::Files => self
+                    .get_entry_under_cursor()
+                    .filter(|e| is_choosable(mode, e))
+                    .map(|e| e.path().to_path_buf()),
+                ChooserMode::Directory => self
+                    .get_directory_under_cursor()
+                    .map(|d| d.path)
+                    .or_else(|| self.current_session_path()),
+            };
+            chosen.extend(fallback);
+        }
+
+        if chosen.is_empty() {
+            self.user_msg = Some(UserMsg::error("nothing to choose"));
+            return;
+        }
+
+        self.chosen_paths = Some(chosen);
+        self.running_state = RunningState::Done;
+    }
+
+    // in a file chooser, confirming on a directory without any files marked enters it instead
+    pub(super) fn get_dir_to_enter_on_confirm(&self) -> Option<DirectoryAddress> {
+        if self.chooser != Some(ChooserMode::Files)
+            || self
+                .marked_paths
+                .iter()
+                .any(|e| is_choosable(ChooserMode::Files, e))
+        {
+            return None;
+        }
+
+        self.get_directory_under_cursor()
+    }
+
     pub(super) fn go_back_or_quit(&mut self) {
         let active_pane = Some(self.active_pane);
         match self.active_pane {
@@ -230,11 +784,452 @@ impl Model {
                 Some(p) => self.active_pane = p,
                 None => self.active_pane = Pane::Explorer,
             },
+            Pane::OpenWith => {
+                self.open_with_menu = None;
+                self.active_pane = Pane::Explorer;
+            }
+            Pane::History => {
+                self.history_menu = None;
+                self.active_pane = Pane::Explorer;
+            }
+            Pane::Prompt => {
+                self.prompt = None;
+                self.active_pane = Pane::Explorer;
+            }
+            Pane::AncestorJump => self.active_pane = Pane::Explorer,
+            Pane::CommandOutput => {
+                self.command_output = None;
+                self.active_pane = Pane::Explorer;
+            }
+            Pane::Confirmation => {
+                self.confirmation = None;
+                self.active_pane = self.last_active_pane.unwrap_or(Pane::Explorer);
+            }
+            Pane::DiskUsage => {
+                self.close_disk_usage();
+            }
         }
 
         self.last_active_pane = active_pane;
     }
 
+    pub(super) fn open_prompt(&mut self, kind: PromptKind) {
+        self.prompt = Some(Prompt::new(kind));
+        self.last_active_pane = Some(self.active_pane);
+        self.active_pane = Pane::Prompt;
+    }
+
+    /// Returns the directory at `index` in the current session's history, for going to it.
+    pub(super) fn history_entry(&self, index: usize) -> Option<SessionInfo> {
+        let path = self.current_session().history()?.dirs().get(index)?.clone();
+
+        Some(SessionInfo {
+            index: self.current_session_index,
+            path,
+        })
+    }
+
+    pub(super) fn current_history(&self) -> Option<&History> {
+        self.current_session().history()
+    }
+
+    // marks the session's read as going through its history, rather than to a new directory
+    pub(super) fn set_history_move(&mut self, session_index: usize, history_index: usize) {
+        if let Some(read) = self.dir_reads.get_mut(&session_index) {
+            read.history_index = Some(history_index);
+        }
+    }
+
+    pub(super) fn show_history_menu(&mut self) {
+        let Some(history) = self.current_history() else {
+            return;
+        };
+
+        self.history_menu = Some(HistoryMenu::new(history));
+        self.last_active_pane = Some(self.active_pane);
+        self.active_pane = Pane::History;
+    }
+
+    pub(super) fn close_history_menu(&mut self) -> Option<HistoryMenu> {
+        if self.active_pane == Pane::History {
+            self.active_pane = Pane::Explorer;
+        }
+
+        self.history_menu.take()
+    }
+
+    pub(super) fn start_ancestor_jump(&mut self) {
+        // directories above the home directory aren't shown, so they can't be jumped to either
+        let has_ancestors_shown = self.get_session_path().is_some_and(|address| {
+            get_breadcrumbs(&address.path, self.home_dir.as_deref()).len() > 1
+        });
+        if !has_ancestors_shown {
+            self.user_msg = Some(UserMsg::error("no parent found"));
+            return;
+        }
+
+        self.last_active_pane = Some(self.active_pane);
+        self.active_pane = Pane::AncestorJump;
+    }
+
+    /// Returns the ancestor of the current session's directory that `key` jumps to; only the
+    /// ones shown in the header have keys.
+    pub(super) fn ancestor_for_key(&self, key: char) -> Option<PathBuf> {
+        self.ancestor_jump_keys
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, dir)| dir.clone())
+    }
+
+    pub(super) fn edit_prompt(&mut self, edit: PromptEdit) {
+        if let Some(prompt) = &mut self.prompt {
+            prompt.edit(edit);
+        }
+    }
+
+    pub(super) fn close_prompt(&mut self) -> Option<Prompt> {
+        if self.active_pane == Pane::Prompt {
+            self.active_pane = Pane::Explorer;
+        }
+
+        self.prompt.take()
+    }
+
+    pub(super) fn toggle_preview(&mut self) {
+        self.show_preview = !self.show_preview;
+        self.preview = None;
+        self.preview_requested = None;
+    }
+
+    // returns the directories to watch, if they've changed since they were last asked for
+    pub(super) fn dirs_to_watch(&mut self) -> Option<Vec<PathBuf>> {
+        let dirs = self
+            .get_unique_session_paths()
+            .into_iter()
+            .map(|info| info.path)
+            .collect::<Vec<_>>();
+        if dirs == self.watched_dirs {
+            return None;
+        }
+
+        self.watched_dirs = dirs.clone();
+        Some(dirs)
+    }
+
+    pub(super) fn toggle_diffs(&mut self) {
+        self.show_diffs = !self.show_diffs;
+    }
+
+    // returns the preview to load, if the one being shown is out of date
+    pub(super) fn preview_to_request(&mut self) -> Option<PreviewRequest> {
+        if !self.show_preview || self.terminal_too_small {
+            return None;
+        }
+
+        let request = self.get_entry_under_cursor().map(|entry| PreviewRequest {
+            path: entry.path().to_path_buf(),
+            diff: self.show_diffs && self.has_git_changes(entry),
+        });
+        if request == self.preview_requested {
+            return None;
+        }
+
+        self.preview_requested = request.clone();
+        if request.is_none() {
+            self.preview = None;
+        }
+
+        request
+    }
+
+    pub(super) fn set_preview(&mut self, request: PreviewRequest, lines: Vec<String>) {
+        if self.preview_requested.as_ref() == Some(&request) {
+            self.preview = Some(Preview {
+                path: request.path,
+                diff: request.diff,
+                lines,
+            });
+        }
+    }
+
+    // whether the entry is a file with changes in the current session's repository
+    fn has_git_changes(&self, entry: &Entry) -> bool {
+        if !entry.kind().is_openable() {
+            return false;
+        }
+
+        matches!(
+            self.git_status_of(entry.path()),
+            Some(GitFileStatus::Staged | GitFileStatus::Modified | GitFileStatus::Conflicted)
+        )
+    }
+
+    // the status of an entry in the current session's repository, if it's in one
+    pub(super) fn git_status_of(&self, path: &Path) -> Option<GitFileStatus> {
+        self.get_session_path()
+            .and_then(|address| self.git_statuses.get(&address.path))
+            .and_then(|git_status| git_status.status_of(path))
+    }
+
+    pub(super) fn ask_for_confirmation(
+        &mut self,
+        message: impl Into<String>,
+        action: ConfirmAction,
+    ) {
+        self.confirmation = Some(Confirmation {
+            message: message.into(),
+            action,
+        });
+        self.last_active_pane = Some(self.active_pane);
+        self.active_pane = Pane::Confirmation;
+    }
+
+    pub(super) fn take_confirmed_action(&mut self) -> Option<ConfirmAction> {
+        // confirmations can be asked for from panes other than the explorer
+        if self.active_pane == Pane::Confirmation {
+            self.active_pane = self.last_active_pane.unwrap_or(Pane::Explorer);
+        }
+
+        self.confirmation.take().map(|c| c.action)
+    }
+
+    pub(super) fn show_command_output(&mut self, command_line: &str, output: CommandOutput) {
+        let mut lines = output
+            .output
+            .lines()
+            .map(|l| l.to_string())
+            .collect::<Vec<_>>();
+        if lines.is_empty() {
+            lines.push("(no output)".to_string());
+        }
+
+        let title = if output.success {
+            format!(" $ {command_line} ")
+        } else {
+            format!(" $ {command_line} ({}) ", output.status)
+        };
+
+        self.command_output = Some(CommandOutputView {
+            title,
+            lines,
+            scroll: 0,
+        });
+        self.last_active_pane = Some(self.active_pane);
+        self.active_pane = Pane::CommandOutput;
+    }
+
+    /// Opens the disk usage view for the current session's directory, and returns the id of the
+    /// scan to start for it, along with the directory.
+    pub(super) fn open_disk_usage(&mut self) -> Option<(u64, PathBuf)> {
+        let path = self.current_session_path()?;
+
+        let scan_id = self.next_disk_usage_scan_id;
+        self.next_disk_usage_scan_id += 1;
+        self.disk_usage = Some(DiskUsageView::new(scan_id, path.clone()));
+        self.last_active_pane = Some(self.active_pane);
+        self.active_pane = Pane::DiskUsage;
+
+        Some((scan_id, path))
+    }
+
+    pub(super) fn close_disk_usage(&mut self) -> Option<DiskUsageView> {
+        if self.active_pane == Pane::DiskUsage {
+            self.active_pane = Pane::Explorer;
+        }
+
+        // entries might've been marked in the view
+        self.sync_marked_paths_to_current_session();
+
+        self.disk_usage.take()
+    }
+
+    pub(super) fn is_scanning_disk_usage(&self) -> bool {
+        self.disk_usage.as_ref().is_some_and(|v| v.tree.is_none())
+    }
+
+    pub(super) fn set_disk_usage_progress(
+        &mut self,
+        scan_id: u64,
+        num_scanned: u64,
+        size_scanned: u64,
+    ) {
+        if let Some(view) = &mut self.disk_usage
+            && view.scan_id == scan_id
+        {
+            view.num_scanned = num_scanned;
+            view.size_scanned = size_scanned;
+        }
+    }
+
+    pub(super) fn set_disk_usage_tree(&mut self, scan_id: u64, tree: UsageNode) {
+        if let Some(view) = &mut self.disk_usage
+            && view.scan_id == scan_id
+        {
+            view.tree = Some(tree);
+            view.state.select((view.num_listed() > 0).then_some(0));
+        }
+    }
+
+    pub(super) fn enter_disk_usage_dir(&mut self) {
+        let Some(view) = &mut self.disk_usage else {
+            return;
+        };
+
+        let Some(node) = view
+            .node_under_cursor()
+            .filter(|node| node.entry.kind() == EntryKind::Directory)
+        else {
+            return;
+        };
+
+        view.dir = node.entry.path().to_path_buf();
+        view.state.select((view.num_listed() > 0).then_some(0));
+    }
+
+    pub(super) fn leave_disk_usage_dir(&mut self) {
+        let Some(view) = &mut self.disk_usage else {
+            return;
+        };
+
+        // the scanned directory is as far up as the view goes
+        if view
+            .tree
+            .as_ref()
+            .is_none_or(|tree| tree.entry.path() == view.dir)
+        {
+            return;
+        }
+
+        let Some(parent) = view.dir.parent().map(Path::to_path_buf) else {
+            return;
+        };
+        let previous_dir = std::mem::replace(&mut view.dir, parent);
+        view.select_path(&previous_dir);
+    }
+
+    /// Ends a scan that failed, closing the view; returns false if the scan had been superseded
+    /// already.
+    pub(super) fn fail_disk_usage_scan(&mut self, scan_id: u64) -> bool {
+        if self
+            .disk_usage
+            .as_ref()
+            .is_some_and(|view| view.scan_id == scan_id)
+        {
+            self.close_disk_usage();
+            return true;
+        }
+
+        false
+    }
+
+    /// Entries to act on, eg. to trash or to stage: the marked ones, or the one under cursor if
+    /// none are marked. In the disk usage view, marks made outside the scanned tree are left out.
+    pub(super) fn marked_or_selected_entries(&self) -> Vec<Entry> {
+        let mut entries = self
+            .marked_paths
+            .iter()
+            .filter(|entry| self.is_in_scope(entry))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !entries.is_empty() {
+            entries.sort();
+            return entries;
+        }
+
+        let entry_under_cursor = match self.active_pane {
+            Pane::DiskUsage => self
+                .disk_usage
+                .as_ref()
+                .and_then(|view| view.node_under_cursor())
+                .map(|node| &node.entry),
+            _ => self.get_entry_under_cursor(),
+        };
+
+        entry_under_cursor.into_iter().cloned().collect()
+    }
+
+    fn is_in_scope(&self, entry: &Entry) -> bool {
+        if self.active_pane != Pane::DiskUsage {
+            return true;
+        }
+
+        self.disk_usage
+            .as_ref()
+            .and_then(|view| view.tree.as_ref())
+            .is_some_and(|tree| {
+                entry.path() != tree.entry.path() && tree.find(entry.path()).is_some()
+            })
+    }
+
+    /// Removes entries that have been trashed or deleted from the disk usage tree, keeping the
+    /// cursor where it was.
+    pub(super) fn remove_from_disk_usage(&mut self, removed: &[PathBuf]) {
+        let Some(view) = &mut self.disk_usage else {
+            return;
+        };
+        let Some(tree) = &mut view.tree else {
+            return;
+        };
+
+        for path in removed {
+            tree.remove(path);
+        }
+
+        // the directory being looked at might've been removed as well
+        while tree.find(&view.dir).is_none()
+            && let Some(parent) = view.dir.parent()
+        {
+            view.dir = parent.to_path_buf();
+        }
+
+        let num_listed = view.num_listed();
+        let selected = view.state.selected().unwrap_or_default();
+        view.state
+            .select((num_listed > 0).then(|| selected.min(num_listed - 1)));
+    }
+
+    pub(super) fn placeholder_values(&self) -> PlaceholderValues {
+        let mut marked = self
+            .marked_paths
+            .iter()
+            .map(|e| e.path().to_path_buf())
+            .collect::<Vec<_>>();
+        marked.sort();
+
+        // the "other" session is the one tab would switch to
+        let other_dir = (1..self.sessions.len())
+            .map(|offset| {
+                &self.sessions[(self.current_session_index + offset) % self.sessions.len()]
+            })
+            .find_map(|s| match s {
+                Session::Uninitialized => None,
+                Session::Initialized { path, .. } => Some(path.clone()),
+            });
+
+        PlaceholderValues {
+            selected: self
+                .get_entry_under_cursor()
+                .map(|e| e.path().to_path_buf()),
+            marked,
+            current_dir: self.get_session_path().map(|d| d.path),
+            other_dir,
+        }
+    }
+
+    pub(super) fn show_open_with_menu(&mut self, path: PathBuf, options: Vec<OpenOption>) {
+        self.open_with_menu = Some(OpenWithMenu::new(path, options));
+        self.last_active_pane = Some(self.active_pane);
+        self.active_pane = Pane::OpenWith;
+    }
+
+    pub(super) fn close_open_with_menu(&mut self) -> Option<OpenWithMenu> {
+        if self.active_pane == Pane::OpenWith {
+            self.active_pane = Pane::Explorer;
+        }
+
+        self.open_with_menu.take()
+    }
+
     pub(super) fn select_next(&mut self) {
         match self.active_pane {
             Pane::Explorer => {
@@ -245,6 +1240,7 @@ impl Model {
                         path: _,
                         entries,
                         state,
+                        ..
                     } => {
                         if entries.is_empty() {
                             return;
@@ -260,7 +1256,49 @@ impl Model {
                     }
                 }
             }
-            Pane::Help => {}
+            Pane::Help => {
+                if self.help_scroll + 1 < self.help_content.lines().count() {
+                    self.help_scroll += 1;
+                }
+            }
+            Pane::Prompt | Pane::Confirmation | Pane::AncestorJump => {}
+            Pane::CommandOutput => {
+                if let Some(output) = &mut self.command_output
+                    && output.scroll + 1 < output.lines.len()
+                {
+                    output.scroll += 1;
+                }
+            }
+            Pane::OpenWith => {
+                if let Some(menu) = &mut self.open_with_menu
+                    && menu
+                        .state
+                        .selected()
+                        .is_some_and(|i| i + 1 < menu.options.len())
+                {
+                    menu.state.select_next();
+                }
+            }
+            Pane::History => {
+                if let Some(menu) = &mut self.history_menu
+                    && menu
+                        .state
+                        .selected()
+                        .is_some_and(|i| i + 1 < menu.dirs.len())
+                {
+                    menu.state.select_next();
+                }
+            }
+            Pane::DiskUsage => {
+                if let Some(view) = &mut self.disk_usage
+                    && view
+                        .state
+                        .selected()
+                        .is_some_and(|i| i + 1 < view.num_listed())
+                {
+                    view.state.select_next();
+                }
+            }
         }
     }
 
@@ -274,6 +1312,7 @@ impl Model {
                         path: _,
                         entries,
                         state,
+                        ..
                     } => {
                         if entries.is_empty() {
                             return;
@@ -289,11 +1328,94 @@ impl Model {
                     }
                 }
             }
-            Pane::Help => {}
+            Pane::Help => self.help_scroll = self.help_scroll.saturating_sub(1),
+            Pane::OpenWith => {
+                if let Some(menu) = &mut self.open_with_menu {
+                    menu.state.select_previous();
+                }
+            }
+            Pane::History => {
+                if let Some(menu) = &mut self.history_menu {
+                    menu.state.select_previous();
+                }
+            }
+            Pane::DiskUsage => {
+                if let Some(view) = &mut self.disk_usage {
+                    view.state.select_previous();
+                }
+            }
+            Pane::Prompt | Pane::Confirmation | Pane::AncestorJump => {}
+            Pane::CommandOutput => {
+                if let Some(output) = &mut self.command_output {
+                    output.scroll = output.scroll.saturating_sub(1);
+                }
+            }
+        }
+    }
+
+    /// Returns the index of the entry listed at `position`, in the explorer or the disk usage
+    /// view.
+    pub(super) fn list_index_at(&self, position: Position) -> Option<usize> {
+        let rect = self
+            .click_areas
+            .list
+            .filter(|rect| rect.contains(position))?;
+        let row = (position.y - rect.y) as usize;
+
+        let (state, num_listed) = match self.active_pane {
+            Pane::Explorer => match self.current_session() {
+                Session::Uninitialized => return None,
+                Session::Initialized { entries, state, .. } => (state, entries.len()),
+            },
+            Pane::DiskUsage => {
+                let view = self.disk_usage.as_ref()?;
+                (&view.state, view.num_listed())
+            }
+            _ => return None,
+        };
+
+        let index = state.offset() + row;
+        (index < num_listed).then_some(index)
+    }
+
+    pub(super) fn select_index(&mut self, index: usize) {
+        match self.active_pane {
+            Pane::Explorer => self.current_session_mut().select_index_clamped(index),
+            Pane::DiskUsage => {
+                if let Some(view) = &mut self.disk_usage
+                    && index < view.num_listed()
+                {
+                    view.state.select(Some(index));
+                }
+            }
+            _ => {}
         }
     }
 
     pub(super) fn select_first(&mut self) {
+        if self.active_pane == Pane::Help {
+            self.help_scroll = 0;
+        }
+
+        if self.active_pane == Pane::CommandOutput
+            && let Some(output) = &mut self.command_output
+        {
+            output.scroll = 0;
+        }
+
+        if self.active_pane == Pane::DiskUsage
+            && let Some(view) = &mut self.disk_usage
+            && view.num_listed() > 0
+        {
+            view.state.select_first();
+        }
+
+        if self.active_pane == Pane::History
+            && let Some(menu) = &mut self.history_menu
+        {
+            menu.state.select_first();
+        }
+
         if self.active_pane == Pane::Explorer {
             let current_session = self.current_session_mut();
             match current_session {
@@ -302,6 +1424,7 @@ impl Model {
                     path: _,
                     entries,
                     state,
+                    ..
                 } => {
                     if entries.is_empty() {
                         return;
@@ -317,8 +1440,31 @@ impl Model {
                 }
             }
         }
-    }
-    pub(super) fn select_last(&mut self) {
+    }
+    pub(super) fn select_last(&mut self) {
+        if self.active_pane == Pane::Help {
+            self.help_scroll = self.help_content.lines().count().saturating_sub(1);
+        }
+
+        if self.active_pane == Pane::CommandOutput
+            && let Some(output) = &mut self.command_output
+        {
+            output.scroll = output.lines.len().saturating_sub(1);
+        }
+
+        if self.active_pane == Pane::DiskUsage
+            && let Some(view) = &mut self.disk_usage
+            && view.num_listed() > 0
+        {
+            view.state.select(Some(view.num_listed() - 1));
+        }
+
+        if self.active_pane == Pane::History
+            && let Some(menu) = &mut self.history_menu
+        {
+            menu.state.select(Some(menu.dirs.len().saturating_sub(1)));
+        }
+
         if self.active_pane == Pane::Explorer {
             match self.current_session_mut() {
                 Session::Uninitialized => {}
@@ -326,6 +1472,7 @@ impl Model {
                     path: _,
                     entries,
                     state,
+                    ..
                 } => {
                     if entries.is_empty() {
                         return;
@@ -344,67 +1491,396 @@ impl Model {
         }
     }
 
-    pub(super) fn update_entries_for_session(
+    /// Starts a read of a directory for a session, superseding any read in progress for it, and
+    /// returns its id.
+    ///
+    /// Refreshing a session that's navigating elsewhere is pointless, so no read is started in
+    /// that case.
+    pub(super) fn start_dir_read(
+        &mut self,
+        session_info: &SessionInfo,
+        navigated_to: bool,
+    ) -> Option<u64> {
+        if !navigated_to
+            && self
+                .dir_reads
+                .get(&session_info.index)
+                .is_some_and(|r| r.navigated_to)
+        {
+            return None;
+        }
+
+        let read_id = self.next_dir_read_id;
+        self.next_dir_read_id += 1;
+        self.dir_reads.insert(
+            session_info.index,
+            DirRead {
+                id: read_id,
+                navigated_to,
+                started: false,
+                num_entries: 0,
+                entries: vec![],
+                selection_pending: false,
+                history_index: None,
+            },
+        );
+
+        Some(read_id)
+    }
+
+    /// Adds a chunk of entries read from a session's directory; chunks from reads that have been
+    /// superseded are discarded.
+    ///
+    /// When navigating, entries are shown as they come in; when refreshing, they're shown once the
+    /// last chunk is read. Returns whether the read is complete.
+    pub(super) fn add_directory_chunk(
         &mut self,
         session_info: SessionInfo,
+        read_id: u64,
         entries: Vec<Entry>,
-        navigated_to: bool,
-    ) {
+        done: bool,
+    ) -> bool {
+        let index = session_info.index;
+        // entries for a session that's been closed shouldn't bring it back
+        let Some(read) = self
+            .dir_reads
+            .get_mut(&index)
+            .filter(|read| read.id == read_id && self.sessions[index].is_initialized())
+        else {
+            debug!(
+                "discarding entries from stale read of {:?}",
+                session_info.path
+            );
+            return false;
+        };
+
+        let is_first_chunk = !read.started;
+        let navigated_to = read.navigated_to;
+        let history_index = read.history_index;
+        read.started = true;
+        read.num_entries += entries.len();
+
+        if !navigated_to {
+            read.entries.extend(entries);
+        } else if is_first_chunk {
+            let entries = self.listing.apply(entries);
+            let selected =
+                self.navigate_session_to(index, &session_info.path, entries, history_index);
+            if let Some(read) = self.dir_reads.get_mut(&index) {
+                read.selection_pending = !selected;
+            }
+        } else {
+            let entries = self.listing.apply(entries);
+            let sort_mode = self.listing.sort_mode;
+            let last_selection = read
+                .selection_pending
+                .then(|| self.last_selections.get(&session_info.path))
+                .flatten();
+            let session = &mut self.sessions[index];
+            session.add_entries(entries, sort_mode);
+            if let Some(last_selection) = last_selection
+                && session.select_path(last_selection)
+            {
+                read.selection_pending = false;
+            }
+        }
+
+        if !done {
+            return false;
+        }
+
+        let Some(read) = self.dir_reads.remove(&index) else {
+            return false;
+        };
+
+        if navigated_to {
+            // other sessions on the same path get the entries as well
+            let entries = self.sessions[index]
+                .entries()
+                .iter()
+                .map(|item| item.entry.clone())
+                .collect();
+            self.refresh_sessions_at(&session_info.path, entries, Some(index));
+        } else {
+            let entries = self.listing.apply(read.entries);
+            self.refresh_sessions_at(&session_info.path, entries, None);
+        }
+
+        true
+    }
+
+    /// Starts a read of a directory's git status, and returns its id. If a read is in progress
+    /// for it already, none is started; the directory is read again once that one's done
+    /// instead, so that bursts of changes don't run git over and over.
+    pub(super) fn start_git_status_read(&mut self, dir: PathBuf) -> Option<u64> {
+        if self.git_status_requests.contains_key(&dir) {
+            self.outdated_git_statuses.insert(dir);
+            return None;
+        }
+
+        let request_id = self.next_git_status_request_id;
+        self.next_git_status_request_id += 1;
+        self.git_status_requests.insert(dir, request_id);
+
+        Some(request_id)
+    }
+
+    /// Records a directory's git status, unless the read has been superseded; `None` means the
+    /// directory isn't in a repository. Returns the id of the read to start if the directory
+    /// changed while it was being read.
+    pub(super) fn set_git_status(
+        &mut self,
+        dir: PathBuf,
+        request_id: u64,
+        status: Option<GitStatus>,
+    ) -> Option<u64> {
+        if self.git_status_requests.get(&dir) != Some(&request_id) {
+            return None;
+        }
+        self.git_status_requests.remove(&dir);
+        let outdated = self.outdated_git_statuses.remove(&dir);
+
+        match status {
+            Some(status) => {
+                self.git_statuses.insert(dir.clone(), status);
+            }
+            None => {
+                self.git_statuses.remove(&dir);
+            }
+        }
+
+        // statuses of directories sessions have moved away from aren't needed anymore
+        let session_dirs = self
+            .get_unique_session_paths()
+            .into_iter()
+            .map(|info| info.path)
+            .collect::<HashSet<_>>();
+        self.git_statuses
+            .retain(|dir, _| session_dirs.contains(dir));
+
+        (outdated && session_dirs.contains(&dir))
+            .then(|| self.start_git_status_read(dir))
+            .flatten()
+    }
+
+    /// Ends a read that failed; returns false if the read had been superseded already.
+    pub(super) fn fail_dir_read(&mut self, session_index: usize, read_id: u64) -> bool {
+        if self
+            .dir_reads
+            .get(&session_index)
+            .is_some_and(|read| read.id == read_id)
+        {
+            self.dir_reads.remove(&session_index);
+            return true;
+        }
+
+        false
+    }
+
+    /// Starts computing the sizes of directories in the current session's directory whose sizes
+    /// aren't known already, and returns the calculation's id along with the directories.
+    ///
+    /// Returns `None` if there's nothing to compute, or if a calculation for the directory is in
+    /// progress already.
+    pub(super) fn start_dir_size_calculation(&mut self) -> Option<(u64, Vec<DirToSize>)> {
+        let Session::Initialized { path, entries, .. } = &self.sessions[self.current_session_index]
+        else {
+            return None;
+        };
+
+        if self
+            .dir_size_calculation
+            .as_ref()
+            .is_some_and(|c| &c.dir == path)
+        {
+            return None;
+        }
+
+        let dirs = entries
+            .iter()
+            .map(|item| &item.entry)
+            .filter(|entry| entry.kind() == EntryKind::Directory)
+            .filter(|entry| !self.dir_sizes.is_up_to_date(entry))
+            .map(|entry| (entry.path().to_path_buf(), entry.modified()))
+            .collect::<Vec<_>>();
+        if dirs.is_empty() {
+            return None;
+        }
+
+        let id = self.next_dir_size_calculation_id;
+        self.next_dir_size_calculation_id += 1;
+        self.dir_size_calculation = Some(DirSizeCalculation {
+            id,
+            dir: path.clone(),
+            remaining: dirs.len(),
+        });
+
+        Some((id, dirs))
+    }
+
+    /// Records the (possibly partial) size of a directory.
+    pub(super) fn set_dir_size(&mut self, calculation_id: u64, path: PathBuf, size: DirSize) {
+        let is_current = self
+            .dir_size_calculation
+            .as_ref()
+            .is_some_and(|c| c.id == calculation_id);
+        // partial sizes from cancelled calculations won't be updated anymore
+        if !is_current && !size.complete {
+            return;
+        }
+
+        self.dir_sizes.insert(path, size);
+
+        if is_current
+            && size.complete
+            && let Some(calculation) = &mut self.dir_size_calculation
+        {
+            calculation.remaining = calculation.remaining.saturating_sub(1);
+            if calculation.remaining == 0 {
+                self.dir_size_calculation = None;
+            }
+        }
+    }
+
+    /// Ends the calculation of directory sizes if the current session has left its directory,
+    /// returning whether it was ended.
+    pub(super) fn end_dir_size_calculation_if_left(&mut self) -> bool {
+        let current_path = self.current_session_path();
+        if self
+            .dir_size_calculation
+            .as_ref()
+            .is_some_and(|c| Some(&c.dir) != current_path.as_ref())
+        {
+            self.dir_size_calculation = None;
+            return true;
+        }
+
+        false
+    }
+
+    /// Whether the current session's directory is due for computing sizes automatically.
+    pub(super) fn needs_auto_dir_sizes(&self) -> bool {
+        if !self.auto_dir_sizes
+            || self.dir_size_calculation.is_some()
+            || self.dir_reads.contains_key(&self.current_session_index)
+        {
+            return false;
+        }
+
+        match self.current_session() {
+            Session::Uninitialized => false,
+            Session::Initialized { path, .. } => self.auto_sized_dir.as_ref() != Some(path),
+        }
+    }
+
+    /// The number of entries read so far, if the current session's directory is being read.
+    pub(super) fn current_dir_read_progress(&self) -> Option<usize> {
+        self.dir_reads
+            .get(&self.current_session_index)
+            .filter(|r| r.started)
+            .map(|r| r.num_entries)
+    }
+
+    // replaces the session's entries with those of a new directory, which is recorded in its
+    // history unless it was gone to from there; returns whether the entry last selected in it was
+    // found
+    fn navigate_session_to(
+        &mut self,
+        index: usize,
+        path: &Path,
+        entries: Vec<Entry>,
+        history_index: Option<usize>,
+    ) -> bool {
+        if index == self.current_session_index
+            && let Some(session_path) = self.current_session_path()
+            && let Some(selected_path) = self.currently_selected_path()
+        {
+            debug!(
+                "add to last_selections: {:?}->{:?}",
+                &session_path, &selected_path
+            );
+            self.last_selections.insert(session_path, selected_path);
+        }
+
+        let mut history = self.sessions[index]
+            .history()
+            .cloned()
+            .unwrap_or_else(|| History::new(path.to_path_buf()));
+        match history_index {
+            Some(history_index) => history.move_to(history_index),
+            None => history.visit(path),
+        }
+
+        self.sessions[index] = Session::new(path.to_path_buf(), entries, history);
+        match self.last_selections.get(path) {
+            Some(last_selection) => {
+                debug!("got last selection: {:?}->{:?}", path, last_selection);
+                self.sessions[index].select_path(last_selection)
+            }
+            None => {
+                debug!("didn't have last selection for path: {:?}", path);
+                false
+            }
+        }
+    }
+
+    // replaces the entries of sessions at `path`, preserving the entry under cursor in each
+    fn refresh_sessions_at(&mut self, path: &Path, entries: Vec<Entry>, skip: Option<usize>) {
         for i in 0..self.sessions.len() {
-            if navigated_to && i == session_info.index {
-                if let Some(session_path) = self.current_session_path()
-                    && let Some(selected_path) = self.currently_selected_path()
-                {
-                    debug!(
-                        "add to last_selections: {:?}->{:?}",
-                        &session_path, &selected_path
-                    );
-                    self.last_selections.insert(session_path, selected_path);
-                }
-                // create a new session with the new path
-                self.sessions[i] = Session::new(session_info.path.clone(), entries.clone());
-                if let Some(last_selection) = self.last_selections.get(&session_info.path) {
-                    debug!(
-                        "got last selection: {:?}->{:?}",
-                        &session_info.path, last_selection
-                    );
-                    self.sessions[i].select_path(last_selection);
-                } else {
-                    debug!(
-                        "didn't have last selection for path: {:?}",
-                        &session_info.path
-                    );
-                }
+            if Some(i) == skip {
+                continue;
+            }
 
+            let Session::Initialized {
+                path: session_path, ..
+            } = &self.sessions[i]
+            else {
+                continue;
+            };
+
+            if session_path != path {
                 continue;
             }
 
-            // maybe some other session is on the same path
-            // if so, update its entries as well
-            match &self.sessions[i] {
-                Session::Uninitialized => {}
-                Session::Initialized {
-                    path: session_path,
-                    entries: _,
-                    state: _,
-                } => {
-                    if session_path == &session_info.path {
-                        let last_selected_path = self.sessions[i].selected_path();
-                        if let Some(selected_path) = &last_selected_path {
-                            self.last_selections
-                                .insert(session_path.clone(), selected_path.clone());
-                        }
+            let last_selected_path = self.sessions[i].selected_path();
+            let last_selected_index = self.sessions[i].selected_index();
+            if let Some(selected_path) = &last_selected_path {
+                self.last_selections
+                    .insert(path.to_path_buf(), selected_path.clone());
+            }
 
-                        self.sessions[i] = Session::new(session_info.path.clone(), entries.clone());
-                        if let Some(selected_path) = last_selected_path {
-                            self.sessions[i].select_path(selected_path);
-                        }
-                    }
-                }
+            let history = self.sessions[i]
+                .history()
+                .cloned()
+                .unwrap_or_else(|| History::new(path.to_path_buf()));
+            self.sessions[i] = Session::new(path.to_path_buf(), entries.clone(), history);
+            let reselected = last_selected_path
+                .is_some_and(|selected_path| self.sessions[i].select_path(selected_path));
+            // the entry under cursor is gone; stay where it was instead of jumping back to the top
+            if !reselected && let Some(index) = last_selected_index {
+                self.sessions[i].select_index_clamped(index);
             }
         }
     }
 
+    pub(super) fn toggle_hidden_entries(&mut self) {
+        self.listing.show_hidden = !self.listing.show_hidden;
+        let message = if self.listing.show_hidden {
+            "showing hidden entries"
+        } else {
+            "hiding hidden entries"
+        };
+        self.user_msg = Some(UserMsg::info(message));
+    }
+
+    pub(super) fn cycle_sort_mode(&mut self) {
+        self.listing.sort_mode = self.listing.sort_mode.next();
+        self.user_msg = Some(UserMsg::info(format!(
+            "sorting by {}",
+            self.listing.sort_mode
+        )));
+    }
+
     pub(super) fn current_session(&self) -> &Session {
         &self.sessions[self.current_session_index]
     }
@@ -414,6 +1890,27 @@ impl Model {
     }
 
     pub(super) fn toggle_path_marked_status(&mut self) {
+        if self.active_pane == Pane::DiskUsage {
+            if let Some(view) = &mut self.disk_usage
+                && let Some(node) = view.node_under_cursor()
+            {
+                let entry = node.entry.clone();
+                if !self.marked_paths.remove(&entry) {
+                    self.marked_paths.insert(entry);
+                }
+
+                if view
+                    .state
+                    .selected()
+                    .is_some_and(|i| i + 1 < view.num_listed())
+                {
+                    view.state.select_next();
+                }
+            }
+
+            return;
+        }
+
         let current_session = &mut self.sessions[self.current_session_index];
         match current_session {
             Session::Uninitialized => todo!(),
@@ -421,6 +1918,7 @@ impl Model {
                 path: _,
                 entries,
                 state,
+                ..
             } => {
                 if let Some(selected_index) = state.selected() {
                     if selected_index >= entries.len() {
@@ -448,41 +1946,42 @@ impl Model {
     }
 
     pub(super) fn go_to_next_session(&mut self) {
-        if self.num_initialized_sessions() == 1 {
-            self.sessions[1] = self.sessions[0].clone();
-            self.current_session_index = 1;
+        // a lone session is opened in the slot after it as well, so that there's one to go to;
+        // it needn't be in the first slot, eg. when it's been restored into another one
+        if self.num_initialized_sessions() == 1 && self.current_session().is_initialized() {
+            let next_index = (self.current_session_index + 1) % self.sessions.len();
+            self.sessions[next_index] = self.current_session().clone();
+            self.current_session_index = next_index;
             return;
         }
 
-        if self.current_session_index == MAX_NUM_SESSIONS - 1 {
-            self.current_session_index = 0;
-        } else {
-            self.current_session_index += 1;
-        }
-
-        if let Session::Uninitialized = &self.current_session() {
-            self.go_to_next_session();
-        } else {
-            self.sync_marked_paths_to_current_session();
-        }
+        self.go_to_adjacent_session(true);
     }
 
     pub(super) fn go_to_previous_session(&mut self) {
-        if self.num_initialized_sessions() == 1 {
-            return;
-        }
+        self.go_to_adjacent_session(false);
+    }
 
-        if self.current_session_index == 0 {
-            self.current_session_index = MAX_NUM_SESSIONS - 1;
-        } else {
-            self.current_session_index -= 1;
-        }
+    // goes to the nearest initialized session after (or before) the current one, wrapping around;
+    // stays put if there's none
+    fn go_to_adjacent_session(&mut self, forward: bool) {
+        let num_sessions = self.sessions.len();
+        let current = self.current_session_index;
+        let Some(index) = (1..num_sessions)
+            .map(|offset| {
+                if forward {
+                    (current + offset) % num_sessions
+                } else {
+                    (current + num_sessions - offset) % num_sessions
+                }
+            })
+            .find(|i| self.sessions[*i].is_initialized())
+        else {
+            return;
+        };
 
-        if let Session::Uninitialized = &self.current_session() {
-            self.go_to_previous_session();
-        } else {
-            self.sync_marked_paths_to_current_session();
-        }
+        self.current_session_index = index;
+        self.sync_marked_paths_to_current_session();
     }
 
     pub(super) fn go_to_session(&mut self, index: usize) {
@@ -509,12 +2008,14 @@ impl Model {
             path: _,
             entries,
             state,
+            ..
         } = self.current_session()
             && let Some(i) = state.selected()
             && i < entries.len()
         {
             let current_entry = &entries[i].entry;
-            if let EntryKind::Directory = current_entry.kind() {
+            // symlinks to directories are entered via their own path, like `cd` does
+            if current_entry.leads_to_dir() {
                 return Some(DirectoryAddress {
                     session_index: self.current_session_index,
                     path: current_entry.path().to_path_buf(),
@@ -525,6 +2026,22 @@ impl Model {
         None
     }
 
+    pub(super) fn get_entry_under_cursor(&self) -> Option<&Entry> {
+        if let Session::Initialized {
+            path: _,
+            entries,
+            state,
+            ..
+        } = self.current_session()
+            && let Some(i) = state.selected()
+            && i < entries.len()
+        {
+            return Some(&entries[i].entry);
+        }
+
+        None
+    }
+
     pub(super) fn get_parent_dir_for_current_session(&self) -> Option<DirectoryAddress> {
         match self.current_session() {
             Session::Uninitialized => None,
@@ -584,6 +2101,7 @@ impl Model {
                 path: _,
                 entries,
                 state: _,
+                ..
             } => {
                 for item in entries {
                     item.marked = self.marked_paths.contains(&item.entry);
@@ -605,6 +2123,7 @@ impl Model {
             path: _,
             entries,
             state,
+            ..
         } = self.current_session()
             && let Some(i) = state.selected()
             && i < entries.len()
@@ -616,12 +2135,14 @@ impl Model {
     }
 
     fn close_current_session(&mut self) -> bool {
-        self.sessions[self.current_session_index] = Session::Uninitialized;
-
-        if self.num_initialized_sessions() == 0 {
+        // the last session is left as is, so that it can be persisted on exit
+        if self.num_initialized_sessions() <= 1 {
             return true;
         }
 
+        self.dir_reads.remove(&self.current_session_index);
+        self.sessions[self.current_session_index] = Session::Uninitialized;
+
         let mut next_index = if self.current_session_index > 0 {
             self.current_session_index - 1
         } else {
@@ -644,3 +2165,644 @@ impl Model {
         false
     }
 }
+
+fn is_choosable(mode: ChooserMode, entry: &Entry) -> bool {
+    let is_dir = entry.leads_to_dir();
+    match mode {
+        ChooserMode::Files => !is_dir,
+        ChooserMode::Directory => is_dir,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::cmd::Cmd;
+    use super::super::keymap::{Action, KeysConfig};
+    use super::super::msg::{Msg, get_event_handling_msg};
+    use super::super::update::update;
+    use super::*;
+    use insta::assert_yaml_snapshot;
+    use ratatui::crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+
+    fn session_info(path: &str) -> SessionInfo {
+        SessionInfo {
+            index: 0,
+            path: PathBuf::from(path),
+        }
+    }
+
+    fn entries(dir: &str, names: &[&str]) -> Vec<Entry> {
+        names
+            .iter()
+            .map(|n| Entry::new(PathBuf::from(dir).join(n), EntryKind::File))
+            .collect()
+    }
+
+    fn chooser_model(mode: ChooserMode) -> Model {
+        let mut model = Model::new(
+            vec![PathBuf::from("/home")],
+            Settings {
+                chooser: Some(mode),
+                ..Default::default()
+            },
+            (80, 24).into(),
+            false,
+        );
+        let read = model
+            .start_dir_read(&session_info("/home"), true)
+            .expect("read should've been started");
+        let mut contents = entries("/home", &["a.txt", "b.txt"]);
+        contents.insert(
+            0,
+            Entry::new(PathBuf::from("/home/dir"), EntryKind::Directory),
+        );
+        model.add_directory_chunk(session_info("/home"), read, contents, true);
+
+        model
+    }
+
+    #[test]
+    fn choosing_files_picks_marked_files_over_the_entry_under_cursor() {
+        // GIVEN
+        let mut model = chooser_model(ChooserMode::Files);
+        model.marked_paths.extend([
+            Entry::new(PathBuf::from("/home/b.txt"), EntryKind::File),
+            Entry::new(PathBuf::from("/home/dir"), EntryKind::Directory),
+            Entry::new(PathBuf::from("/home/a.txt"), EntryKind::File),
+        ]);
+
+        // WHEN
+        model.confirm_choice();
+
+        // THEN
+        assert_yaml_snapshot!(model.chosen_paths, @r"
+        - /home/a.txt
+        - /home/b.txt
+        ");
+        assert_eq!(model.running_state, RunningState::Done);
+    }
+
+    #[test]
+    fn choosing_files_without_marks_enters_the_directory_under_cursor() {
+        // GIVEN
+        let mut model = chooser_model(ChooserMode::Files);
+
+        // WHEN
+        let dir_to_enter = model.get_dir_to_enter_on_confirm().map(|d| d.path);
+        model.select_next();
+        model.confirm_choice();
+
+        // THEN
+        assert_eq!(dir_to_enter, Some(PathBuf::from("/home/dir")));
+        assert_yaml_snapshot!(model.chosen_paths, @"- /home/a.txt");
+    }
+
+    #[test]
+    fn choosing_a_directory_ignores_marked_files() {
+        // GIVEN
+        let mut model = chooser_model(ChooserMode::Directory);
+        model
+            .marked_paths
+            .insert(Entry::new(PathBuf::from("/home/a.txt"), EntryKind::File));
+
+        // WHEN
+        let dir_to_enter = model.get_dir_to_enter_on_confirm();
+        model.confirm_choice();
+
+        // THEN
+        assert!(dir_to_enter.is_none());
+        assert_yaml_snapshot!(model.chosen_paths, @"- /home/dir");
+    }
+
+    #[test]
+    fn half_typed_key_sequences_are_dropped_when_the_pane_changes() {
+        // GIVEN
+        let mut keys = KeysConfig::default();
+        keys.explorer
+            .insert(Action::SelectFirst, vec!["gg".to_string()]);
+        let mut model = Model::new(
+            vec![PathBuf::from("/home")],
+            Settings {
+                keymap: Keymap::new(&keys, &[]).expect("keymap should've been built"),
+                ..Default::default()
+            },
+            (80, 24).into(),
+            false,
+        );
+        let g = Event::Key(KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE));
+        let msg = get_event_handling_msg(&mut model, g);
+        let pending_before = model.pending_keys.len();
+
+        // WHEN
+        update(
+            &mut model,
+            Msg::CommandOutputCaptured {
+                command_line: "ls".to_string(),
+                result: Ok(CommandOutput {
+                    success: true,
+                    status: "exit status: 0".to_string(),
+                    output: String::new(),
+                }),
+                refresh: false,
+            },
+        );
+
+        // THEN
+        assert!(msg.is_none());
+        assert_eq!(pending_before, 1);
+        assert_eq!(model.active_pane, Pane::CommandOutput);
+        assert!(model.pending_keys.is_empty());
+    }
+
+    fn read_into_current_session(model: &mut Model, dir: &str, contents: Vec<Entry>) {
+        let session_info = SessionInfo {
+            index: model.current_session_index,
+            path: PathBuf::from(dir),
+        };
+        let read = model
+            .start_dir_read(&session_info, true)
+            .expect("read should've been started");
+        model.add_directory_chunk(session_info, read, contents, true);
+    }
+
+    fn dir_entry(path: &str, modified_secs: u64) -> Entry {
+        Entry::new(PathBuf::from(path), EntryKind::Directory).with_metadata(
+            0,
+            Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(modified_secs)),
+        )
+    }
+
+    fn dir_size(bytes: u64, entry: &Entry, complete: bool) -> DirSize {
+        DirSize {
+            bytes,
+            modified: entry.modified(),
+            complete,
+        }
+    }
+
+    #[test]
+    fn dir_sizes_are_cached_till_the_directories_change() {
+        // GIVEN
+        let mut model = Model::new(
+            vec![PathBuf::from("/home")],
+            Settings::default(),
+            (80, 24).into(),
+            false,
+        );
+        let (a, b) = (dir_entry("/home/a", 1), dir_entry("/home/b", 1));
+        read_into_current_session(&mut model, "/home", vec![a.clone(), b.clone()]);
+        let (id, dirs) = model
+            .start_dir_size_calculation()
+            .expect("calculation should've been started");
+
+        // WHEN
+        model.set_dir_size(id, PathBuf::from("/home/a"), dir_size(10, &a, false));
+        let partial = model.dir_sizes.get(&a);
+        model.set_dir_size(id, PathBuf::from("/home/a"), dir_size(20, &a, true));
+        model.set_dir_size(id, PathBuf::from("/home/b"), dir_size(30, &b, true));
+        let restarted_when_up_to_date = model.start_dir_size_calculation();
+
+        let changed_a = dir_entry("/home/a", 2);
+        read_into_current_session(&mut model, "/home", vec![changed_a.clone(), b.clone()]);
+        let restarted_after_change = model.start_dir_size_calculation();
+
+        // THEN
+        assert_eq!(dirs.len(), 2);
+        assert_eq!(partial, Some(dir_size(10, &a, false)));
+        assert_eq!(model.dir_sizes.get(&a), Some(dir_size(20, &a, true)));
+        assert!(restarted_when_up_to_date.is_none());
+        assert_eq!(model.dir_sizes.get(&changed_a), None);
+        assert_eq!(model.dir_sizes.get(&b), Some(dir_size(30, &b, true)));
+        assert_eq!(
+            restarted_after_change.map(|(_, dirs)| dirs),
+            Some(vec![(PathBuf::from("/home/a"), changed_a.modified())])
+        );
+    }
+
+    #[test]
+    fn leaving_a_directory_cancels_the_calculation_of_its_sizes() {
+        // GIVEN
+        let mut model = Model::new(
+            vec![PathBuf::from("/home")],
+            Settings::default(),
+            (80, 24).into(),
+            false,
+        );
+        let (a, b) = (dir_entry("/home/a", 1), dir_entry("/home/b", 1));
+        read_into_current_session(&mut model, "/home", vec![a.clone(), b.clone()]);
+        let (id, _) = model
+            .start_dir_size_calculation()
+            .expect("calculation should've been started");
+        let ended_before_leaving = model.end_dir_size_calculation_if_left();
+
+        // WHEN
+        read_into_current_session(&mut model, "/home/a", vec![]);
+        let ended_after_leaving = model.end_dir_size_calculation_if_left();
+        model.set_dir_size(id, PathBuf::from("/home/a"), dir_size(10, &a, false));
+        model.set_dir_size(id, PathBuf::from("/home/b"), dir_size(30, &b, true));
+
+        // THEN
+        assert!(!ended_before_leaving);
+        assert!(ended_after_leaving);
+        assert!(model.dir_size_calculation.is_none());
+        // partial sizes of a cancelled calculation won't ever be completed, unlike complete ones
+        assert_eq!(model.dir_sizes.get(&a), None);
+        assert_eq!(model.dir_sizes.get(&b), Some(dir_size(30, &b, true)));
+    }
+
+    #[test]
+    fn disk_usage_acts_only_on_marks_within_the_scan() {
+        // GIVEN
+        let mut model = Model::new(
+            vec![PathBuf::from("/home")],
+            Settings::default(),
+            (80, 24).into(),
+            false,
+        );
+        read_into_current_session(&mut model, "/home", entries("/home", &["a.txt", "b.txt"]));
+        model
+            .marked_paths
+            .extend(entries("/elsewhere", &["other.txt"]));
+        let (scan_id, _) = model
+            .open_disk_usage()
+            .expect("disk usage should've been opened");
+        let files = entries("/home", &["a.txt", "b.txt"])
+            .into_iter()
+            .zip([10, 20])
+            .map(|(entry, size)| UsageNode::new(entry, size, vec![]))
+            .collect();
+        let home = Entry::new(PathBuf::from("/home"), EntryKind::Directory);
+        model.set_disk_usage_tree(scan_id, UsageNode::new(home, 30, files));
+
+        // WHEN
+        let without_marks_in_scan = model.marked_or_selected_entries();
+        model.marked_paths.extend(entries("/home", &["a.txt"]));
+        let with_marks_in_scan = model.marked_or_selected_entries();
+
+        // THEN
+        let paths = |entries: Vec<Entry>| {
+            entries
+                .iter()
+                .map(|e| e.path().to_path_buf())
+                .collect::<Vec<_>>()
+        };
+        // the largest entry is under the cursor
+        assert_eq!(paths(without_marks_in_scan), [PathBuf::from("/home/b.txt")]);
+        assert_eq!(paths(with_marks_in_scan), [PathBuf::from("/home/a.txt")]);
+    }
+
+    #[test]
+    fn git_statuses_of_directories_changing_while_being_read_are_read_once_more() {
+        // GIVEN
+        let mut model = Model::new(
+            vec![PathBuf::from("/home")],
+            Settings::default(),
+            (80, 24).into(),
+            false,
+        );
+        read_into_current_session(&mut model, "/home", entries("/home", &["a.txt"]));
+        let dir = PathBuf::from("/home");
+        let first_read = model
+            .start_git_status_read(dir.clone())
+            .expect("read should've been started");
+
+        // WHEN
+        let while_reading = [
+            model.start_git_status_read(dir.clone()),
+            model.start_git_status_read(dir.clone()),
+        ];
+        let after_first_read = model.set_git_status(dir.clone(), first_read, None);
+        let second_read = after_first_read.expect("read should've been started again");
+        let after_second_read = model.set_git_status(dir.clone(), second_read, None);
+
+        // THEN
+        assert_eq!(while_reading, [None, None]);
+        assert_ne!(second_read, first_read);
+        assert_eq!(after_second_read, None);
+        assert!(model.git_status_requests.is_empty());
+    }
+
+    // runs the message through update, finishing any directory reads it starts as if each
+    // directory had a single file in it
+    fn update_and_read(model: &mut Model, msg: Msg) {
+        for cmd in update(model, msg) {
+            if let Cmd::ReadDir {
+                session_info,
+                read_id,
+            } = cmd
+            {
+                let contents = vec![Entry::new(session_info.path.join("file"), EntryKind::File)];
+                update(
+                    model,
+                    Msg::DirectoryRead {
+                        session_info,
+                        read_id,
+                        entries: contents,
+                        done: true,
+                    },
+                );
+            }
+        }
+    }
+
+    fn model_with_history(dirs: &[&str]) -> Model {
+        let mut model = Model::new(
+            vec![PathBuf::from(dirs[0])],
+            Settings::default(),
+            (80, 24).into(),
+            false,
+        );
+        read_into_current_session(&mut model, dirs[0], entries(dirs[0], &["file"]));
+        for dir in &dirs[1..] {
+            update_and_read(&mut model, Msg::NavigateToDir(PathBuf::from(dir)));
+        }
+
+        model
+    }
+
+    #[test]
+    fn going_back_and_forward_moves_through_the_history_without_changing_it() {
+        // GIVEN
+        let mut model = model_with_history(&["/a", "/a/b", "/a/b/c"]);
+        let mut visited = vec![];
+
+        // WHEN
+        for msg in [
+            Msg::GoBackInHistory,
+            Msg::GoBackInHistory,
+            Msg::GoBackInHistory,
+            Msg::GoForwardInHistory,
+            Msg::GoForwardInHistory,
+            Msg::GoForwardInHistory,
+        ] {
+            update_and_read(&mut model, msg);
+            let path = model
+                .current_session_path()
+                .expect("session should have a path");
+            let history = model
+                .current_history()
+                .expect("session should have a history");
+            visited.push((path, history.current(), history.dirs().len()));
+        }
+
+        // THEN
+        let p = PathBuf::from;
+        assert_eq!(
+            visited,
+            [
+                (p("/a/b"), 1, 3),
+                (p("/a"), 0, 3),
+                (p("/a"), 0, 3),
+                (p("/a/b"), 1, 3),
+                (p("/a/b/c"), 2, 3),
+                (p("/a/b/c"), 2, 3),
+            ]
+        );
+        assert_eq!(
+            model.user_msg.map(|msg| msg.value),
+            Some("no directory to go forward to".to_string())
+        );
+    }
+
+    #[test]
+    fn directories_picked_from_the_history_menu_are_gone_to() {
+        // GIVEN
+        let mut model = model_with_history(&["/a", "/a/b", "/a/b/c"]);
+        update_and_read(&mut model, Msg::GoBackInHistory);
+        update_and_read(&mut model, Msg::GoBackInHistory);
+
+        // WHEN
+        update(&mut model, Msg::ShowHistory);
+        let menu = model.history_menu.as_ref().expect("menu should be shown");
+        let (listed, current, index_of_current) = (
+            menu.dirs.clone(),
+            menu.current,
+            menu.selected_history_index(),
+        );
+        update(&mut model, Msg::SelectFirst);
+        let index_of_first = model
+            .history_menu
+            .as_ref()
+            .and_then(|menu| menu.selected_history_index());
+        update_and_read(&mut model, Msg::GoToSelectedHistoryEntry);
+
+        // THEN
+        // the menu lists the most recent directory first
+        assert_eq!(
+            listed,
+            [
+                PathBuf::from("/a/b/c"),
+                PathBuf::from("/a/b"),
+                PathBuf::from("/a"),
+            ]
+        );
+        assert_eq!(current, 2);
+        assert_eq!(index_of_current, Some(0));
+        assert_eq!(index_of_first, Some(2));
+        assert!(model.history_menu.is_none());
+        assert_eq!(model.active_pane, Pane::Explorer);
+        assert_eq!(model.current_session_path(), Some(PathBuf::from("/a/b/c")));
+        let history = model
+            .current_history()
+            .expect("session should have a history");
+        assert_eq!(history.current(), 2);
+        assert_eq!(history.dirs().len(), 3);
+    }
+
+    #[test]
+    fn a_lone_session_restored_into_a_later_slot_survives_switching_sessions() {
+        // GIVEN
+        let mut model = Model::new(
+            vec![PathBuf::from("/home")],
+            Settings::default(),
+            (80, 24).into(),
+            false,
+        );
+        let state = WorkspaceState {
+            sessions: vec![
+                None,
+                Some(SessionState {
+                    path: PathBuf::from("/home/dir"),
+                    selected: None,
+                }),
+            ],
+            current_session_index: 1,
+            ..Default::default()
+        };
+        model.restore_workspace_state(state, vec![]);
+
+        // WHEN
+        update(&mut model, Msg::GoToNextSession);
+        let after_first = model.current_session_index;
+        update(&mut model, Msg::GoToNextSession);
+        update(&mut model, Msg::GoToPreviousSession);
+
+        // THEN
+        let paths = model
+            .sessions
+            .iter()
+            .map(|s| match s {
+                Session::Uninitialized => None,
+                Session::Initialized { path, .. } => Some(path.clone()),
+            })
+            .collect::<Vec<_>>();
+        let dir = Some(PathBuf::from("/home/dir"));
+        assert_eq!(after_first, 2);
+        assert_eq!(model.current_session_index, 2);
+        assert_eq!(paths, [None, dir.clone(), dir, None]);
+    }
+
+    #[test]
+    fn switching_sessions_without_any_open_stays_put() {
+        // GIVEN
+        let mut model = Model::new(
+            vec![PathBuf::from("/home")],
+            Settings::default(),
+            (80, 24).into(),
+            false,
+        );
+        model.sessions = vec![Session::Uninitialized; MAX_NUM_SESSIONS];
+
+        // WHEN
+        update(&mut model, Msg::GoToNextSession);
+        update(&mut model, Msg::GoToPreviousSession);
+
+        // THEN
+        assert_eq!(model.current_session_index, 0);
+    }
+
+    #[test]
+    fn typed_paths_are_checked_off_the_update_loop_before_being_gone_to() {
+        // GIVEN
+        let mut model = Model::new(
+            vec![PathBuf::from("/home")],
+            Settings::default(),
+            (80, 24).into(),
+            false,
+        );
+        read_into_current_session(&mut model, "/home", entries("/home", &["a.txt"]));
+        update(&mut model, Msg::OpenPrompt(PromptKind::GoToPath));
+        if let Some(prompt) = &mut model.prompt {
+            prompt.input = "/srv/notes.txt".to_string();
+        }
+
+        // WHEN
+        let submitted = update(&mut model, Msg::SubmitPrompt);
+        let file_checked = update(
+            &mut model,
+            Msg::GoToPathChecked {
+                path: PathBuf::from("/srv/notes.txt"),
+                is_dir: Ok(false),
+            },
+        );
+        let missing_checked = update(
+            &mut model,
+            Msg::GoToPathChecked {
+                path: PathBuf::from("/nowhere"),
+                is_dir: Err(std::io::ErrorKind::NotFound.into()),
+            },
+        );
+
+        // THEN
+        assert!(
+            matches!(submitted.as_slice(), [Cmd::CheckGoToPath(path)] if path == Path::new("/srv/notes.txt"))
+        );
+        // the file's directory is read, and the file is selected once it is
+        assert!(matches!(
+            file_checked.as_slice(),
+            [Cmd::ReadDir { session_info, .. }] if session_info.path == Path::new("/srv")
+        ));
+        assert_eq!(
+            model.last_selections.get(Path::new("/srv")),
+            Some(&PathBuf::from("/srv/notes.txt"))
+        );
+        assert!(missing_checked.is_empty());
+        assert!(
+            model
+                .user_msg
+                .is_some_and(|msg| msg.value.starts_with("couldn't go to path"))
+        );
+    }
+
+    #[test]
+    fn entries_from_superseded_reads_are_discarded() {
+        // GIVEN
+        let mut model = Model::new(
+            vec![PathBuf::from("/home")],
+            Settings::default(),
+            (80, 24).into(),
+            false,
+        );
+        let first_read = model
+            .start_dir_read(&session_info("/home/a"), true)
+            .expect("read should've been started");
+        let second_read = model
+            .start_dir_read(&session_info("/home/b"), true)
+            .expect("read should've been started");
+
+        // WHEN
+        model.add_directory_chunk(
+            session_info("/home/b"),
+            second_read,
+            entries("/home/b", &["b1"]),
+            false,
+        );
+        model.add_directory_chunk(
+            session_info("/home/a"),
+            first_read,
+            entries("/home/a", &["a1"]),
+            true,
+        );
+        model.add_directory_chunk(
+            session_info("/home/b"),
+            second_read,
+            entries("/home/b", &["b2"]),
+            true,
+        );
+
+        // THEN
+        let session_path = model.current_session_path();
+        let paths = model
+            .current_session()
+            .entries()
+            .iter()
+            .map(|item| item.entry.path_str())
+            .collect::<Vec<_>>();
+        assert_eq!(session_path, Some(PathBuf::from("/home/b")));
+        assert_yaml_snapshot!(paths, @r"
+        - b1
+        - b2
+        ");
+        assert!(model.dir_reads.is_empty());
+    }
+
+    #[test]
+    fn entries_read_for_closed_sessions_are_discarded() {
+        // GIVEN
+        let mut model = Model::new(
+            vec![PathBuf::from("/home"), PathBuf::from("/tmp")],
+            Settings::default(),
+            (80, 24).into(),
+            false,
+        );
+        model.go_to_session(1);
+        let session_info = SessionInfo {
+            index: 1,
+            path: PathBuf::from("/tmp/a"),
+        };
+        let read = model
+            .start_dir_read(&session_info, true)
+            .expect("read should've been started");
+
+        // WHEN
+        model.go_back_or_quit();
+        let added = model.add_directory_chunk(session_info, read, entries("/tmp/a", &["a1"]), true);
+
+        // THEN
+        assert!(!added);
+        assert!(!model.sessions[1].is_initialized());
+        assert_eq!(model.current_session_index, 0);
+        assert_eq!(model.running_state, RunningState::Running);
+        assert!(model.dir_reads.is_empty());
+    }
+}
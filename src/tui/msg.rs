@@ -1,95 +1,448 @@
-use crate::domain::Entry;
+use crate::domain::{CommandOutput, Entry, GitAction, GitStatus, UsageNode};
 
 use super::common::{Pane, SessionInfo};
-use super::model::Model;
-use ratatui::crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
+use super::keymap::{Action, Key, Lookup};
+use super::model::{DirSize, HeaderTarget, Model, PreviewRequest};
+use super::prompt::{PromptEdit, PromptKind};
+use ratatui::crossterm::event::{
+    Event, KeyCode, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use ratatui::layout::Position;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+// the most time there can be between the clicks of a double click
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
 
 #[derive(Debug)]
 pub enum Msg {
     // user actions
+    AcceptConfirmation,
+    CompletePrompt {
+        reverse: bool,
+    },
+    ComputeDirSizes,
+    ConfirmChoice,
     CopyMarkedItems,
+    CycleSortMode,
+    DeleteEntries,
+    EditPrompt(PromptEdit),
+    EnterDiskUsageDir,
+    GitAction(GitAction),
+    GoBackInHistory,
     GoBackOrQuit,
+    GoForwardInHistory,
     GoToNextSession,
     GoToPane(Pane),
     GoToPreviousSession,
+    GoToSelectedHistoryEntry,
     GoToSession(usize),
+    LeaveDiskUsageDir,
     MarkPath,
     MoveMarkedItems,
     NavigateIntoDir,
     NavigateOutOfDir,
+    NavigateToDir(PathBuf),
+    OpenPrompt(PromptKind),
+    OpenWithSelectedOption,
     QuitImmediately,
+    QuitIntoDirUnderCursor,
+    RunCustomCommand(usize),
     SelectFirst,
+    SelectIndex(usize),
     SelectLast,
     SelectNext,
     SelectPrevious,
+    SubmitPrompt,
+    ShowDiskUsage,
+    ShowHistory,
+    StartAncestorJump,
+    ShowOpenWithMenu,
     TerminalResize(u16, u16),
+    ToggleHiddenEntries,
+    ToggleDiffs,
+    TogglePreview,
+    TrashEntries,
+    ViewFile,
     // internal
-    FSOperationFinished(anyhow::Result<()>),
+    CommandOutputCaptured {
+        command_line: String,
+        result: anyhow::Result<CommandOutput>,
+        refresh: bool,
+    },
+    ExternalCommandFinished {
+        result: anyhow::Result<()>,
+        refresh: bool,
+    },
+    DirectoriesChanged(Vec<PathBuf>),
+    DirSizeComputed {
+        calculation_id: u64,
+        path: PathBuf,
+        size: DirSize,
+    },
+    DiskUsageScanProgress {
+        scan_id: u64,
+        num_scanned: u64,
+        size_scanned: u64,
+    },
+    DiskUsageScanned {
+        scan_id: u64,
+        result: anyhow::Result<UsageNode>,
+    },
+    FSOperationFinished {
+        result: anyhow::Result<()>,
+        // paths trashed or deleted, which are gone from the disk usage tree as well
+        removed: Vec<PathBuf>,
+    },
+    GitActionFinished(anyhow::Result<()>),
+    GitStatusRead {
+        dir: PathBuf,
+        request_id: u64,
+        // None if the directory isn't in a repository
+        status: Option<GitStatus>,
+    },
+    // the path typed into the go to prompt, and whether it's a directory
+    GoToPathChecked {
+        path: PathBuf,
+        is_dir: std::io::Result<bool>,
+    },
+    PathCompletionsRead {
+        input: String,
+        result: anyhow::Result<Vec<String>>,
+    },
+    PreviewLoaded {
+        request: PreviewRequest,
+        result: anyhow::Result<Vec<String>>,
+    },
+    // a chunk of a directory's entries; directories are read in chunks, so that huge ones can
+    // be shown before they're read completely
     DirectoryRead {
         session_info: SessionInfo,
+        read_id: u64,
         entries: Vec<Entry>,
-        navigated_to: bool,
+        // whether this is the last chunk
+        done: bool,
+    },
+    ReadingDirFailed {
+        session_index: usize,
+        read_id: u64,
+        error: String,
     },
-    ReadingDirFailed(String),
 }
 
-pub fn get_event_handling_msg(model: &Model, event: Event) -> Option<Msg> {
+pub fn get_event_handling_msg(model: &mut Model, event: Event) -> Option<Msg> {
     match event {
-        Event::Key(key_event) => match model.terminal_too_small {
-            true => match key_event.kind {
-                KeyEventKind::Press => match key_event.code {
+        Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+            if model.terminal_too_small {
+                return match key_event.code {
                     KeyCode::Esc | KeyCode::Char('q') => Some(Msg::GoBackOrQuit),
                     _ => None,
-                },
-                _ => None,
-            },
-            false => match key_event.kind {
-                KeyEventKind::Press => match model.active_pane {
-                    Pane::Explorer => match key_event.code {
-                        KeyCode::Char(' ') => Some(Msg::MarkPath),
-                        KeyCode::Char('j') | KeyCode::Down => Some(Msg::SelectNext),
-                        KeyCode::Char('k') | KeyCode::Up => Some(Msg::SelectPrevious),
-                        KeyCode::Char('g') => Some(Msg::SelectFirst),
-                        KeyCode::Char('G') => Some(Msg::SelectLast),
-                        KeyCode::Tab => Some(Msg::GoToNextSession),
-                        KeyCode::Char('1') => Some(Msg::GoToSession(0)),
-                        KeyCode::Char('2') => Some(Msg::GoToSession(1)),
-                        KeyCode::Char('3') => Some(Msg::GoToSession(2)),
-                        KeyCode::Char('4') => Some(Msg::GoToSession(3)),
-                        KeyCode::BackTab => Some(Msg::GoToPreviousSession),
-                        KeyCode::Char('l') | KeyCode::Right => Some(Msg::NavigateIntoDir),
-                        KeyCode::Char('h') | KeyCode::Left => Some(Msg::NavigateOutOfDir),
-                        KeyCode::Char('p') if !model.marked_paths.is_empty() => {
-                            Some(Msg::CopyMarkedItems)
-                        }
-                        KeyCode::Char('v') if !model.marked_paths.is_empty() => {
-                            Some(Msg::MoveMarkedItems)
-                        }
-                        KeyCode::Esc | KeyCode::Char('q') => Some(Msg::GoBackOrQuit),
-                        KeyCode::Char('c') if key_event.modifiers == KeyModifiers::CONTROL => {
-                            Some(Msg::QuitImmediately)
-                        }
-                        KeyCode::Char('?') => Some(Msg::GoToPane(Pane::Help)),
-                        _ => None,
-                    },
-                    Pane::Help => match key_event.code {
-                        KeyCode::Char('?') | KeyCode::Char('q') | KeyCode::Esc => {
-                            Some(Msg::GoBackOrQuit)
-                        }
-                        KeyCode::Char('c') => {
-                            if key_event.modifiers == KeyModifiers::CONTROL {
-                                Some(Msg::QuitImmediately)
-                            } else {
-                                None
-                            }
+                };
+            }
+
+            if model.active_pane == Pane::Prompt {
+                return get_prompt_msg(key_event.code, key_event.modifiers);
+            }
+
+            // keys that don't jump anywhere cancel the jump
+            if model.active_pane == Pane::AncestorJump {
+                return match key_event.code {
+                    KeyCode::Char(c)
+                        if key_event
+                            .modifiers
+                            .difference(KeyModifiers::SHIFT)
+                            .is_empty() =>
+                    {
+                        match model.ancestor_for_key(c) {
+                            Some(path) => Some(Msg::NavigateToDir(path)),
+                            None => Some(Msg::GoBackOrQuit),
                         }
-                        _ => None,
-                    },
-                },
-                _ => None,
-            },
-        },
+                    }
+                    _ => Some(Msg::GoBackOrQuit),
+                };
+            }
+
+            get_keymap_msg(model, Key::from(key_event))
+        }
+        Event::Mouse(mouse_event) if !model.terminal_too_small => get_mouse_msg(model, mouse_event),
         Event::Resize(w, h) => Some(Msg::TerminalResize(w, h)),
         _ => None,
     }
 }
+
+fn get_mouse_msg(model: &mut Model, event: MouseEvent) -> Option<Msg> {
+    match event.kind {
+        MouseEventKind::ScrollDown => Some(Msg::SelectNext),
+        MouseEventKind::ScrollUp => Some(Msg::SelectPrevious),
+        MouseEventKind::Down(MouseButton::Left) => {
+            let position = Position::new(event.column, event.row);
+
+            if model.active_pane == Pane::Explorer
+                && let Some((_, target)) = model
+                    .click_areas
+                    .header
+                    .iter()
+                    .find(|(rect, _)| rect.contains(position))
+            {
+                return match target {
+                    HeaderTarget::Session(index) => Some(Msg::GoToSession(*index)),
+                    HeaderTarget::Dir(path) => Some(Msg::NavigateToDir(path.clone())),
+                };
+            }
+
+            let index = model.list_index_at(position)?;
+            let now = Instant::now();
+            let double_click = model
+                .last_click
+                .take()
+                .is_some_and(|(clicked_at, clicked)| {
+                    clicked == index && now.duration_since(clicked_at) <= DOUBLE_CLICK_INTERVAL
+                });
+            if double_click {
+                return get_action_msg(model, Action::Open);
+            }
+
+            model.last_click = Some((now, index));
+            Some(Msg::SelectIndex(index))
+        }
+        _ => None,
+    }
+}
+
+fn get_keymap_msg(model: &mut Model, key: Key) -> Option<Msg> {
+    // escape abandons a half typed key sequence
+    if !model.pending_keys.is_empty() && key.is_esc() {
+        model.pending_keys.clear();
+        return None;
+    }
+
+    model.pending_keys.push(key);
+    match model.keymap.lookup(model.active_pane, &model.pending_keys) {
+        Lookup::Matched(action) => {
+            model.pending_keys.clear();
+            get_action_msg(model, action)
+        }
+        Lookup::Pending => None,
+        Lookup::Unmatched => {
+            let was_sequence = model.pending_keys.len() > 1;
+            model.pending_keys.clear();
+
+            // the key that broke the sequence might be a binding on its own
+            if was_sequence {
+                get_keymap_msg(model, key)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+fn get_action_msg(model: &Model, action: Action) -> Option<Msg> {
+    let msg = match action {
+        Action::SelectNext => Msg::SelectNext,
+        Action::SelectPrevious => Msg::SelectPrevious,
+        Action::SelectFirst => Msg::SelectFirst,
+        Action::SelectLast => Msg::SelectLast,
+        Action::Open if model.active_pane == Pane::DiskUsage => Msg::EnterDiskUsageDir,
+        Action::Open => Msg::NavigateIntoDir,
+        Action::Confirm => match model.active_pane {
+            Pane::Explorer if model.chooser.is_some() => Msg::ConfirmChoice,
+            Pane::Explorer => Msg::NavigateIntoDir,
+            Pane::OpenWith => Msg::OpenWithSelectedOption,
+            Pane::History => Msg::GoToSelectedHistoryEntry,
+            Pane::Confirmation => Msg::AcceptConfirmation,
+            Pane::Help
+            | Pane::Prompt
+            | Pane::CommandOutput
+            | Pane::DiskUsage
+            | Pane::AncestorJump => return None,
+        },
+        Action::Parent if model.active_pane == Pane::DiskUsage => Msg::LeaveDiskUsageDir,
+        Action::Parent => Msg::NavigateOutOfDir,
+        Action::NextSession => Msg::GoToNextSession,
+        Action::PreviousSession => Msg::GoToPreviousSession,
+        Action::GoToSession1 => Msg::GoToSession(0),
+        Action::GoToSession2 => Msg::GoToSession(1),
+        Action::GoToSession3 => Msg::GoToSession(2),
+        Action::GoToSession4 => Msg::GoToSession(3),
+        Action::Mark => Msg::MarkPath,
+        Action::CopyMarked => Msg::CopyMarkedItems,
+        Action::MoveMarked => Msg::MoveMarkedItems,
+        Action::Trash => Msg::TrashEntries,
+        Action::Delete => Msg::DeleteEntries,
+        Action::View => Msg::ViewFile,
+        Action::OpenWith => Msg::ShowOpenWithMenu,
+        Action::ShellCommand => Msg::OpenPrompt(PromptKind::ShellCommand {
+            capture_output: true,
+        }),
+        Action::InteractiveShellCommand => Msg::OpenPrompt(PromptKind::ShellCommand {
+            capture_output: false,
+        }),
+        Action::GoToPath => Msg::OpenPrompt(PromptKind::GoToPath),
+        Action::HistoryBack => Msg::GoBackInHistory,
+        Action::HistoryForward => Msg::GoForwardInHistory,
+        Action::ShowHistory => Msg::ShowHistory,
+        Action::ToggleHidden => Msg::ToggleHiddenEntries,
+        Action::CycleSort => Msg::CycleSortMode,
+        Action::TogglePreview => Msg::TogglePreview,
+        Action::ToggleDiffs => Msg::ToggleDiffs,
+        Action::GitStage => Msg::GitAction(GitAction::Stage),
+        Action::GitUnstage => Msg::GitAction(GitAction::Unstage),
+        Action::GitDiscard => Msg::GitAction(GitAction::Discard),
+        Action::ComputeDirSizes => Msg::ComputeDirSizes,
+        Action::DiskUsage => Msg::ShowDiskUsage,
+        Action::JumpToAncestor => Msg::StartAncestorJump,
+        Action::Help if model.active_pane == Pane::Help => Msg::GoBackOrQuit,
+        Action::Help => Msg::GoToPane(Pane::Help),
+        Action::Back => Msg::GoBackOrQuit,
+        Action::QuitIntoDir => Msg::QuitIntoDirUnderCursor,
+        Action::Quit => Msg::QuitImmediately,
+        Action::RunCustomCommand(index) => Msg::RunCustomCommand(index),
+    };
+
+    Some(msg)
+}
+
+fn get_prompt_msg(code: KeyCode, modifiers: KeyModifiers) -> Option<Msg> {
+    let edit = match code {
+        KeyCode::Enter => return Some(Msg::SubmitPrompt),
+        KeyCode::Esc => return Some(Msg::GoBackOrQuit),
+        KeyCode::Tab => return Some(Msg::CompletePrompt { reverse: false }),
+        KeyCode::BackTab => return Some(Msg::CompletePrompt { reverse: true }),
+        KeyCode::Char('c') if modifiers == KeyModifiers::CONTROL => return Some(Msg::GoBackOrQuit),
+        KeyCode::Char('a') if modifiers == KeyModifiers::CONTROL => PromptEdit::MoveToStart,
+        KeyCode::Char('e') if modifiers == KeyModifiers::CONTROL => PromptEdit::MoveToEnd,
+        KeyCode::Char('u') if modifiers == KeyModifiers::CONTROL => PromptEdit::Clear,
+        KeyCode::Char(c) if !modifiers.contains(KeyModifiers::CONTROL) => PromptEdit::Insert(c),
+        KeyCode::Backspace => PromptEdit::DeleteBackward,
+        KeyCode::Delete => PromptEdit::DeleteForward,
+        KeyCode::Left => PromptEdit::MoveLeft,
+        KeyCode::Right => PromptEdit::MoveRight,
+        KeyCode::Home => PromptEdit::MoveToStart,
+        KeyCode::End => PromptEdit::MoveToEnd,
+        _ => return None,
+    };
+
+    Some(Msg::EditPrompt(edit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::common::Settings;
+    use super::super::model::Session;
+    use super::*;
+    use crate::domain::EntryKind;
+    use ratatui::layout::Rect;
+    use std::path::Path;
+
+    fn model_listing(num_entries: usize) -> Model {
+        let mut model = Model::new(
+            vec![PathBuf::from("/home")],
+            Settings::default(),
+            (80, 24).into(),
+            false,
+        );
+        let session_info = SessionInfo {
+            index: 0,
+            path: PathBuf::from("/home"),
+        };
+        let read = model
+            .start_dir_read(&session_info, true)
+            .expect("read should've been started");
+        let entries = (0..num_entries)
+            .map(|i| Entry::new(PathBuf::from(format!("/home/{i:02}")), EntryKind::File))
+            .collect();
+        model.add_directory_chunk(session_info, read, entries, true);
+        // entries are listed from the third row on
+        model.click_areas.list = Some(Rect::new(0, 2, 80, 10));
+
+        model
+    }
+
+    fn click(column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn clicking_an_entry_twice_in_quick_succession_opens_it() {
+        // GIVEN
+        let mut model = model_listing(5);
+
+        // WHEN
+        let first = get_mouse_msg(&mut model, click(4, 3));
+        let second = get_mouse_msg(&mut model, click(4, 3));
+        let third = get_mouse_msg(&mut model, click(4, 3));
+        let elsewhere = get_mouse_msg(&mut model, click(4, 4));
+        model.last_click = Instant::now()
+            .checked_sub(DOUBLE_CLICK_INTERVAL + Duration::from_millis(100))
+            .map(|clicked_at| (clicked_at, 2));
+        let too_late = get_mouse_msg(&mut model, click(4, 4));
+
+        // THEN
+        assert!(matches!(first, Some(Msg::SelectIndex(1))));
+        assert!(matches!(second, Some(Msg::NavigateIntoDir)));
+        // a double click isn't the first half of another one
+        assert!(matches!(third, Some(Msg::SelectIndex(1))));
+        assert!(matches!(elsewhere, Some(Msg::SelectIndex(2))));
+        assert!(matches!(too_late, Some(Msg::SelectIndex(2))));
+    }
+
+    #[test]
+    fn clicks_on_the_header_go_to_what_was_clicked() {
+        // GIVEN
+        let mut model = model_listing(5);
+        model.click_areas.header = vec![
+            (Rect::new(0, 0, 3, 1), HeaderTarget::Session(1)),
+            (Rect::new(4, 0, 6, 1), HeaderTarget::Dir(PathBuf::from("/"))),
+        ];
+
+        // WHEN
+        let on_session = get_mouse_msg(&mut model, click(1, 0));
+        let on_dir = get_mouse_msg(&mut model, click(9, 0));
+        let in_between = get_mouse_msg(&mut model, click(3, 0));
+
+        // THEN
+        assert!(matches!(on_session, Some(Msg::GoToSession(1))));
+        assert!(matches!(on_dir, Some(Msg::NavigateToDir(path)) if path == Path::new("/")));
+        assert!(in_between.is_none());
+    }
+
+    #[test]
+    fn clicked_rows_map_to_entries_past_the_scroll_offset() {
+        // GIVEN
+        let mut model = model_listing(20);
+        if let Session::Initialized { state, .. } = model.current_session_mut() {
+            *state.offset_mut() = 5;
+        }
+
+        // WHEN
+        let first_row = model.list_index_at(Position::new(0, 2));
+        let last_row = model.list_index_at(Position::new(0, 11));
+        let below_list = model.list_index_at(Position::new(0, 12));
+        let above_list = model.list_index_at(Position::new(0, 1));
+
+        // THEN
+        assert_eq!(first_row, Some(5));
+        assert_eq!(last_row, Some(14));
+        assert_eq!(below_list, None);
+        assert_eq!(above_list, None);
+    }
+
+    #[test]
+    fn clicks_past_the_last_entry_select_nothing() {
+        // GIVEN
+        let mut model = model_listing(3);
+
+        // WHEN
+        let msg = get_mouse_msg(&mut model, click(0, 6));
+
+        // THEN
+        assert!(msg.is_none());
+        assert!(model.last_click.is_none());
+    }
+}
@@ -0,0 +1,234 @@
+/// What the text entered in a prompt is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    ShellCommand { capture_output: bool },
+    GoToPath,
+}
+
+impl PromptKind {
+    pub(super) fn prefix(&self) -> &'static str {
+        match self {
+            PromptKind::ShellCommand {
+                capture_output: true,
+            } => ":",
+            PromptKind::ShellCommand {
+                capture_output: false,
+            } => "!",
+            PromptKind::GoToPath => "cd ",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptEdit {
+    Insert(char),
+    DeleteBackward,
+    DeleteForward,
+    MoveLeft,
+    MoveRight,
+    MoveToStart,
+    MoveToEnd,
+    Clear,
+}
+
+/// What a prompt's input can be completed to; tabbing through them puts each in the input.
+#[derive(Debug, Clone)]
+pub struct Completions {
+    pub candidates: Vec<String>,
+    pub selected: Option<usize>,
+}
+
+/// A single line text input; `cursor` is a char (not byte) index into `input`.
+#[derive(Debug, Clone)]
+pub struct Prompt {
+    pub kind: PromptKind,
+    pub input: String,
+    pub cursor: usize,
+    pub completions: Option<Completions>,
+}
+
+impl Prompt {
+    pub(super) fn new(kind: PromptKind) -> Self {
+        Self {
+            kind,
+            input: String::new(),
+            cursor: 0,
+            completions: None,
+        }
+    }
+
+    /// Splits the input, as a path, into the directory part as typed, and the start of the name
+    /// of an entry in it.
+    pub(super) fn path_to_complete(&self) -> (&str, &str) {
+        match self.input.rfind(['/', std::path::MAIN_SEPARATOR]) {
+            Some(index) => self.input.split_at(index + 1),
+            None => ("", &self.input),
+        }
+    }
+
+    /// Completes the input with the names of entries matching it: a single match replaces it, and
+    /// several are listed, with the input extended to what they have in common.
+    pub(super) fn complete(&mut self, names: Vec<String>) {
+        let (dir, _) = self.path_to_complete();
+        let candidates = names
+            .into_iter()
+            .map(|name| format!("{dir}{name}"))
+            .collect::<Vec<_>>();
+
+        match candidates.as_slice() {
+            [] => {}
+            [candidate] => self.set_input(candidate.clone()),
+            [first, rest @ ..] => {
+                let common_prefix = rest.iter().fold(first.as_str(), |prefix, candidate| {
+                    let len = prefix
+                        .char_indices()
+                        .zip(candidate.chars())
+                        .find(|((_, a), b)| a != b)
+                        .map(|((i, _), _)| i)
+                        .unwrap_or(prefix.len().min(candidate.len()));
+                    &prefix[..len]
+                });
+                if common_prefix.len() > self.input.len() {
+                    self.set_input(common_prefix.to_string());
+                }
+                self.completions = Some(Completions {
+                    candidates,
+                    selected: None,
+                });
+            }
+        }
+    }
+
+    /// Puts the next (or the previous) completion in the input; returns false if there are none
+    /// to cycle through.
+    pub(super) fn cycle_completions(&mut self, reverse: bool) -> bool {
+        let Some(completions) = &mut self.completions else {
+            return false;
+        };
+
+        let num_candidates = completions.candidates.len();
+        let selected = match (completions.selected, reverse) {
+            (None, false) => 0,
+            (None, true) => num_candidates - 1,
+            (Some(i), false) => (i + 1) % num_candidates,
+            (Some(i), true) => (i + num_candidates - 1) % num_candidates,
+        };
+        completions.selected = Some(selected);
+
+        let input = completions.candidates[selected].clone();
+        self.input = input;
+        self.cursor = self.input.chars().count();
+
+        true
+    }
+
+    fn set_input(&mut self, input: String) {
+        self.cursor = input.chars().count();
+        self.input = input;
+        self.completions = None;
+    }
+
+    pub(super) fn edit(&mut self, edit: PromptEdit) {
+        // completions are for what was typed before
+        self.completions = None;
+
+        let num_chars = self.input.chars().count();
+        match edit {
+            PromptEdit::Insert(c) => {
+                let index = self.byte_index(self.cursor);
+                self.input.insert(index, c);
+                self.cursor += 1;
+            }
+            PromptEdit::DeleteBackward => {
+                if self.cursor > 0 {
+                    self.cursor -= 1;
+                    let index = self.byte_index(self.cursor);
+                    self.input.remove(index);
+                }
+            }
+            PromptEdit::DeleteForward => {
+                if self.cursor < num_chars {
+                    let index = self.byte_index(self.cursor);
+                    self.input.remove(index);
+                }
+            }
+            PromptEdit::MoveLeft => self.cursor = self.cursor.saturating_sub(1),
+            PromptEdit::MoveRight => self.cursor = (self.cursor + 1).min(num_chars),
+            PromptEdit::MoveToStart => self.cursor = 0,
+            PromptEdit::MoveToEnd => self.cursor = num_chars,
+            PromptEdit::Clear => {
+                self.input.clear();
+                self.cursor = 0;
+            }
+        }
+    }
+
+    fn byte_index(&self, char_index: usize) -> usize {
+        self.input
+            .char_indices()
+            .nth(char_index)
+            .map(|(i, _)| i)
+            .unwrap_or(self.input.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn editing_a_prompt_works_with_multibyte_chars() {
+        // GIVEN
+        let mut prompt = Prompt::new(PromptKind::ShellCommand {
+            capture_output: true,
+        });
+
+        // WHEN
+        for edit in [
+            PromptEdit::Insert('l'),
+            PromptEdit::Insert('s'),
+            PromptEdit::Insert(' '),
+            PromptEdit::Insert('ü'),
+            PromptEdit::Insert('x'),
+            PromptEdit::MoveLeft,
+            PromptEdit::DeleteBackward,
+            PromptEdit::Insert('é'),
+            PromptEdit::MoveToStart,
+            PromptEdit::DeleteForward,
+            PromptEdit::Insert('L'),
+            PromptEdit::MoveToEnd,
+            PromptEdit::Insert('!'),
+        ] {
+            prompt.edit(edit);
+        }
+
+        // THEN
+        assert_eq!(prompt.input, "Ls éx!");
+        assert_eq!(prompt.cursor, 6);
+    }
+
+    #[test]
+    fn path_completions_extend_the_input_and_can_be_cycled_through() {
+        // GIVEN
+        let mut prompt = Prompt::new(PromptKind::GoToPath);
+        for c in "~/pro".chars() {
+            prompt.edit(PromptEdit::Insert(c));
+        }
+
+        // WHEN
+        let to_complete = prompt.path_to_complete();
+        assert_eq!(to_complete, ("~/", "pro"));
+        prompt.complete(vec!["projects/".to_string(), "projections.md".to_string()]);
+        let after_completing = prompt.input.clone();
+        prompt.cycle_completions(false);
+        let after_cycling = prompt.input.clone();
+        prompt.cycle_completions(true);
+        let after_cycling_back = prompt.input.clone();
+
+        // THEN
+        assert_eq!(after_completing, "~/project");
+        assert_eq!(after_cycling, "~/projects/");
+        assert_eq!(after_cycling_back, "~/projections.md");
+        assert_eq!(prompt.cursor, 16);
+    }
+}
@@ -0,0 +1,96 @@
+use ratatui::Terminal;
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{
+    EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+};
+use std::io::{IsTerminal, Stderr, Stdout, Write};
+
+pub(super) type AppTerminal = Terminal<CrosstermBackend<TerminalOutput>>;
+
+/// The stream the TUI is rendered to.
+///
+/// When stdout is not a terminal (eg, when atls is run as `$(atls --print-cwd)`), the TUI is
+/// rendered to stderr instead, leaving stdout free for atls' output.
+pub(super) enum TerminalOutput {
+    Stdout(Stdout),
+    Stderr(Stderr),
+}
+
+impl TerminalOutput {
+    fn get() -> Self {
+        if std::io::stdout().is_terminal() {
+            Self::Stdout(std::io::stdout())
+        } else {
+            Self::Stderr(std::io::stderr())
+        }
+    }
+}
+
+impl Write for TerminalOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Stdout(s) => s.write(buf),
+            Self::Stderr(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Stdout(s) => s.flush(),
+            Self::Stderr(s) => s.flush(),
+        }
+    }
+}
+
+pub(super) fn init_terminal(capture_mouse: bool) -> anyhow::Result<AppTerminal> {
+    set_panic_hook();
+    enable_raw_mode()?;
+    let mut output = TerminalOutput::get();
+    execute!(output, EnterAlternateScreen)?;
+    if capture_mouse {
+        execute!(output, EnableMouseCapture)?;
+    }
+
+    let terminal = Terminal::new(CrosstermBackend::new(output))?;
+
+    Ok(terminal)
+}
+
+pub(super) fn restore_terminal() -> anyhow::Result<()> {
+    // disabling raw mode first is important as it has more side effects than leaving the alternate
+    // screen buffer; disabling mouse capture is harmless if it wasn't enabled
+    disable_raw_mode()?;
+    execute!(
+        TerminalOutput::get(),
+        DisableMouseCapture,
+        LeaveAlternateScreen
+    )?;
+
+    Ok(())
+}
+
+/// Brings the terminal back into the state the TUI needs after it was handed over to another
+/// program via [`restore_terminal`].
+pub(super) fn resume_terminal(
+    terminal: &mut AppTerminal,
+    capture_mouse: bool,
+) -> anyhow::Result<()> {
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+    if capture_mouse {
+        execute!(terminal.backend_mut(), EnableMouseCapture)?;
+    }
+    terminal.clear()?;
+
+    Ok(())
+}
+
+fn set_panic_hook() {
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        hook(info);
+    }));
+}
@@ -0,0 +1,236 @@
+use crate::domain::EntryKind;
+use ratatui::style::Color;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+pub const DEFAULT_THEME: &str = "dark";
+const BUILTIN_THEMES: [&str; 3] = ["dark", "light", "high-contrast"];
+
+/// Colours used throughout the TUI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub primary: Color,
+    pub info: Color,
+    pub error: Color,
+    pub help: Color,
+    pub menu: Color,
+    pub selection: Color,
+    // text drawn on top of the selection, pane titles and other highlighted backgrounds
+    pub selection_fg: Color,
+    pub marked: Color,
+    pub text: Color,
+    // borders, hints, and other secondary text
+    pub muted: Color,
+    pub directory: Color,
+    pub file: Color,
+    pub executable: Color,
+    pub symlink: Color,
+    pub fifo: Color,
+    pub socket: Color,
+    pub device: Color,
+    pub unknown: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    fn dark() -> Self {
+        Self {
+            primary: Color::LightBlue,
+            info: Color::LightBlue,
+            error: Color::LightRed,
+            help: Color::Yellow,
+            menu: Color::LightGreen,
+            selection: Color::Blue,
+            selection_fg: Color::Black,
+            marked: Color::Yellow,
+            text: Color::White,
+            muted: Color::DarkGray,
+            directory: Color::LightRed,
+            file: Color::White,
+            executable: Color::LightGreen,
+            symlink: Color::Magenta,
+            fifo: Color::Yellow,
+            socket: Color::LightMagenta,
+            device: Color::LightYellow,
+            unknown: Color::Gray,
+        }
+    }
+
+    fn light() -> Self {
+        Self {
+            primary: Color::Blue,
+            info: Color::Blue,
+            error: Color::Red,
+            help: Color::Indexed(130),
+            menu: Color::Green,
+            selection: Color::Blue,
+            selection_fg: Color::White,
+            marked: Color::Indexed(130),
+            text: Color::Black,
+            muted: Color::Gray,
+            directory: Color::Blue,
+            file: Color::Black,
+            executable: Color::Green,
+            symlink: Color::Magenta,
+            fifo: Color::Indexed(130),
+            socket: Color::Magenta,
+            device: Color::Red,
+            unknown: Color::DarkGray,
+        }
+    }
+
+    fn high_contrast() -> Self {
+        Self {
+            primary: Color::White,
+            info: Color::LightCyan,
+            error: Color::LightRed,
+            help: Color::LightYellow,
+            menu: Color::LightGreen,
+            selection: Color::White,
+            selection_fg: Color::Black,
+            marked: Color::LightYellow,
+            text: Color::White,
+            muted: Color::Gray,
+            directory: Color::LightCyan,
+            file: Color::White,
+            executable: Color::LightGreen,
+            symlink: Color::LightMagenta,
+            fifo: Color::LightYellow,
+            socket: Color::LightMagenta,
+            device: Color::LightYellow,
+            unknown: Color::Gray,
+        }
+    }
+
+    pub fn entry_color(&self, kind: EntryKind) -> Color {
+        match kind {
+            EntryKind::Directory => self.directory,
+            EntryKind::File => self.file,
+            EntryKind::Executable => self.executable,
+            EntryKind::Symlink => self.symlink,
+            EntryKind::Fifo => self.fifo,
+            EntryKind::Socket => self.socket,
+            EntryKind::BlockDevice | EntryKind::CharDevice => self.device,
+            EntryKind::Unknown => self.unknown,
+        }
+    }
+
+    fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "high-contrast" => Some(Self::high_contrast()),
+            _ => None,
+        }
+    }
+
+    /// Resolves the theme called `name`, which is either a built-in theme or one of `themes`, and
+    /// applies `overrides` on top of it.
+    pub fn resolve(
+        name: &str,
+        themes: &BTreeMap<String, ThemeSpec>,
+        overrides: &ThemeSpec,
+    ) -> Result<Self, String> {
+        if let Some(user_theme) = themes.keys().find(|n| Self::builtin(n).is_some()) {
+            return Err(format!(
+                r#"theme "{user_theme}" has the same name as a built-in theme"#
+            ));
+        }
+
+        if overrides.base.is_some() {
+            return Err(r#""base" can only be set for themes defined under [themes]"#.to_string());
+        }
+
+        let mut theme = match (Self::builtin(name), themes.get(name)) {
+            (Some(theme), _) => theme,
+            (None, Some(spec)) => {
+                let base = spec.base.as_deref().unwrap_or(DEFAULT_THEME);
+                let mut theme = Self::builtin(base).ok_or_else(|| {
+                    format!(
+                        r#"base of theme "{name}" must be one of {}, got "{base}""#,
+                        BUILTIN_THEMES.join(", ")
+                    )
+                })?;
+                spec.apply(&mut theme);
+                theme
+            }
+            (None, None) => {
+                let mut available = BUILTIN_THEMES.map(String::from).to_vec();
+                available.extend(themes.keys().cloned());
+                return Err(format!(
+                    r#"unknown theme "{name}"; available themes: {}"#,
+                    available.join(", ")
+                ));
+            }
+        };
+
+        overrides.apply(&mut theme);
+
+        Ok(theme)
+    }
+}
+
+/// Colours to override in a theme, as written in the config file.
+///
+/// Colours can be named ("lightblue", "bright-white"), hex codes ("#ff8700") or ANSI indexes
+/// ("42").
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeSpec {
+    // built-in theme to start from
+    pub base: Option<String>,
+    pub primary: Option<Color>,
+    pub info: Option<Color>,
+    pub error: Option<Color>,
+    pub help: Option<Color>,
+    pub menu: Option<Color>,
+    pub selection: Option<Color>,
+    pub selection_fg: Option<Color>,
+    pub marked: Option<Color>,
+    pub text: Option<Color>,
+    pub muted: Option<Color>,
+    pub directory: Option<Color>,
+    pub file: Option<Color>,
+    pub executable: Option<Color>,
+    pub symlink: Option<Color>,
+    pub fifo: Option<Color>,
+    pub socket: Option<Color>,
+    pub device: Option<Color>,
+    pub unknown: Option<Color>,
+}
+
+impl ThemeSpec {
+    fn apply(&self, theme: &mut Theme) {
+        let overrides = [
+            (self.primary, &mut theme.primary),
+            (self.info, &mut theme.info),
+            (self.error, &mut theme.error),
+            (self.help, &mut theme.help),
+            (self.menu, &mut theme.menu),
+            (self.selection, &mut theme.selection),
+            (self.selection_fg, &mut theme.selection_fg),
+            (self.marked, &mut theme.marked),
+            (self.text, &mut theme.text),
+            (self.muted, &mut theme.muted),
+            (self.directory, &mut theme.directory),
+            (self.file, &mut theme.file),
+            (self.executable, &mut theme.executable),
+            (self.symlink, &mut theme.symlink),
+            (self.fifo, &mut theme.fifo),
+            (self.socket, &mut theme.socket),
+            (self.device, &mut theme.device),
+            (self.unknown, &mut theme.unknown),
+        ];
+
+        for (color, field) in overrides {
+            if let Some(c) = color {
+                *field = c;
+            }
+        }
+    }
+}
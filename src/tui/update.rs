@@ -2,16 +2,64 @@ use super::cmd::Cmd;
 use super::common::*;
 use super::model::*;
 use super::msg::Msg;
-use crate::domain::FSOperation;
+use super::prompt::PromptKind;
+use crate::domain::{
+    ExternalCommand, FSOperation, GitAction, GitFileStatus, expand_path, expand_placeholders,
+    get_matching_openers,
+};
+use crate::services;
+use std::path::{Path, PathBuf};
 use tracing::debug;
 
+const READ_ONLY_ERROR: &str = "atls is running in read-only mode";
+
 pub fn update(model: &mut Model, msg: Msg) -> Vec<Cmd> {
     debug!("tui got message: {:#?}", &msg);
+    let active_pane = model.active_pane;
     let mut cmds = vec![];
     match msg {
         // user actions
+        Msg::AcceptConfirmation => match model.take_confirmed_action() {
+            Some(ConfirmAction::RunCustomCommand(index)) => {
+                cmds.extend(get_custom_command_cmd(model, index));
+            }
+            Some(ConfirmAction::Trash(items)) => {
+                cmds.push(Cmd::RunFSOperation(FSOperation::Trash { items }));
+            }
+            Some(ConfirmAction::Delete(items)) => {
+                cmds.push(Cmd::RunFSOperation(FSOperation::Delete { items }));
+            }
+            Some(ConfirmAction::DiscardGitChanges(paths)) => {
+                cmds.push(Cmd::RunGitAction {
+                    action: GitAction::Discard,
+                    paths,
+                });
+            }
+            None => {}
+        },
+        Msg::CompletePrompt { reverse } => cmds.extend(get_prompt_completion_cmd(model, reverse)),
+        Msg::ComputeDirSizes => {
+            model.show_sizes = true;
+            if model.dir_size_calculation.is_some() {
+                model.user_msg = Some(UserMsg::info("directory sizes are being computed"));
+            } else {
+                match get_dir_sizes_cmd(model) {
+                    Some(cmd) => cmds.push(cmd),
+                    None => model.user_msg = Some(UserMsg::info("directory sizes are up to date")),
+                }
+            }
+        }
+        Msg::ConfirmChoice => {
+            if let Some(directory_address) = model.get_dir_to_enter_on_confirm() {
+                cmds.extend(get_read_dir_cmd(model, directory_address.into(), true));
+            } else {
+                model.confirm_choice();
+            }
+        }
         Msg::CopyMarkedItems => {
-            if !model.marked_paths.is_empty()
+            if model.read_only {
+                model.user_msg = Some(UserMsg::error(READ_ONLY_ERROR));
+            } else if !model.marked_paths.is_empty()
                 && let Some(session_dir_addr) = model.get_session_path()
             {
                 let items = model.marked_paths.iter().cloned().collect::<Vec<_>>();
@@ -23,7 +71,47 @@ pub fn update(model: &mut Model, msg: Msg) -> Vec<Cmd> {
                 cmds.push(Cmd::RunFSOperation(op));
             }
         }
-        Msg::GoBackOrQuit => model.go_back_or_quit(),
+        Msg::CycleSortMode => {
+            model.cycle_sort_mode();
+            cmds.extend(get_refresh_cmds(model));
+        }
+        Msg::DeleteEntries => ask_to_remove_entries(model, true),
+        Msg::EditPrompt(edit) => model.edit_prompt(edit),
+        Msg::EnterDiskUsageDir => model.enter_disk_usage_dir(),
+        Msg::GitAction(action) => cmds.extend(get_git_action_cmd(model, action)),
+        Msg::GoBackOrQuit => {
+            let was_scanning = model.is_scanning_disk_usage();
+            let session_index = model.current_session_index;
+            model.go_back_or_quit();
+            if was_scanning && model.disk_usage.is_none() {
+                cmds.push(Cmd::CancelDiskUsageScan);
+            }
+            // the session might've been closed, along with any read of its directory
+            if !model.sessions[session_index].is_initialized() {
+                cmds.push(Cmd::CancelDirRead(session_index));
+            }
+        }
+        Msg::GoBackInHistory => match model.current_history().and_then(|history| history.back()) {
+            Some(index) => cmds.extend(get_history_cmd(model, index)),
+            None => model.user_msg = Some(UserMsg::info("no directory to go back to")),
+        },
+        Msg::GoForwardInHistory => {
+            match model
+                .current_history()
+                .and_then(|history| history.forward())
+            {
+                Some(index) => cmds.extend(get_history_cmd(model, index)),
+                None => model.user_msg = Some(UserMsg::info("no directory to go forward to")),
+            }
+        }
+        Msg::GoToSelectedHistoryEntry => {
+            if let Some(index) = model
+                .close_history_menu()
+                .and_then(|menu| menu.selected_history_index())
+            {
+                cmds.extend(get_history_cmd(model, index));
+            }
+        }
         Msg::GoToNextSession => model.go_to_next_session(),
         Msg::GoToPane(pane) => {
             model.last_active_pane = Some(model.active_pane);
@@ -31,25 +119,76 @@ pub fn update(model: &mut Model, msg: Msg) -> Vec<Cmd> {
         }
         Msg::GoToPreviousSession => model.go_to_previous_session(),
         Msg::GoToSession(index) => model.go_to_session(index),
+        Msg::LeaveDiskUsageDir => model.leave_disk_usage_dir(),
         Msg::NavigateIntoDir => {
             if let Some(directory_address) = model.get_directory_under_cursor() {
-                cmds.push(Cmd::ReadDir((directory_address.into(), true)));
+                cmds.extend(get_read_dir_cmd(model, directory_address.into(), true));
+            } else if let Some(path) = get_file_under_cursor(model) {
+                let (command_line, blocking) =
+                    match get_matching_openers(&model.openers, &path).first() {
+                        Some(opener) => (opener.command.clone(), opener.blocking),
+                        // editing is off limits in read-only mode; viewing is the next best thing
+                        None if model.read_only => (services::get_pager_command_line(), true),
+                        None => (services::get_editor_command_line(), true),
+                    };
+                cmds.extend(get_open_cmd(model, &path, &command_line, blocking));
             }
         }
         Msg::NavigateOutOfDir => {
             if let Some(directory_address) = model.get_parent_dir_for_current_session() {
-                cmds.push(Cmd::ReadDir((directory_address.into(), true)));
+                cmds.extend(get_read_dir_cmd(model, directory_address.into(), true));
             } else {
                 model.user_msg = Some(UserMsg::error("no parent found"));
             }
         }
+        Msg::NavigateToDir(path) => {
+            if model.active_pane == Pane::AncestorJump {
+                model.active_pane = Pane::Explorer;
+            }
+
+            let session_info = SessionInfo {
+                index: model.current_session_index,
+                path,
+            };
+            cmds.extend(get_read_dir_cmd(model, session_info, true));
+        }
+        Msg::OpenPrompt(kind) => match kind {
+            PromptKind::ShellCommand { .. } if model.read_only => {
+                model.user_msg = Some(UserMsg::error(READ_ONLY_ERROR));
+            }
+            PromptKind::ShellCommand { .. } | PromptKind::GoToPath => model.open_prompt(kind),
+        },
         Msg::QuitImmediately => model.running_state = RunningState::Done,
+        Msg::QuitIntoDirUnderCursor => match model.get_directory_under_cursor() {
+            Some(directory_address) => {
+                model.quit_dir = Some(directory_address.path);
+                model.running_state = RunningState::Done;
+            }
+            None => {
+                model.user_msg = Some(UserMsg::error("entry under cursor is not a directory"));
+            }
+        },
+        Msg::RunCustomCommand(index) => {
+            if model.read_only {
+                model.user_msg = Some(UserMsg::error(READ_ONLY_ERROR));
+            } else if let Some(custom_command) = model.custom_commands.get(index) {
+                if custom_command.confirm {
+                    let message = format!(r#"run "{}"?"#, custom_command.name);
+                    model.ask_for_confirmation(message, ConfirmAction::RunCustomCommand(index));
+                } else {
+                    cmds.extend(get_custom_command_cmd(model, index));
+                }
+            }
+        }
         Msg::SelectFirst => model.select_first(),
+        Msg::SelectIndex(index) => model.select_index(index),
         Msg::SelectLast => model.select_last(),
         Msg::SelectNext => model.select_next(),
         Msg::MarkPath => model.toggle_path_marked_status(),
         Msg::MoveMarkedItems => {
-            if !model.marked_paths.is_empty()
+            if model.read_only {
+                model.user_msg = Some(UserMsg::error(READ_ONLY_ERROR));
+            } else if !model.marked_paths.is_empty()
                 && let Some(session_dir_addr) = model.get_session_path()
             {
                 let items = model.marked_paths.iter().cloned().collect::<Vec<_>>();
@@ -62,38 +201,230 @@ pub fn update(model: &mut Model, msg: Msg) -> Vec<Cmd> {
             }
         }
         Msg::SelectPrevious => model.select_previous(),
+        Msg::SubmitPrompt => {
+            if let Some(prompt) = model.close_prompt() {
+                match prompt.kind {
+                    PromptKind::ShellCommand { capture_output } => {
+                        cmds.extend(get_shell_cmd(model, &prompt.input, capture_output, true));
+                    }
+                    PromptKind::GoToPath => cmds.extend(get_go_to_path_cmd(model, &prompt.input)),
+                }
+            }
+        }
         Msg::TerminalResize(new_width, new_height) => {
             model.terminal_dimensions.update(new_width, new_height);
             model.terminal_too_small =
                 !(new_width >= MIN_TERMINAL_WIDTH && new_height >= MIN_TERMINAL_HEIGHT);
         }
+        Msg::ViewFile => {
+            if let Some(path) = get_file_under_cursor(model) {
+                cmds.extend(get_open_cmd(
+                    model,
+                    &path,
+                    &services::get_pager_command_line(),
+                    true,
+                ));
+            }
+        }
+        Msg::ShowDiskUsage => {
+            if let Some((scan_id, path)) = model.open_disk_usage() {
+                cmds.push(Cmd::ScanDiskUsage { scan_id, path });
+            }
+        }
+        Msg::StartAncestorJump => model.start_ancestor_jump(),
+        Msg::ShowHistory => model.show_history_menu(),
+        Msg::ShowOpenWithMenu => {
+            if let Some(path) = get_file_under_cursor(model) {
+                let options = get_open_options(model, &path);
+                model.show_open_with_menu(path, options);
+            }
+        }
+        Msg::OpenWithSelectedOption => {
+            if let Some(menu) = model.close_open_with_menu()
+                && let Some(option) = menu.selected_option()
+            {
+                cmds.extend(get_open_cmd(
+                    model,
+                    &menu.path,
+                    &option.command_line,
+                    option.blocking,
+                ));
+            }
+        }
+        Msg::ToggleHiddenEntries => {
+            model.toggle_hidden_entries();
+            // directory previews depend on whether hidden entries are shown
+            model.preview_requested = None;
+            cmds.extend(get_refresh_cmds(model));
+        }
+        Msg::ToggleDiffs => model.toggle_diffs(),
+        Msg::TogglePreview => model.toggle_preview(),
+        Msg::TrashEntries => ask_to_remove_entries(model, false),
         // internal
-        Msg::FSOperationFinished(error) => {
-            if let Err(e) = error {
+        Msg::DirectoriesChanged(dirs) => {
+            // the entry being previewed might've changed as well
+            if let Some(request) = &model.preview_requested
+                && request
+                    .path
+                    .parent()
+                    .is_some_and(|p| dirs.iter().any(|d| d == p))
+            {
+                model.preview_requested = None;
+            }
+
+            for info in model.get_unique_session_paths() {
+                if dirs.contains(&info.path) {
+                    cmds.extend(get_read_dir_cmd(model, info, false));
+                }
+            }
+        }
+        Msg::DirSizeComputed {
+            calculation_id,
+            path,
+            size,
+        } => model.set_dir_size(calculation_id, path, size),
+        Msg::DiskUsageScanProgress {
+            scan_id,
+            num_scanned,
+            size_scanned,
+        } => model.set_disk_usage_progress(scan_id, num_scanned, size_scanned),
+        Msg::DiskUsageScanned { scan_id, result } => match result {
+            Ok(tree) => model.set_disk_usage_tree(scan_id, tree),
+            Err(e) => {
+                if model.fail_disk_usage_scan(scan_id) {
+                    model.user_msg = Some(UserMsg::error(format!("{e:#}")));
+                }
+            }
+        },
+        Msg::FSOperationFinished { result, removed } => {
+            if let Err(e) = result {
                 model.user_msg = Some(UserMsg::error(e.to_string()));
             }
 
+            model.remove_from_disk_usage(&removed);
+
             model.clear_marked_paths();
 
-            model
-                .get_unique_session_paths()
-                .into_iter()
-                .for_each(|info| {
-                    cmds.push(Cmd::ReadDir((info, false)));
-                });
+            cmds.extend(get_refresh_cmds(model));
+        }
+        Msg::CommandOutputCaptured {
+            command_line,
+            result,
+            refresh,
+        } => {
+            match result {
+                Ok(output) => model.show_command_output(&command_line, output),
+                Err(e) => model.user_msg = Some(UserMsg::error(format!("{e:#}"))),
+            }
+
+            if refresh {
+                cmds.extend(get_refresh_cmds(model));
+            }
+        }
+        Msg::ExternalCommandFinished { result, refresh } => {
+            if let Err(e) = result {
+                model.user_msg = Some(UserMsg::error(format!("{e:#}")));
+            }
+
+            if refresh {
+                cmds.extend(get_refresh_cmds(model));
+            }
+        }
+        Msg::GoToPathChecked { path, is_dir } => {
+            cmds.extend(get_checked_go_to_path_cmd(model, path, is_dir));
+        }
+        Msg::PathCompletionsRead { input, result } => {
+            // the input might've changed since the completions were asked for
+            if let Some(prompt) = &mut model.prompt
+                && prompt.input == input
+            {
+                match result {
+                    Ok(names) if names.is_empty() => {
+                        model.user_msg = Some(UserMsg::info("no matches"));
+                    }
+                    Ok(names) => prompt.complete(names),
+                    Err(e) => model.user_msg = Some(UserMsg::error(format!("{e:#}"))),
+                }
+            }
+        }
+        Msg::PreviewLoaded { request, result } => {
+            let lines = match result {
+                Ok(lines) => lines,
+                Err(e) => vec![format!("couldn't load preview: {e:#}")],
+            };
+            model.set_preview(request, lines);
         }
         Msg::DirectoryRead {
             session_info,
+            read_id,
             entries,
-            navigated_to,
+            done,
+        } => {
+            // the status is read once the entries are, so that it covers all of them
+            if model.add_directory_chunk(session_info.clone(), read_id, entries, done)
+                && let Some(request_id) = model.start_git_status_read(session_info.path.clone())
+            {
+                cmds.push(Cmd::ReadGitStatus {
+                    dir: session_info.path,
+                    request_id,
+                });
+            }
+        }
+        Msg::GitActionFinished(result) => {
+            if let Err(e) = result {
+                model.user_msg = Some(UserMsg::error(format!("{e:#}")));
+            }
+
+            model.clear_marked_paths();
+
+            cmds.extend(get_refresh_cmds(model));
+        }
+        Msg::GitStatusRead {
+            dir,
+            request_id,
+            status,
         } => {
-            model.update_entries_for_session(session_info, entries, navigated_to);
+            if let Some(request_id) = model.set_git_status(dir.clone(), request_id, status) {
+                cmds.push(Cmd::ReadGitStatus { dir, request_id });
+            }
         }
-        Msg::ReadingDirFailed(error) => {
-            model.user_msg = Some(UserMsg::error(format!("reading directory failed: {error}")));
+        Msg::ReadingDirFailed {
+            session_index,
+            read_id,
+            error,
+        } => {
+            if model.fail_dir_read(session_index, read_id) {
+                model.user_msg = Some(UserMsg::error(format!("reading directory failed: {error}")));
+            }
         }
     }
 
+    // a half typed key sequence means nothing in a pane other than the one it was typed in
+    if model.active_pane != active_pane {
+        model.pending_keys.clear();
+    }
+
+    if model.end_dir_size_calculation_if_left() {
+        cmds.push(Cmd::CancelDirSizes);
+    }
+
+    if model.needs_auto_dir_sizes() {
+        model.auto_sized_dir = model.get_session_path().map(|address| address.path);
+        cmds.extend(get_dir_sizes_cmd(model));
+    }
+
+    if let Some(dirs) = model.dirs_to_watch() {
+        cmds.push(Cmd::WatchDirs(dirs));
+    }
+
+    if let Some(request) = model.preview_to_request() {
+        cmds.push(Cmd::LoadPreview {
+            request,
+            max_lines: model.terminal_dimensions.height as usize,
+            show_hidden: model.listing.show_hidden,
+        });
+    }
+
     if let Some(message) = &mut model.user_msg {
         let clear = if message.frames_left == 0 {
             true
@@ -109,3 +440,296 @@ pub fn update(model: &mut Model, msg: Msg) -> Vec<Cmd> {
 
     cmds
 }
+
+// re-reads every open session's directory, preserving the entry under cursor
+fn get_refresh_cmds(model: &mut Model) -> Vec<Cmd> {
+    model
+        .get_unique_session_paths()
+        .into_iter()
+        .filter_map(|info| get_read_dir_cmd(model, info, false))
+        .collect()
+}
+
+fn get_dir_sizes_cmd(model: &mut Model) -> Option<Cmd> {
+    let (calculation_id, dirs) = model.start_dir_size_calculation()?;
+
+    Some(Cmd::ComputeDirSizes {
+        calculation_id,
+        dirs,
+    })
+}
+
+pub(super) fn get_read_dir_cmd(
+    model: &mut Model,
+    session_info: SessionInfo,
+    navigated_to: bool,
+) -> Option<Cmd> {
+    let read_id = model.start_dir_read(&session_info, navigated_to)?;
+
+    Some(Cmd::ReadDir {
+        session_info,
+        read_id,
+    })
+}
+
+fn ask_to_remove_entries(model: &mut Model, permanently: bool) {
+    if model.read_only {
+        model.user_msg = Some(UserMsg::error(READ_ONLY_ERROR));
+        return;
+    }
+
+    let items = model.marked_or_selected_entries();
+    let what = match items.as_slice() {
+        [] => return,
+        [entry] => format!(r#""{}""#, entry.path_str()),
+        _ => format!("{} entries", items.len()),
+    };
+
+    if permanently {
+        model.ask_for_confirmation(
+            format!("permanently delete {what}?"),
+            ConfirmAction::Delete(items),
+        );
+    } else {
+        model.ask_for_confirmation(
+            format!("move {what} to trash?"),
+            ConfirmAction::Trash(items),
+        );
+    }
+}
+
+// discarding changes can't be undone, so it's confirmed first
+fn get_git_action_cmd(model: &mut Model, action: GitAction) -> Option<Cmd> {
+    if model.read_only {
+        model.user_msg = Some(UserMsg::error(READ_ONLY_ERROR));
+        return None;
+    }
+
+    let mut entries = model.marked_or_selected_entries();
+    // untracked and ignored entries have no changes git could discard
+    if action == GitAction::Discard {
+        entries.retain(|entry| {
+            !matches!(
+                model.git_status_of(entry.path()),
+                Some(GitFileStatus::Untracked | GitFileStatus::Ignored)
+            )
+        });
+        if entries.is_empty() {
+            model.user_msg = Some(UserMsg::info("nothing to discard"));
+            return None;
+        }
+    }
+
+    let paths = entries
+        .iter()
+        .map(|entry| entry.path().to_path_buf())
+        .collect::<Vec<_>>();
+
+    match (action, entries.as_slice()) {
+        (_, []) => None,
+        (GitAction::Discard, [entry]) => {
+            let message = format!(r#"discard changes to "{}"?"#, entry.path_str());
+            model.ask_for_confirmation(message, ConfirmAction::DiscardGitChanges(paths));
+            None
+        }
+        (GitAction::Discard, _) => {
+            let message = format!("discard changes to {} entries?", entries.len());
+            model.ask_for_confirmation(message, ConfirmAction::DiscardGitChanges(paths));
+            None
+        }
+        (action, _) => Some(Cmd::RunGitAction { action, paths }),
+    }
+}
+
+// going through the history moves within it, rather than adding to it
+fn get_history_cmd(model: &mut Model, history_index: usize) -> Option<Cmd> {
+    let session_info = model.history_entry(history_index)?;
+    let session_index = session_info.index;
+
+    let cmd = get_read_dir_cmd(model, session_info, true)?;
+    model.set_history_move(session_index, history_index);
+
+    Some(cmd)
+}
+
+fn expand_typed_path(model: &Model, input: &str) -> Result<PathBuf, String> {
+    let cwd = model
+        .get_session_path()
+        .map(|address| address.path)
+        .ok_or("no directory to go from")?;
+
+    expand_path(input, &cwd, model.home_dir.as_deref(), |name| {
+        std::env::var(name).ok()
+    })
+}
+
+// tabbing through completions that are listed doesn't need them to be read again
+fn get_prompt_completion_cmd(model: &mut Model, reverse: bool) -> Option<Cmd> {
+    let prompt = model.prompt.as_mut()?;
+    if prompt.kind != PromptKind::GoToPath || prompt.cycle_completions(reverse) {
+        return None;
+    }
+
+    let (dir, prefix) = prompt.path_to_complete();
+    let (dir, prefix, input) = (dir.to_string(), prefix.to_string(), prompt.input.clone());
+    let dir = match expand_typed_path(model, &dir) {
+        Ok(dir) => dir,
+        Err(e) => {
+            model.user_msg = Some(UserMsg::error(e));
+            return None;
+        }
+    };
+
+    Some(Cmd::ReadPathCompletions {
+        input,
+        dir,
+        prefix,
+        show_hidden: model.listing.show_hidden,
+    })
+}
+
+// the path is checked before going to it, see get_checked_go_to_path_cmd
+fn get_go_to_path_cmd(model: &mut Model, input: &str) -> Option<Cmd> {
+    if input.trim().is_empty() {
+        return None;
+    }
+
+    match expand_typed_path(model, input.trim()) {
+        Ok(path) => Some(Cmd::CheckGoToPath(path)),
+        Err(e) => {
+            model.user_msg = Some(UserMsg::error(e));
+            None
+        }
+    }
+}
+
+// a file's directory is gone to instead, with the file selected
+fn get_checked_go_to_path_cmd(
+    model: &mut Model,
+    path: PathBuf,
+    is_dir: std::io::Result<bool>,
+) -> Option<Cmd> {
+    let dir = match is_dir {
+        Ok(true) => path,
+        Ok(false) => {
+            let dir = path.parent()?.to_path_buf();
+            model.select_paths_on_read(vec![path]);
+            dir
+        }
+        Err(e) => {
+            model.user_msg = Some(UserMsg::error(format!("couldn't go to path: {e}")));
+            return None;
+        }
+    };
+
+    let session_info = SessionInfo {
+        index: model.current_session_index,
+        path: dir,
+    };
+    get_read_dir_cmd(model, session_info, true)
+}
+
+fn get_file_under_cursor(model: &Model) -> Option<PathBuf> {
+    let entry = model.get_entry_under_cursor()?;
+
+    entry
+        .kind()
+        .is_openable()
+        .then(|| entry.path().to_path_buf())
+}
+
+// matching openers come first, followed by the editor and the pager
+fn get_open_options(model: &Model, path: &Path) -> Vec<OpenOption> {
+    let mut options = get_matching_openers(&model.openers, path)
+        .into_iter()
+        .map(|opener| OpenOption {
+            label: opener.name.clone(),
+            command_line: opener.command.clone(),
+            blocking: opener.blocking,
+        })
+        .collect::<Vec<_>>();
+
+    if !model.read_only {
+        options.push(OpenOption {
+            label: "editor".to_string(),
+            command_line: services::get_editor_command_line(),
+            blocking: true,
+        });
+    }
+
+    options.push(OpenOption {
+        label: "pager".to_string(),
+        command_line: services::get_pager_command_line(),
+        blocking: true,
+    });
+
+    options
+}
+
+fn get_open_cmd(model: &mut Model, path: &Path, command_line: &str, blocking: bool) -> Option<Cmd> {
+    match ExternalCommand::for_path(command_line, path) {
+        Some(command) if blocking => Some(Cmd::RunInTerminal {
+            command,
+            pause_after: false,
+            refresh: true,
+        }),
+        Some(command) => Some(Cmd::SpawnDetached(command)),
+        None if command_line.trim().is_empty() => {
+            model.user_msg = Some(UserMsg::error("no command configured to open files with"));
+            None
+        }
+        None => {
+            model.user_msg = Some(UserMsg::error(format!(
+                "couldn't parse command \"{command_line}\""
+            )));
+            None
+        }
+    }
+}
+
+fn get_custom_command_cmd(model: &mut Model, index: usize) -> Option<Cmd> {
+    let custom_command = model.custom_commands.get(index)?.clone();
+
+    get_shell_cmd(
+        model,
+        &custom_command.command,
+        custom_command.capture_output,
+        custom_command.refresh,
+    )
+}
+
+fn get_shell_cmd(
+    model: &mut Model,
+    template: &str,
+    capture_output: bool,
+    refresh: bool,
+) -> Option<Cmd> {
+    let template = template.trim();
+    if template.is_empty() {
+        return None;
+    }
+
+    let values = model.placeholder_values();
+    let command_line = match expand_placeholders(template, &values) {
+        Ok(c) => c,
+        Err(e) => {
+            model.user_msg = Some(UserMsg::error(e));
+            return None;
+        }
+    };
+
+    let command = ExternalCommand::shell(&command_line, values.current_dir);
+    if capture_output {
+        Some(Cmd::RunCapturingOutput {
+            command_line,
+            command,
+            refresh,
+        })
+    } else {
+        Some(Cmd::RunInTerminal {
+            command,
+            pause_after: true,
+            refresh,
+        })
+    }
+}
@@ -1,37 +1,75 @@
 use super::common::*;
-use super::model::{EntryItem, MessageKind, Model, Session};
-use ratatui::style::Color;
+use super::ls_colors::LsColors;
+use super::model::{
+    ClickAreas, DirSizeCache, EntryItem, HeaderTarget, MessageKind, Model, Session,
+};
+use super::theme::Theme;
+use crate::domain::{
+    Breadcrumb, Entry, EntryKind, GitFileStatus, GitStatus, display_path, escape_os_str,
+    format_size, get_breadcrumbs,
+};
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Layout, Rect},
-    style::Style,
+    style::{Color, Style},
     text::{Line, Span},
-    widgets::{Block, List, ListDirection, ListItem, Padding, Paragraph, Wrap},
+    widgets::{
+        Block, Borders, Clear, List, ListDirection, ListItem, ListState, Padding, Paragraph, Wrap,
+    },
 };
+use std::ops::Range;
+use std::path::{MAIN_SEPARATOR, MAIN_SEPARATOR_STR, PathBuf};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-use crate::domain::EntryKind;
-
-const PANE_TITLE_FG_COLOR: Color = Color::Black;
-const PRIMARY_COLOR: Color = Color::LightBlue;
-const INFO_MESSAGE_COLOR: Color = Color::LightBlue;
-const ERROR_MESSAGE_COLOR: Color = Color::LightRed;
-const HELP_COLOR: Color = Color::Yellow;
+const MENU_WIDTH: u16 = 50;
+// the preview is only shown if the explorer can still be this wide (or tall) alongside it
+const MIN_EXPLORER_WIDTH_WITH_PREVIEW: u16 = 40;
+const MIN_EXPLORER_HEIGHT_WITH_PREVIEW: u16 = 10;
 
 const TITLE: &str = " atls ";
+// wide enough for any size formatted like "1023K"
+const SIZE_WIDTH: usize = 5;
+const USAGE_BAR_WIDTH: usize = 10;
+// borders included
+const MAX_COMPLETIONS_HEIGHT: u16 = 12;
 
 pub fn view(model: &mut Model, frame: &mut Frame) {
+    // panes record what can be clicked, and which ancestors can be jumped to, as they're rendered
+    model.click_areas = ClickAreas::default();
+    model.ancestor_jump_keys.clear();
+
     if model.terminal_too_small {
-        render_terminal_too_small_view(&model.terminal_dimensions, frame);
+        render_terminal_too_small_view(&model.terminal_dimensions, &model.theme, frame);
         return;
     }
 
     match model.active_pane {
         Pane::Explorer => render_explorer_view(model, frame),
         Pane::Help => render_help_pane(model, frame),
+        Pane::OpenWith => {
+            render_explorer_view(model, frame);
+            render_open_with_menu(model, frame);
+        }
+        Pane::History => {
+            render_explorer_view(model, frame);
+            render_history_menu(model, frame);
+        }
+        Pane::Confirmation if model.last_active_pane == Some(Pane::DiskUsage) => {
+            render_disk_usage_view(model, frame);
+        }
+        Pane::Prompt | Pane::Confirmation | Pane::AncestorJump => {
+            render_explorer_view(model, frame)
+        }
+        Pane::CommandOutput => render_command_output_pane(model, frame),
+        Pane::DiskUsage => render_disk_usage_view(model, frame),
     }
 }
 
-fn render_terminal_too_small_view(dimensions: &TerminalDimensions, frame: &mut Frame) {
+fn render_terminal_too_small_view(
+    dimensions: &TerminalDimensions,
+    theme: &Theme,
+    frame: &mut Frame,
+) {
     let message = format!(
         r#"
 Terminal size too small:
@@ -47,7 +85,7 @@ Press (q/<ctrl+c>/<esc> to exit)
 
     let p = Paragraph::new(message)
         .block(Block::bordered())
-        .style(Style::new().fg(PRIMARY_COLOR))
+        .style(Style::new().fg(theme.primary))
         .wrap(Wrap { trim: false })
         .alignment(Alignment::Center);
 
@@ -60,8 +98,190 @@ fn render_explorer_view(model: &mut Model, frame: &mut Frame) {
         .constraints(vec![Constraint::Min(10), Constraint::Length(1)])
         .split(frame.area());
 
-    render_explorer_pane(model, frame, main_rect[0]);
-    render_status_line(model, frame, main_rect[1]);
+    match get_preview_rect(model, main_rect[0]) {
+        Some((explorer_rect, preview_rect)) => {
+            render_explorer_pane(model, frame, explorer_rect);
+            render_preview_pane(model, frame, preview_rect);
+        }
+        None => render_explorer_pane(model, frame, main_rect[0]),
+    }
+
+    if model.active_pane == Pane::Prompt {
+        render_prompt(model, frame, main_rect[1]);
+        render_completions(model, frame, main_rect[1]);
+    } else if model.active_pane == Pane::Confirmation {
+        render_confirmation(model, frame, main_rect[1]);
+    } else {
+        render_status_line(model, frame, main_rect[1]);
+    }
+}
+
+// splits the explorer's area between it and the preview, if the preview is to be shown
+fn get_preview_rect(model: &Model, rect: Rect) -> Option<(Rect, Rect)> {
+    if !model.show_preview {
+        return None;
+    }
+
+    let (direction, fits) = match model.layout {
+        PaneLayout::Horizontal => (
+            ratatui::layout::Direction::Horizontal,
+            rect.width / 2 >= MIN_EXPLORER_WIDTH_WITH_PREVIEW,
+        ),
+        PaneLayout::Vertical => (
+            ratatui::layout::Direction::Vertical,
+            rect.height / 2 >= MIN_EXPLORER_HEIGHT_WITH_PREVIEW,
+        ),
+    };
+
+    if !fits {
+        return None;
+    }
+
+    let rects = Layout::default()
+        .direction(direction)
+        .constraints(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(rect);
+
+    Some((rects[0], rects[1]))
+}
+
+fn render_preview_pane(model: &Model, frame: &mut Frame, rect: Rect) {
+    let Some(preview) = &model.preview else {
+        return;
+    };
+
+    let title = preview
+        .path
+        .file_name()
+        .map(|n| match preview.diff {
+            true => format!(" {} (diff) ", escape_os_str(n)),
+            false => format!(" {} ", escape_os_str(n)),
+        })
+        .unwrap_or_default();
+
+    let lines: Vec<Line> = preview
+        .lines
+        .iter()
+        .map(|l| match preview.diff {
+            true => Line::styled(l.as_str(), get_diff_line_style(model, l)),
+            false => Line::raw(l.as_str()),
+        })
+        .collect();
+
+    let (borders, padding) = match model.layout {
+        PaneLayout::Horizontal => (Borders::LEFT, Padding::new(1, 0, 0, 0)),
+        PaneLayout::Vertical => (Borders::TOP, Padding::ZERO),
+    };
+
+    let preview_widget = Paragraph::new(lines).block(
+        Block::new()
+            .borders(borders)
+            .border_style(Style::new().fg(model.theme.muted))
+            .title_style(Style::new().fg(model.theme.primary))
+            .title(title)
+            .padding(padding),
+    );
+
+    frame.render_widget(preview_widget, rect);
+}
+
+fn get_diff_line_style(model: &Model, line: &str) -> Style {
+    if line.starts_with("+++") || line.starts_with("---") {
+        Style::new().fg(model.theme.muted)
+    } else if line.starts_with('+') {
+        Style::new().fg(model.theme.menu)
+    } else if line.starts_with('-') {
+        Style::new().fg(model.theme.error)
+    } else if line.starts_with("@@") {
+        Style::new().fg(model.theme.info)
+    } else {
+        Style::new()
+    }
+}
+
+fn render_prompt(model: &Model, frame: &mut Frame, rect: Rect) {
+    let Some(prompt) = &model.prompt else {
+        return;
+    };
+
+    let prefix = Span::styled(
+        prompt.kind.prefix(),
+        Style::new().bold().fg(model.theme.primary),
+    );
+    let before_cursor = prompt.input.chars().take(prompt.cursor).collect::<String>();
+    let cursor_x = rect.x + (prefix.width() + Span::from(before_cursor).width()) as u16;
+
+    let line = Line::from(vec![prefix, Span::from(prompt.input.as_str())]);
+    frame.render_widget(Paragraph::new(line), rect);
+    frame.set_cursor_position((cursor_x.min(rect.right().saturating_sub(1)), rect.y));
+}
+
+// lists the prompt's completions right above it
+fn render_completions(model: &Model, frame: &mut Frame, prompt_rect: Rect) {
+    let Some(prompt) = &model.prompt else {
+        return;
+    };
+    let Some(completions) = &prompt.completions else {
+        return;
+    };
+
+    // candidates are listed by the names they end with
+    let names = completions
+        .candidates
+        .iter()
+        .map(|candidate| {
+            let trimmed = candidate.trim_end_matches(['/', MAIN_SEPARATOR]);
+            let start = trimmed
+                .rfind(['/', MAIN_SEPARATOR])
+                .map(|i| i + 1)
+                .unwrap_or(0);
+            &candidate[start..]
+        })
+        .collect::<Vec<_>>();
+
+    let x = prompt_rect.x + Span::from(prompt.kind.prefix()).width() as u16;
+    let max_name_width = names.iter().map(|n| n.width()).max().unwrap_or_default() as u16;
+    let width = (max_name_width + 4).min(prompt_rect.right().saturating_sub(x));
+    let height = (names.len() as u16 + 2)
+        .min(MAX_COMPLETIONS_HEIGHT)
+        .min(prompt_rect.y);
+    let rect = Rect::new(x, prompt_rect.y - height, width, height);
+
+    let list = List::new(names)
+        .block(
+            Block::bordered()
+                .border_style(Style::new().fg(model.theme.muted))
+                .padding(Padding::new(1, 1, 0, 0)),
+        )
+        .style(Style::new().fg(model.theme.text))
+        .highlight_style(
+            Style::new()
+                .bg(model.theme.selection)
+                .fg(model.theme.selection_fg)
+                .bold(),
+        );
+    let mut state = ListState::default().with_selected(completions.selected);
+
+    frame.render_widget(Clear, rect);
+    frame.render_stateful_widget(list, rect, &mut state);
+}
+
+fn render_confirmation(model: &Model, frame: &mut Frame, rect: Rect) {
+    let Some(confirmation) = &model.confirmation else {
+        return;
+    };
+
+    let line = Line::from(vec![
+        Span::styled(
+            format!(" {} ", confirmation.message),
+            Style::new()
+                .bold()
+                .bg(model.theme.help)
+                .fg(model.theme.selection_fg),
+        ),
+        Span::styled(" (y/n)", Style::new().fg(model.theme.help)),
+    ]);
+    frame.render_widget(Paragraph::new(line), rect);
 }
 
 fn render_explorer_pane(model: &mut Model, frame: &mut Frame, rect: Rect) {
@@ -71,6 +291,8 @@ fn render_explorer_pane(model: &mut Model, frame: &mut Frame, rect: Rect) {
         .split(rect);
 
     let mut header_spans = vec![];
+    // clickable spans, by index
+    let mut header_targets = vec![];
     for (i, session) in model.sessions.iter().enumerate() {
         let mut span_style = Style::new();
         if session.is_initialized() && i != model.current_session_index {
@@ -78,11 +300,15 @@ fn render_explorer_pane(model: &mut Model, frame: &mut Frame, rect: Rect) {
         }
 
         if i == model.current_session_index {
-            span_style = span_style.bold().fg(Color::Black).bg(Color::Blue);
+            span_style = span_style
+                .bold()
+                .fg(model.theme.selection_fg)
+                .bg(model.theme.selection);
         }
 
         let span = Span::styled(format!("{}", i + 1), span_style);
 
+        header_targets.push((header_spans.len(), HeaderTarget::Session(i)));
         header_spans.push(span);
 
         if i < 3 {
@@ -92,39 +318,238 @@ fn render_explorer_pane(model: &mut Model, frame: &mut Frame, rect: Rect) {
 
     header_spans.push(Span::from(" "));
 
+    let theme = &model.theme;
+    let ls_colors = model.ls_colors.as_ref();
+    let dir_sizes = model.show_sizes.then_some(&model.dir_sizes);
+    let home_dir = model.home_dir.as_deref();
+    let jumping = model.active_pane == Pane::AncestorJump;
+    let read_progress = model.current_dir_read_progress();
+    let git_status = model
+        .get_session_path()
+        .and_then(|address| model.git_statuses.get(&address.path));
+
     // TODO: can be made better
-    // gets a mutable reference to the entire current session
-    match model.current_session_mut() {
+    // gets a mutable reference to the entire current session; borrowing the field directly lets
+    // the theme be borrowed alongside it
+    let list_block = Block::new().padding(Padding::new(0, 0, 1, 0));
+    let mut list_rect = None;
+    match &mut model.sessions[model.current_session_index] {
         Session::Uninitialized => {
-            let header = Line::from(header_spans);
+            let header = Line::from(header_spans.clone());
             frame.render_widget(header, rect[0]);
         }
         Session::Initialized {
             path,
             entries,
             state,
+            ..
         } => {
-            header_spans.push(Span::styled(
-                path.to_string_lossy(),
-                Style::new().fg(Color::Blue),
-            ));
+            let mut trailing_spans = vec![];
+            if let Some(git_status) = git_status {
+                trailing_spans.push(Span::styled(
+                    format!("  {}", get_branch_summary(git_status)),
+                    Style::new().fg(theme.info),
+                ));
+            }
+            if let Some(num_entries) = read_progress {
+                trailing_spans.push(Span::styled(
+                    format!("  loading {num_entries} entries…"),
+                    Style::new().fg(theme.muted),
+                ));
+            }
 
-            let header = Line::from(header_spans);
+            // the path takes up whatever room is left
+            let max_path_width = (rect[0].width as usize)
+                .saturating_sub(Line::from(header_spans.clone()).width())
+                .saturating_sub(Line::from(trailing_spans.clone()).width());
+            let breadcrumbs = get_breadcrumbs(path, home_dir);
+            let (breadcrumb_spans, jump_keys) =
+                get_breadcrumb_spans(breadcrumbs, jumping, theme, max_path_width);
+            for (span, target) in breadcrumb_spans {
+                if let Some(target) = target {
+                    header_targets.push((header_spans.len(), target));
+                }
+                header_spans.push(span);
+            }
+            model.ancestor_jump_keys = jump_keys;
+            header_spans.extend(trailing_spans);
+
+            let header = Line::from(header_spans.clone());
 
             let selected_index = state.selected();
+            // columns are taken up by the marker, the git status if there's one, and by the size
+            // along with a space before it, if sizes are shown
+            let git_status_width = if git_status.is_some() { 2 } else { 0 };
+            let size_width = if dir_sizes.is_some() {
+                1 + SIZE_WIDTH
+            } else {
+                0
+            };
+            let max_name_width =
+                (rect[1].width as usize).saturating_sub(1 + git_status_width + size_width);
             let items: Vec<ListItem> = entries
                 .iter()
                 .enumerate()
-                .map(|(i, entry)| entry_to_list_item(entry, selected_index == Some(i)))
+                .map(|(i, entry)| {
+                    entry_to_list_item(
+                        entry,
+                        selected_index == Some(i),
+                        theme,
+                        ls_colors,
+                        dir_sizes,
+                        git_status,
+                        max_name_width,
+                    )
+                })
                 .collect();
 
             let list = List::new(items)
-                .block(Block::new().padding(Padding::new(0, 0, 1, 0)))
+                .block(list_block.clone())
                 .direction(ListDirection::TopToBottom);
             frame.render_widget(header, rect[0]);
             frame.render_stateful_widget(list, rect[1], state);
+            list_rect = Some(list_block.inner(rect[1]));
         }
     }
+
+    model.click_areas.list = list_rect;
+    model.click_areas.header = get_click_areas(&header_spans, header_targets, rect[0]);
+}
+
+// a span of the header, along with what clicking it goes to, if anything
+type HeaderSpan = (Span<'static>, Option<HeaderTarget>);
+
+// lays out the breadcrumbs of a path, each of which can be clicked to go to its directory; when
+// jumping to ancestors, each ancestor is shown along with its key, which is returned as well
+//
+// the ones in the middle are replaced with an ellipsis if they don't all fit; only the ones shown
+// get keys
+fn get_breadcrumb_spans(
+    breadcrumbs: Vec<Breadcrumb>,
+    jumping: bool,
+    theme: &Theme,
+    max_width: usize,
+) -> (Vec<HeaderSpan>, Vec<(char, PathBuf)>) {
+    let num_ancestors = breadcrumbs.len().saturating_sub(1);
+    let path_style = Style::new().fg(theme.selection);
+
+    // whether each breadcrumb is preceded by a separator; the root already ends with one
+    let separated = (0..breadcrumbs.len())
+        .map(|i| i > 0 && !breadcrumbs[i - 1].name.ends_with(MAIN_SEPARATOR))
+        .collect::<Vec<_>>();
+    // the width of each breadcrumb, along with the separator before it, and the key next to it if
+    // it's an ancestor that's being jumped to
+    let widths = breadcrumbs
+        .iter()
+        .enumerate()
+        .map(|(i, breadcrumb)| {
+            let separator_width = if separated[i] {
+                MAIN_SEPARATOR_STR.width()
+            } else {
+                0
+            };
+            let key_width = usize::from(jumping && i < num_ancestors);
+            separator_width + key_width + breadcrumb.name.width()
+        })
+        .collect::<Vec<_>>();
+    let hidden = get_hidden_breadcrumbs(&widths, max_width);
+
+    // keys go to the ancestors that are shown, nearest first
+    let mut keys = vec![None; breadcrumbs.len()];
+    if jumping {
+        let shown = (0..num_ancestors).rev().filter(|i| !hidden.contains(i));
+        for (i, key) in shown.zip(ANCESTOR_JUMP_KEYS.chars()) {
+            keys[i] = Some(key);
+        }
+    }
+
+    // the spans of each breadcrumb, starting with the separator before it
+    let mut groups = vec![];
+    let mut jump_keys = vec![];
+    for (i, (breadcrumb, key)) in breadcrumbs.into_iter().zip(keys).enumerate() {
+        let mut group = vec![];
+        if separated[i] {
+            group.push((Span::styled(MAIN_SEPARATOR_STR, path_style), None));
+        }
+
+        if let Some(key) = key {
+            group.push((
+                Span::styled(
+                    key.to_string(),
+                    Style::new().bold().bg(theme.info).fg(theme.selection_fg),
+                ),
+                None,
+            ));
+            jump_keys.push((key, breadcrumb.dir.clone()));
+        }
+
+        let target = HeaderTarget::Dir(breadcrumb.dir);
+        group.push((Span::styled(breadcrumb.name, path_style), Some(target)));
+        groups.push(group);
+    }
+
+    let mut spans = vec![];
+    for (i, group) in groups.into_iter().enumerate() {
+        if !hidden.is_empty() && hidden.start == i {
+            let after_separator = spans
+                .last()
+                .is_some_and(|(span, _): &(Span, _)| span.content.ends_with(MAIN_SEPARATOR));
+            if !after_separator {
+                spans.push((Span::styled(MAIN_SEPARATOR_STR, path_style), None));
+            }
+            spans.push((Span::styled("…", Style::new().fg(theme.muted)), None));
+        }
+        if !hidden.contains(&i) {
+            spans.extend(group);
+        }
+    }
+
+    (spans, jump_keys)
+}
+
+// returns the range of breadcrumbs, given their widths, to leave out so that the rest fit in
+// `max_width`; the first and the last ones are always shown
+fn get_hidden_breadcrumbs(widths: &[usize], max_width: usize) -> Range<usize> {
+    let total_width: usize = widths.iter().sum();
+    if total_width <= max_width || widths.len() <= 2 {
+        return 0..0;
+    }
+
+    // a separator and an ellipsis stand in for the hidden ones
+    let ellipsis_width = 2;
+    let mut width = widths[0] + ellipsis_width;
+    let mut first_shown = widths.len() - 1;
+    width += widths[first_shown];
+    while first_shown > 1 && width + widths[first_shown - 1] <= max_width {
+        first_shown -= 1;
+        width += widths[first_shown];
+    }
+
+    1..first_shown
+}
+
+// finds where the spans at the targets' indices end up, when laid out in a line at the rect
+fn get_click_areas(
+    spans: &[Span],
+    targets: Vec<(usize, HeaderTarget)>,
+    rect: Rect,
+) -> Vec<(Rect, HeaderTarget)> {
+    let mut offsets = Vec::with_capacity(spans.len());
+    let mut x = rect.x;
+    for span in spans {
+        let width = span.width() as u16;
+        offsets.push((x, width));
+        x = x.saturating_add(width);
+    }
+
+    targets
+        .into_iter()
+        .filter_map(|(index, target)| {
+            let (x, width) = *offsets.get(index)?;
+            let area = Rect::new(x, rect.y, width, 1).intersection(rect);
+            (!area.is_empty()).then_some((area, target))
+        })
+        .collect()
 }
 
 fn render_status_line(model: &Model, frame: &mut Frame, rect: Rect) {
@@ -132,19 +557,40 @@ fn render_status_line(model: &Model, frame: &mut Frame, rect: Rect) {
         TITLE,
         Style::new()
             .bold()
-            .bg(PRIMARY_COLOR)
-            .fg(PANE_TITLE_FG_COLOR),
+            .bg(model.theme.primary)
+            .fg(model.theme.selection_fg),
     )];
 
+    if let Some(mode) = model.chooser {
+        let hint = match mode {
+            ChooserMode::Files => " [choosing files; <enter> to confirm]",
+            ChooserMode::Directory => " [choosing a directory; <enter> to confirm]",
+        };
+        status_bar_lines.push(Span::styled(hint, Style::new().fg(model.theme.info)));
+    }
+
+    if model.active_pane == Pane::AncestorJump {
+        status_bar_lines.push(Span::styled(
+            " [press a directory's key to jump to it]",
+            Style::new().fg(model.theme.info),
+        ));
+    }
+
+    if model.read_only {
+        status_bar_lines.push(Span::styled(
+            " [read-only]",
+            Style::new().fg(model.theme.info),
+        ));
+    }
+
     if let Some(msg) = &model.user_msg {
         let span = match msg.kind {
-            MessageKind::Info => Span::styled(
-                format!(" {}", msg.value),
-                Style::new().fg(INFO_MESSAGE_COLOR),
-            ),
+            MessageKind::Info => {
+                Span::styled(format!(" {}", msg.value), Style::new().fg(model.theme.info))
+            }
             MessageKind::Error => Span::styled(
                 format!(" {}", msg.value),
-                Style::new().fg(ERROR_MESSAGE_COLOR),
+                Style::new().fg(model.theme.error),
             ),
         };
 
@@ -162,6 +608,7 @@ fn render_status_line(model: &Model, frame: &mut Frame, rect: Rect) {
                 path: _,
                 entries: _,
                 state,
+                ..
             } => {
                 status_bar_lines.push(Span::from(format!(" [selected: {:?}]", state.selected())));
             }
@@ -187,8 +634,8 @@ fn render_help_pane(model: &Model, frame: &mut Frame) {
         .constraints(vec![Constraint::Min(10), Constraint::Length(1)])
         .split(frame.area());
 
-    let help_content = get_help_content();
-    let lines: Vec<Line> = help_content
+    let lines: Vec<Line> = model
+        .help_content
         .lines()
         .skip(model.help_scroll)
         .map(Line::raw)
@@ -199,11 +646,16 @@ fn render_help_pane(model: &Model, frame: &mut Frame) {
     let help_widget = Paragraph::new(lines)
         .block(
             Block::new()
-                .title_style(Style::new().bold().bg(HELP_COLOR).fg(PANE_TITLE_FG_COLOR))
+                .title_style(
+                    Style::new()
+                        .bold()
+                        .bg(model.theme.help)
+                        .fg(model.theme.selection_fg),
+                )
                 .title(title)
                 .padding(Padding::new(1, 0, 1, 0)),
         )
-        .style(Style::new().white().on_black())
+        .style(Style::new().fg(model.theme.text))
         .wrap(Wrap { trim: false })
         .alignment(Alignment::Left);
 
@@ -211,33 +663,495 @@ fn render_help_pane(model: &Model, frame: &mut Frame) {
     render_status_line(model, frame, main_rect[1]);
 }
 
-fn entry_to_list_item(item: &EntryItem, is_selected: bool) -> ListItem<'_> {
-    let color = match item.entry.kind() {
-        EntryKind::File => Color::White,
-        EntryKind::Directory => Color::LightRed,
-        EntryKind::Symlink => Color::Magenta,
-        EntryKind::Unknown => Color::Gray,
+fn render_command_output_pane(model: &Model, frame: &mut Frame) {
+    let Some(output) = &model.command_output else {
+        return;
+    };
+
+    let main_rect = Layout::default()
+        .direction(ratatui::layout::Direction::Vertical)
+        .constraints(vec![Constraint::Min(10), Constraint::Length(1)])
+        .split(frame.area());
+
+    let lines: Vec<Line> = output
+        .lines
+        .iter()
+        .skip(output.scroll)
+        .map(|l| Line::raw(l.as_str()))
+        .collect();
+
+    let output_widget = Paragraph::new(lines)
+        .block(
+            Block::new()
+                .title_style(
+                    Style::new()
+                        .bold()
+                        .bg(model.theme.menu)
+                        .fg(model.theme.selection_fg),
+                )
+                .title(output.title.as_str())
+                .padding(Padding::new(1, 0, 1, 0)),
+        )
+        .alignment(Alignment::Left);
+
+    frame.render_widget(&output_widget, main_rect[0]);
+    render_status_line(model, frame, main_rect[1]);
+}
+
+fn render_disk_usage_view(model: &mut Model, frame: &mut Frame) {
+    let main_rect = Layout::default()
+        .direction(ratatui::layout::Direction::Vertical)
+        .constraints(vec![Constraint::Min(10), Constraint::Length(1)])
+        .split(frame.area());
+    let rect = Layout::default()
+        .direction(ratatui::layout::Direction::Vertical)
+        .constraints(vec![Constraint::Length(1), Constraint::Min(9)])
+        .split(main_rect[0]);
+
+    let theme = &model.theme;
+    let ls_colors = model.ls_colors.as_ref();
+    let marked_paths = &model.marked_paths;
+    let Some(view) = &mut model.disk_usage else {
+        return;
+    };
+
+    let mut header_spans = vec![
+        Span::styled(
+            " disk usage ",
+            Style::new().bold().bg(theme.menu).fg(theme.selection_fg),
+        ),
+        Span::from(" "),
+        Span::styled(display_path(&view.dir), Style::new().fg(theme.selection)),
+    ];
+
+    let Some(node) = view.tree.as_ref().and_then(|tree| tree.find(&view.dir)) else {
+        header_spans.push(Span::styled(
+            format!(
+                "  scanning… {} entries, {}",
+                view.num_scanned,
+                format_size(view.size_scanned)
+            ),
+            Style::new().fg(theme.muted),
+        ));
+        frame.render_widget(Line::from(header_spans), rect[0]);
+        render_status_line(model, frame, main_rect[1]);
+        return;
+    };
+
+    header_spans.push(Span::styled(
+        format!("  {} in total", format_size(node.size)),
+        Style::new().fg(theme.muted),
+    ));
+
+    let selected_index = view.state.selected();
+    // columns are taken up by the marker, the size, the percentage, and the bar
+    let max_name_width =
+        (rect[1].width as usize).saturating_sub(1 + SIZE_WIDTH + 8 + USAGE_BAR_WIDTH + 3);
+    let items: Vec<ListItem> = node
+        .children
+        .iter()
+        .enumerate()
+        .map(|(i, child)| {
+            let fraction = if node.size == 0 {
+                0.0
+            } else {
+                child.size as f64 / node.size as f64
+            };
+            let filled = (fraction * USAGE_BAR_WIDTH as f64).round() as usize;
+
+            let name_style = if selected_index == Some(i) {
+                Style::new()
+                    .bg(theme.selection)
+                    .fg(theme.selection_fg)
+                    .bold()
+            } else {
+                ls_colors
+                    .and_then(|l| l.style_for(&child.entry))
+                    .unwrap_or_else(|| Style::new().fg(theme.entry_color(child.entry.kind())))
+            };
+            let marker = if marked_paths.contains(&child.entry) {
+                Span::styled("+", Style::new().bg(theme.marked).fg(theme.selection_fg))
+            } else {
+                " ".into()
+            };
+
+            ListItem::new(Line::from(vec![
+                marker,
+                Span::styled(
+                    format!("{:>SIZE_WIDTH$}", format_size(child.size)),
+                    Style::new().fg(theme.text),
+                ),
+                Span::styled(
+                    format!(" {:>5.1}% ", fraction * 100.0),
+                    Style::new().fg(theme.muted),
+                ),
+                Span::styled(
+                    format!(
+                        "[{}{}]",
+                        "#".repeat(filled),
+                        " ".repeat(USAGE_BAR_WIDTH - filled)
+                    ),
+                    Style::new().fg(theme.primary),
+                ),
+                Span::from(" "),
+                Span::styled(
+                    truncate_to_width(child.entry.path_str(), max_name_width),
+                    name_style,
+                ),
+            ]))
+        })
+        .collect();
+
+    let list_block = Block::new().padding(Padding::new(0, 0, 1, 0));
+    let list_rect = list_block.inner(rect[1]);
+    let list = List::new(items)
+        .block(list_block)
+        .direction(ListDirection::TopToBottom);
+    frame.render_widget(Line::from(header_spans), rect[0]);
+    frame.render_stateful_widget(list, rect[1], &mut view.state);
+    model.click_areas.list = Some(list_rect);
+
+    if model.active_pane == Pane::Confirmation {
+        render_confirmation(model, frame, main_rect[1]);
+    } else {
+        render_status_line(model, frame, main_rect[1]);
+    }
+}
+
+fn render_open_with_menu(model: &mut Model, frame: &mut Frame) {
+    let Some(menu) = &mut model.open_with_menu else {
+        return;
     };
 
-    let base_style = Style::new().fg(color);
+    let items: Vec<ListItem> = menu
+        .options
+        .iter()
+        .map(|option| {
+            ListItem::new(Line::from(vec![
+                Span::from(option.label.as_str()),
+                Span::styled(
+                    format!("  {}", option.command_line),
+                    Style::new().fg(model.theme.muted),
+                ),
+            ]))
+        })
+        .collect();
+
+    let area = frame.area();
+    let width = MENU_WIDTH.min(area.width);
+    let height = (items.len() as u16 + 3).min(area.height);
+    let rect = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+
+    let file_name = menu.path.file_name().map(escape_os_str).unwrap_or_default();
+
+    let list = List::new(items)
+        .block(
+            Block::bordered()
+                .border_style(Style::new().fg(model.theme.menu))
+                .title_style(
+                    Style::new()
+                        .bold()
+                        .bg(model.theme.menu)
+                        .fg(model.theme.selection_fg),
+                )
+                .title(format!(" open {file_name} with "))
+                .padding(Padding::new(1, 1, 0, 0)),
+        )
+        .highlight_style(
+            Style::new()
+                .bg(model.theme.menu)
+                .fg(model.theme.selection_fg)
+                .bold(),
+        )
+        .direction(ListDirection::TopToBottom);
+
+    frame.render_widget(Clear, rect);
+    frame.render_stateful_widget(list, rect, &mut menu.state);
+}
+
+fn render_history_menu(model: &mut Model, frame: &mut Frame) {
+    let Some(menu) = &mut model.history_menu else {
+        return;
+    };
+
+    let items: Vec<ListItem> = menu
+        .dirs
+        .iter()
+        .enumerate()
+        .map(|(i, dir)| {
+            let path = match model.home_dir.as_deref().map(|home| dir.strip_prefix(home)) {
+                Some(Ok(rest)) if rest.as_os_str().is_empty() => "~".to_string(),
+                Some(Ok(rest)) => format!("~{MAIN_SEPARATOR}{}", display_path(rest)),
+                _ => display_path(dir),
+            };
+            let marker = if i == menu.current { "• " } else { "  " };
+
+            ListItem::new(Line::from(vec![
+                Span::styled(marker, Style::new().fg(model.theme.info)),
+                Span::from(path),
+            ]))
+        })
+        .collect();
+
+    let area = frame.area();
+    let width = MENU_WIDTH.min(area.width);
+    let height = (items.len() as u16 + 3).min(area.height);
+    let rect = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+
+    let list = List::new(items)
+        .block(
+            Block::bordered()
+                .border_style(Style::new().fg(model.theme.menu))
+                .title_style(
+                    Style::new()
+                        .bold()
+                        .bg(model.theme.menu)
+                        .fg(model.theme.selection_fg),
+                )
+                .title(" history ")
+                .padding(Padding::new(1, 1, 0, 0)),
+        )
+        .highlight_style(
+            Style::new()
+                .bg(model.theme.menu)
+                .fg(model.theme.selection_fg)
+                .bold(),
+        )
+        .direction(ListDirection::TopToBottom);
+
+    frame.render_widget(Clear, rect);
+    frame.render_stateful_widget(list, rect, &mut menu.state);
+}
+
+fn entry_to_list_item<'a>(
+    item: &'a EntryItem,
+    is_selected: bool,
+    theme: &Theme,
+    ls_colors: Option<&LsColors>,
+    dir_sizes: Option<&DirSizeCache>,
+    git_status: Option<&GitStatus>,
+    max_name_width: usize,
+) -> ListItem<'a> {
+    let base_style = ls_colors
+        .and_then(|l| l.style_for(&item.entry))
+        .unwrap_or_else(|| Style::new().fg(theme.entry_color(item.entry.kind())));
+
     let highlight_style = if is_selected {
-        Style::new().bg(Color::Blue).fg(Color::Black).bold()
+        Style::new()
+            .bg(theme.selection)
+            .fg(theme.selection_fg)
+            .bold()
     } else {
         base_style
     };
 
-    let spans = if item.marked {
-        vec![
-            Span::styled("+", Style::new().bg(Color::Yellow).fg(Color::Black)),
-            Span::from(item.entry.path_str()).style(highlight_style),
-        ]
+    let name = truncate_to_width(item.entry.path_str(), max_name_width);
+
+    let marker = if item.marked {
+        Span::styled("+", Style::new().bg(theme.marked).fg(theme.selection_fg))
     } else {
-        vec![
-            " ".into(),
-            Span::from(item.entry.path_str()).style(highlight_style),
-        ]
+        " ".into()
     };
+    let mut spans = vec![marker];
+    if let Some(git_status) = git_status {
+        spans.push(match git_status.status_of(item.entry.path()) {
+            Some(status) => Span::styled(
+                format!("{} ", status.indicator()),
+                Style::new().fg(get_git_status_color(status, theme)),
+            ),
+            None => Span::from("  "),
+        });
+    }
+    if let Some(dir_sizes) = dir_sizes {
+        let padding = " ".repeat(max_name_width.saturating_sub(name.width()) + 1);
+        spans.extend([
+            Span::from(name).style(highlight_style),
+            Span::from(padding),
+            get_size_span(&item.entry, dir_sizes, theme),
+        ]);
+    } else {
+        spans.push(Span::from(name).style(highlight_style));
+    }
     let line = Line::from(spans);
 
     ListItem::new(line)
 }
+
+// sizes of directories are only known once they've been computed; partial ones are muted
+fn get_size_span(entry: &Entry, dir_sizes: &DirSizeCache, theme: &Theme) -> Span<'static> {
+    let size = match entry.kind() {
+        EntryKind::File | EntryKind::Executable => Some((format_size(entry.size()), theme.text)),
+        EntryKind::Directory => dir_sizes.get(entry).map(|size| {
+            let color = if size.complete {
+                theme.text
+            } else {
+                theme.muted
+            };
+            (format_size(size.bytes), color)
+        }),
+        _ => None,
+    };
+
+    match size {
+        Some((size, color)) => Span::styled(format!("{size:>SIZE_WIDTH$}"), Style::new().fg(color)),
+        None => Span::from(" ".repeat(SIZE_WIDTH)),
+    }
+}
+
+fn get_git_status_color(status: GitFileStatus, theme: &Theme) -> Color {
+    match status {
+        GitFileStatus::Ignored => theme.muted,
+        GitFileStatus::Untracked => theme.info,
+        GitFileStatus::Staged => theme.menu,
+        GitFileStatus::Modified => theme.help,
+        GitFileStatus::Conflicted => theme.error,
+    }
+}
+
+// eg. "main ↑2 ↓1"
+fn get_branch_summary(git_status: &GitStatus) -> String {
+    let mut summary = git_status
+        .branch
+        .clone()
+        .unwrap_or_else(|| "(detached)".to_string());
+
+    if let Some((ahead, behind)) = git_status.ahead_behind {
+        if ahead > 0 {
+            summary.push_str(&format!(" ↑{ahead}"));
+        }
+        if behind > 0 {
+            summary.push_str(&format!(" ↓{behind}"));
+        }
+    }
+
+    summary
+}
+
+// shortens `value` to fit in `max_width` columns, accounting for wide and zero width characters,
+// and marks that it was shortened with an ellipsis
+fn truncate_to_width(value: String, max_width: usize) -> String {
+    if value.width() <= max_width {
+        return value;
+    }
+
+    // there's no room for even the ellipsis
+    if max_width == 0 {
+        return String::new();
+    }
+
+    let mut truncated = String::new();
+    let mut width = 0;
+    for c in value.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if width + char_width + 1 > max_width {
+            break;
+        }
+        width += char_width;
+        truncated.push(c);
+    }
+    truncated.push('…');
+
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn truncating_to_width_accounts_for_wide_characters() {
+        // GIVEN
+        let cases = [
+            ("atls", 10),
+            ("atls", 4),
+            ("atls", 3),
+            ("atls", 1),
+            ("atls", 0),
+            ("日本語のファイル", 16),
+            ("日本語のファイル", 7),
+            ("日本語のファイル", 6),
+            ("a日b", 3),
+        ];
+
+        // WHEN
+        let truncated = cases
+            .iter()
+            .map(|(value, width)| truncate_to_width(value.to_string(), *width))
+            .collect::<Vec<_>>();
+
+        // THEN
+        assert_eq!(
+            truncated,
+            [
+                "atls",
+                "atls",
+                "at…",
+                "…",
+                "",
+                "日本語のファイル",
+                "日本語…",
+                "日本…",
+                "a…",
+            ]
+        );
+        for (value, (_, width)) in truncated.iter().zip(cases) {
+            assert!(value.width() <= width);
+        }
+    }
+
+    #[test]
+    fn breadcrumbs_in_the_middle_are_hidden_till_the_rest_fit() {
+        // GIVEN
+        let widths = [1, 4, 4, 4, 3];
+        let max_widths = [16, 15, 10, 9, 0];
+
+        // WHEN
+        let hidden = max_widths
+            .iter()
+            .map(|max_width| get_hidden_breadcrumbs(&widths, *max_width))
+            .collect::<Vec<_>>();
+        let only_two = get_hidden_breadcrumbs(&[10, 10], 5);
+
+        // THEN
+        // two columns are taken up by the ellipsis and the separator before it
+        assert_eq!(hidden, [0..0, 1..2, 1..3, 1..4, 1..4]);
+        assert_eq!(only_two, 0..0);
+    }
+
+    #[test]
+    fn only_ancestors_that_are_shown_get_jump_keys() {
+        // GIVEN
+        let breadcrumbs = get_breadcrumbs(
+            Path::new("/home/user/projects/atls/src"),
+            Some(Path::new("/home/user")),
+        );
+
+        // WHEN
+        let (spans, jump_keys) = get_breadcrumb_spans(breadcrumbs, true, &Theme::default(), 15);
+
+        // THEN
+        let text = spans
+            .iter()
+            .map(|(span, _)| span.content.as_ref())
+            .collect::<String>();
+        assert_eq!(text, "b~/…/aatls/src");
+        assert_eq!(
+            jump_keys,
+            [
+                ('b', PathBuf::from("/home/user")),
+                ('a', PathBuf::from("/home/user/projects/atls")),
+            ]
+        );
+    }
+}